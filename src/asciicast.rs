@@ -0,0 +1,435 @@
+//! Recording and replaying terminal sessions in the asciicast v2 format.
+//!
+//! See <https://docs.asciinema.org/manual/asciicast/v2/>.
+
+use std::{
+	fmt::Write as _,
+	fs::File,
+	io::{BufRead, BufWriter, Error, ErrorKind, Result, Write},
+	os::fd::{AsFd, OwnedFd},
+	path::Path,
+	str,
+	sync::{
+		Arc, Mutex, MutexGuard,
+		atomic::{AtomicBool, Ordering},
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use nix::{
+	errno::Errno,
+	poll::{PollFd, PollFlags, PollTimeout, poll},
+	unistd::read,
+};
+
+/// Environment variables recorded in the header, as asciinema does.
+pub(crate) const HEADER_ENV: [&str; 2] = ["SHELL", "TERM"];
+
+/// How long the recorder waits for more output after the child has exited.
+const DRAIN_TIMEOUT: u8 = 50;
+
+/// What goes in the header of a recording.
+#[derive(Clone, Debug)]
+pub(crate) struct Header {
+	pub width: u16,
+	pub height: u16,
+	pub title: Option<String>,
+	pub env: Vec<(String, String)>,
+}
+
+/// A recording being written, shared between the output thread and the child wrapper.
+#[derive(Debug)]
+pub(crate) struct Recording {
+	writer: BufWriter<File>,
+	start: Instant,
+	record_input: bool,
+	// the end of the output so far, if it's an incomplete UTF-8 sequence
+	partial: Vec<u8>,
+	error: Option<Error>,
+}
+
+impl Recording {
+	/// Create the recording file and write its header.
+	pub fn create(path: &Path, header: &Header, record_input: bool) -> Result<Self> {
+		let mut line = format!(
+			r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}"#,
+			header.width,
+			header.height,
+			SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.unwrap_or_default()
+				.as_secs()
+		);
+		if let Some(title) = &header.title {
+			line.push_str(r#", "title": "#);
+			push_json_string(&mut line, title);
+		}
+		line.push_str(r#", "env": {"#);
+		for (n, (name, value)) in header.env.iter().enumerate() {
+			if n > 0 {
+				line.push_str(", ");
+			}
+			push_json_string(&mut line, name);
+			line.push_str(": ");
+			push_json_string(&mut line, value);
+		}
+		line.push_str("}}\n");
+
+		let mut writer = BufWriter::new(File::create(path)?);
+		writer.write_all(line.as_bytes())?;
+		Ok(Self {
+			writer,
+			start: Instant::now(),
+			record_input,
+			partial: Vec::new(),
+			error: None,
+		})
+	}
+
+	fn output(&mut self, data: &[u8]) {
+		self.partial.extend_from_slice(data);
+		let text = decode_utf8(&mut self.partial);
+		if !text.is_empty() {
+			self.event("o", &text);
+		}
+	}
+
+	fn input(&mut self, data: &[u8]) {
+		if self.record_input {
+			self.event("i", &String::from_utf8_lossy(data));
+		}
+	}
+
+	fn resize(&mut self, cols: u16, rows: u16) {
+		self.event("r", &format!("{cols}x{rows}"));
+	}
+
+	fn event(&mut self, code: &str, data: &str) {
+		if self.error.is_some() {
+			return;
+		}
+
+		let mut line = format!("[{:.6}, \"{code}\", ", self.start.elapsed().as_secs_f64());
+		push_json_string(&mut line, data);
+		line.push_str("]\n");
+		if let Err(err) = self.writer.write_all(line.as_bytes()) {
+			self.error = Some(err);
+		}
+	}
+
+	fn finish(&mut self) -> Result<()> {
+		if !self.partial.is_empty() {
+			let rest = String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned();
+			self.event("o", &rest);
+		}
+		if let Some(err) = self.error.take() {
+			return Err(err);
+		}
+		self.writer.flush()
+	}
+}
+
+/// Records the output of a pseudo-terminal on a background thread.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+	recording: Arc<Mutex<Recording>>,
+	stop: Arc<AtomicBool>,
+	thread: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+	/// Start recording the output read from the pseudo-terminal's master side.
+	pub fn start(recording: Recording, master: OwnedFd) -> Result<Self> {
+		let recording = Arc::new(Mutex::new(recording));
+		let stop = Arc::new(AtomicBool::new(false));
+		let thread = thread::Builder::new()
+			.name("process-wrap asciicast".into())
+			.spawn({
+				let recording = recording.clone();
+				let stop = stop.clone();
+				move || record_output(&master, &recording, &stop)
+			})?;
+
+		Ok(Self {
+			recording,
+			stop,
+			thread: Some(thread),
+		})
+	}
+
+	/// Record input sent to the child, if input is recorded.
+	pub fn input(&self, data: &[u8]) {
+		self.lock().input(data);
+	}
+
+	/// Record a change of the terminal size.
+	pub fn resize(&self, cols: u16, rows: u16) {
+		self.lock().resize(cols, rows);
+	}
+
+	/// Stop recording once the remaining output is read.
+	///
+	/// This is meant to be called after the child has exited. The returned [`Finisher`] does the
+	/// blocking part, so it can be moved to another thread. Finishing twice does nothing.
+	pub fn finisher(&mut self) -> Finisher {
+		self.stop.store(true, Ordering::SeqCst);
+		Finisher {
+			thread: self.thread.take(),
+			recording: self.recording.clone(),
+		}
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Recording> {
+		self.recording.lock().unwrap_or_else(|err| err.into_inner())
+	}
+}
+
+/// Waits for the last of the output to be recorded, and flushes the recording.
+#[derive(Debug)]
+pub(crate) struct Finisher {
+	thread: Option<JoinHandle<()>>,
+	recording: Arc<Mutex<Recording>>,
+}
+
+impl Finisher {
+	/// Block until there's been no output for a short while, or the pseudo-terminal is closed.
+	pub fn finish(self) -> Result<()> {
+		let Some(thread) = self.thread else {
+			return Ok(());
+		};
+
+		thread
+			.join()
+			.map_err(|_| Error::other("asciicast recorder thread panicked"))?;
+		self.recording
+			.lock()
+			.unwrap_or_else(|err| err.into_inner())
+			.finish()
+	}
+}
+
+impl Drop for Recorder {
+	fn drop(&mut self) {
+		// the thread finishes on its own, and the recording is flushed when it's dropped
+		self.stop.store(true, Ordering::SeqCst);
+	}
+}
+
+fn record_output(master: &OwnedFd, recording: &Mutex<Recording>, stop: &AtomicBool) {
+	let mut buf = [0; 8 * 1024];
+	loop {
+		let mut fds = [PollFd::new(master.as_fd(), PollFlags::POLLIN)];
+		match poll(&mut fds, PollTimeout::from(DRAIN_TIMEOUT)) {
+			Ok(0) if stop.load(Ordering::SeqCst) => return,
+			Ok(0) | Err(Errno::EINTR) => continue,
+			Ok(_) => {}
+			Err(_) => return,
+		}
+
+		match read(master, &mut buf) {
+			// EIO is what Linux returns once every slave descriptor is closed
+			Ok(0) | Err(Errno::EIO) => return,
+			Ok(n) => recording
+				.lock()
+				.unwrap_or_else(|err| err.into_inner())
+				.output(&buf[..n]),
+			Err(Errno::EINTR | Errno::EAGAIN) => {}
+			Err(_) => return,
+		}
+	}
+}
+
+/// Take the longest decodable prefix out of the buffer, replacing invalid sequences.
+///
+/// An incomplete sequence at the end is left in the buffer, to be completed by the next read.
+fn decode_utf8(buf: &mut Vec<u8>) -> String {
+	let mut text = String::new();
+	loop {
+		match str::from_utf8(buf) {
+			Ok(valid) => {
+				text.push_str(valid);
+				buf.clear();
+				return text;
+			}
+			Err(err) => {
+				let valid = err.valid_up_to();
+				text.push_str(str::from_utf8(&buf[..valid]).expect("checked valid"));
+				match err.error_len() {
+					None => {
+						buf.drain(..valid);
+						return text;
+					}
+					Some(invalid) => {
+						text.push(char::REPLACEMENT_CHARACTER);
+						buf.drain(..valid + invalid);
+					}
+				}
+			}
+		}
+	}
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if c < ' ' || c == '\u{7f}' => {
+				write!(out, "\\u{:04x}", c as u32).expect("writing to a String");
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+/// Replay an asciicast v2 recording, writing its output with the original timing.
+///
+/// The output events of the recording are written to `output` at the times they were recorded,
+/// divided by `speed`: use 1.0 to replay in real time, 2.0 to replay twice as fast, and
+/// [`f64::INFINITY`] to replay without waiting. Input and resize events are skipped.
+///
+/// This is useful to check the output of a recorded session in tests, or to show it to a user.
+/// Fails with [`ErrorKind::InvalidData`] if the recording isn't valid asciicast v2.
+///
+/// ```rust,no_run
+/// use process_wrap::replay_asciicast;
+/// use std::{fs::File, io::{BufReader, stdout}};
+///
+/// let recording = BufReader::new(File::open("session.cast")?);
+/// replay_asciicast(recording, stdout(), 1.0)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn replay_asciicast(recording: impl BufRead, mut output: impl Write, speed: f64) -> Result<()> {
+	let mut lines = recording.lines();
+	let header = lines.next().ok_or_else(|| invalid(1, "missing header"))??;
+	if header_version(&header) != Some(2) {
+		return Err(invalid(1, "not an asciicast v2 header"));
+	}
+
+	let start = Instant::now();
+	for (n, line) in lines.enumerate() {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		let (time, code, data) =
+			parse_event(&line).ok_or_else(|| invalid(n + 2, "malformed event"))?;
+		if code != "o" {
+			continue;
+		}
+
+		if let Ok(at) = Duration::try_from_secs_f64(time / speed) {
+			if let Some(wait) = at.checked_sub(start.elapsed()) {
+				thread::sleep(wait);
+			}
+		}
+		output.write_all(data.as_bytes())?;
+		output.flush()?;
+	}
+
+	Ok(())
+}
+
+fn invalid(line: usize, error: &str) -> Error {
+	Error::new(
+		ErrorKind::InvalidData,
+		format!("invalid asciicast on line {line}: {error}"),
+	)
+}
+
+/// Get the `version` field out of the header line.
+fn header_version(header: &str) -> Option<u64> {
+	let mut parser = JsonParser(header.trim());
+	parser.eat('{')?;
+	let (_, rest) = parser.0.split_once("\"version\"")?;
+	let mut parser = JsonParser(rest);
+	parser.eat(':')?;
+	parser.number().map(|version| version as u64)
+}
+
+/// Parse an event line: `[time, "code", "data"]`.
+fn parse_event(line: &str) -> Option<(f64, String, String)> {
+	let mut parser = JsonParser(line);
+	parser.eat('[')?;
+	let time = parser.number()?;
+	parser.eat(',')?;
+	let code = parser.string()?;
+	parser.eat(',')?;
+	let data = parser.string()?;
+	parser.eat(']')?;
+	parser.0.trim().is_empty().then_some((time, code, data))
+}
+
+/// Just enough of a JSON parser for asciicast events.
+struct JsonParser<'s>(&'s str);
+
+impl JsonParser<'_> {
+	fn eat(&mut self, c: char) -> Option<()> {
+		self.0 = self.0.trim_start().strip_prefix(c)?;
+		Some(())
+	}
+
+	fn number(&mut self) -> Option<f64> {
+		self.0 = self.0.trim_start();
+		let end = self
+			.0
+			.find(|c: char| !matches!(c, '0'..='9' | '.' | '-' | '+' | 'e' | 'E'))
+			.unwrap_or(self.0.len());
+		let number = self.0[..end].parse().ok()?;
+		self.0 = &self.0[end..];
+		Some(number)
+	}
+
+	fn string(&mut self) -> Option<String> {
+		self.eat('"')?;
+		let mut out = String::new();
+		let mut chars = self.0.char_indices();
+		while let Some((i, c)) = chars.next() {
+			match c {
+				'"' => {
+					self.0 = &self.0[i + 1..];
+					return Some(out);
+				}
+				'\\' => match chars.next()?.1 {
+					'"' => out.push('"'),
+					'\\' => out.push('\\'),
+					'/' => out.push('/'),
+					'b' => out.push('\u{8}'),
+					'f' => out.push('\u{c}'),
+					'n' => out.push('\n'),
+					'r' => out.push('\r'),
+					't' => out.push('\t'),
+					'u' => {
+						let high = hex4(&mut chars)?;
+						let code = if (0xd800..0xdc00).contains(&high) {
+							// a surrogate pair, for characters outside the basic plane
+							if chars.next()?.1 != '\\' || chars.next()?.1 != 'u' {
+								return None;
+							}
+							let low = hex4(&mut chars)?;
+							0x10000 + ((high - 0xd800) << 10) + (low.checked_sub(0xdc00)?)
+						} else {
+							high
+						};
+						out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+					}
+					_ => return None,
+				},
+				c => out.push(c),
+			}
+		}
+		None
+	}
+}
+
+fn hex4(chars: &mut str::CharIndices<'_>) -> Option<u32> {
+	let digits: String = chars.take(4).map(|(_, c)| c).collect();
+	u32::from_str_radix(&digits, 16).ok()
+}
@@ -0,0 +1,83 @@
+use std::any::{TypeId, type_name};
+
+/// A constraint on how a wrapper can be combined with another wrapper type.
+///
+/// Wrappers declare these from `CommandWrapper::constraints()`, and they're checked by
+/// `CommandWrap::spawn()` before any `pre_spawn` hook runs. A conflict makes the spawn fail. An
+/// ordering constraint that isn't met also makes the spawn fail, unless it's been marked as
+/// [`reorderable()`](Self::reorderable), in which case the declaring wrapper is moved to meet it.
+///
+/// Constraints naming a wrapper type that isn't present are ignored.
+///
+/// ```rust
+/// use process_wrap::WrapperConstraint;
+/// # #[derive(Debug)] struct Other;
+///
+/// // in `fn constraints(&self)`:
+/// vec![WrapperConstraint::before::<Other>().reorderable()]
+/// # ;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrapperConstraint {
+	pub(crate) kind: ConstraintKind,
+	pub(crate) other: TypeId,
+	pub(crate) other_name: &'static str,
+	pub(crate) reorder: bool,
+}
+
+/// What a [`WrapperConstraint`] requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintKind {
+	/// The wrappers can't be used together.
+	ConflictsWith,
+
+	/// The declaring wrapper must be added before the other one.
+	Before,
+
+	/// The declaring wrapper must be added after the other one.
+	After,
+}
+
+impl WrapperConstraint {
+	fn new<W: 'static>(kind: ConstraintKind) -> Self {
+		Self {
+			kind,
+			other: TypeId::of::<W>(),
+			other_name: type_name::<W>(),
+			reorder: false,
+		}
+	}
+
+	/// The wrapper can't be used together with `W`.
+	pub fn conflicts_with<W: 'static>() -> Self {
+		Self::new::<W>(ConstraintKind::ConflictsWith)
+	}
+
+	/// The wrapper must be added before `W`, if both are used.
+	pub fn before<W: 'static>() -> Self {
+		Self::new::<W>(ConstraintKind::Before)
+	}
+
+	/// The wrapper must be added after `W`, if both are used.
+	pub fn after<W: 'static>() -> Self {
+		Self::new::<W>(ConstraintKind::After)
+	}
+
+	/// Declare that it's safe to move the wrapper to meet this ordering constraint.
+	///
+	/// This has no effect on conflicts.
+	pub fn reorderable(mut self) -> Self {
+		self.reorder = true;
+		self
+	}
+
+	/// What this constraint requires.
+	pub fn kind(&self) -> ConstraintKind {
+		self.kind
+	}
+
+	/// The name of the other wrapper type.
+	pub fn other_name(&self) -> &'static str {
+		self.other_name
+	}
+}
@@ -0,0 +1,121 @@
+use std::{
+	ffi::{OsStr, OsString},
+	fmt,
+	path::PathBuf,
+};
+
+/// What a `CommandWrap` will spawn: the command and the wrappers applied to it.
+///
+/// This is obtained from `CommandWrap::describe()`, and is meant for inspecting and logging how a
+/// command is set up. It's printable with `Display` in a human-readable form.
+///
+/// Only the changes made to the environment are listed, not the inherited environment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandPlan {
+	/// The program to run.
+	pub program: OsString,
+
+	/// The arguments, not including the program.
+	pub args: Vec<OsString>,
+
+	/// The environment variables set (`Some`) or removed (`None`) for the child.
+	pub envs: Vec<(OsString, Option<OsString>)>,
+
+	/// The working directory, if it's been set.
+	pub current_dir: Option<PathBuf>,
+
+	/// The wrappers, in the order they're applied.
+	pub wrappers: Vec<WrapperPlan>,
+}
+
+/// A wrapper in a [`CommandPlan`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrapperPlan {
+	/// The wrapper's name, from `CommandWrapper::name()`.
+	pub name: &'static str,
+
+	/// The wrapper's configuration, from its `Debug` representation.
+	pub config: String,
+}
+
+impl CommandPlan {
+	pub(crate) fn new(command: &std::process::Command, wrappers: Vec<WrapperPlan>) -> Self {
+		Self {
+			program: command.get_program().to_owned(),
+			args: command.get_args().map(OsStr::to_owned).collect(),
+			envs: command
+				.get_envs()
+				.map(|(key, value)| (key.to_owned(), value.map(OsStr::to_owned)))
+				.collect(),
+			current_dir: command.get_current_dir().map(ToOwned::to_owned),
+			wrappers,
+		}
+	}
+}
+
+impl fmt::Display for CommandPlan {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:?}", self.program)?;
+		for arg in &self.args {
+			write!(f, " {arg:?}")?;
+		}
+
+		if let Some(dir) = &self.current_dir {
+			write!(f, "\n  cwd: {dir:?}")?;
+		}
+		for (key, value) in &self.envs {
+			match value {
+				Some(value) => write!(f, "\n  env: {}={value:?}", key.to_string_lossy())?,
+				None => write!(f, "\n  env: unset {}", key.to_string_lossy())?,
+			}
+		}
+		for (n, wrapper) in self.wrappers.iter().enumerate() {
+			write!(
+				f,
+				"\n  wrapper {}: {}: {}",
+				n + 1,
+				wrapper.name,
+				wrapper.config
+			)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// The layers of a child wrapper chain, from the outermost wrapper to the bottom of the chain.
+///
+/// This is obtained from `ChildWrapper::layers()`. It's printable with `Display`, as the layers'
+/// names separated by `>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChildLayers(pub Vec<&'static str>);
+
+impl fmt::Display for ChildLayers {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (n, layer) in self.0.iter().enumerate() {
+			if n > 0 {
+				f.write_str(" > ")?;
+			}
+			f.write_str(layer)?;
+		}
+		Ok(())
+	}
+}
+
+/// Access to the std `Command` underneath a frontend's command type.
+pub(crate) trait AsStdCommand {
+	fn as_std_command(&self) -> &std::process::Command;
+}
+
+impl AsStdCommand for std::process::Command {
+	fn as_std_command(&self) -> &std::process::Command {
+		self
+	}
+}
+
+#[cfg(feature = "tokio1")]
+impl AsStdCommand for tokio::process::Command {
+	fn as_std_command(&self) -> &std::process::Command {
+		self.as_std()
+	}
+}
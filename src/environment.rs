@@ -0,0 +1,232 @@
+//! Policy resolution for the `Environment` wrappers.
+
+use std::{
+	collections::BTreeMap,
+	env,
+	ffi::{OsStr, OsString},
+	fs,
+	io::{Error, ErrorKind, Result},
+	path::PathBuf,
+	process::Command,
+};
+
+/// The environment of a child process, by variable name.
+pub(crate) type EnvMap = BTreeMap<OsString, OsString>;
+
+/// An environment policy, built up by the `Environment` wrappers.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EnvPolicy {
+	pub clear: bool,
+	pub allow: Vec<String>,
+	pub files: Vec<PathBuf>,
+	pub pinned: Vec<(OsString, OsString)>,
+}
+
+impl EnvPolicy {
+	/// Merge another policy into this one, with the other's settings taking precedence.
+	pub fn merge(&mut self, other: Self) {
+		self.clear |= other.clear;
+		self.allow.extend(other.allow);
+		self.files.extend(other.files);
+		self.pinned.extend(other.pinned);
+	}
+
+	/// Work out the environment the command will run with.
+	///
+	/// From lowest to highest precedence, this is: the parent's environment (or only the allowed
+	/// parts of it if clearing), the env files in order, the variables set on the command, and the
+	/// pinned variables.
+	pub fn resolve(&self, command: &CommandEnv) -> Result<EnvMap> {
+		let mut vars: EnvMap = env::vars_os()
+			.filter(|(key, _)| !self.clear || self.allows(key))
+			.collect();
+
+		for file in &self.files {
+			let contents = fs::read_to_string(file).map_err(|err| {
+				Error::new(
+					err.kind(),
+					format!("reading env file {}: {err}", file.display()),
+				)
+			})?;
+			vars.extend(parse_dotenv(&contents).map_err(|err| {
+				Error::new(err.kind(), format!("in env file {}: {err}", file.display()))
+			})?);
+		}
+
+		for (key, value) in &command.vars {
+			match value {
+				Some(value) => vars.insert(key.clone(), value.clone()),
+				None => vars.remove(key),
+			};
+		}
+
+		vars.extend(self.pinned.iter().cloned());
+		Ok(vars)
+	}
+
+	fn allows(&self, key: &OsString) -> bool {
+		key.to_str()
+			.is_some_and(|key| self.allow.iter().any(|pattern| glob_match(pattern, key)))
+	}
+}
+
+/// The variables set on a `Command` itself, with `env()` and `env_remove()`.
+///
+/// This is kept while the policy is applied for a spawn, so it can be put back afterwards.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CommandEnv {
+	pub vars: Vec<(OsString, Option<OsString>)>,
+}
+
+impl CommandEnv {
+	/// The variables set on the command, except those which were only put back by the wrapper.
+	pub fn of(command: &Command, restored: &RestoredEnv) -> Self {
+		Self {
+			vars: command
+				.get_envs()
+				.filter(|&(key, value)| restored.get(key).map(Option::as_deref) != Some(value))
+				.map(|(key, value)| (key.to_owned(), value.map(ToOwned::to_owned)))
+				.collect(),
+		}
+	}
+
+	/// The value set on the command for a variable, or `None` if it's not set there.
+	pub fn get(&self, key: &OsStr) -> Option<Option<&OsStr>> {
+		self.vars
+			.iter()
+			.find(|(name, _)| name == key)
+			.map(|(_, value)| value.as_deref())
+	}
+}
+
+/// Variables which the wrapper set on a `Command` when undoing its changes, and which weren't set
+/// there before, with the values it set them to.
+///
+/// A `Command` can only be made to stop setting a variable by clearing it entirely, so these are
+/// set to the parent's value instead (or removed, if the parent doesn't have it), and are then told
+/// apart from the variables set by the user.
+pub(crate) type RestoredEnv = BTreeMap<OsString, Option<OsString>>;
+
+/// The changes to make to a `Command` for its child to get `vars` as its environment.
+///
+/// Only the differences from the parent's environment are needed, as the rest is inherited.
+pub(crate) fn changes_from_parent(vars: &EnvMap) -> Vec<(OsString, Option<OsString>)> {
+	let parent: EnvMap = env::vars_os().collect();
+	let removed = parent
+		.keys()
+		.filter(|key| !vars.contains_key(*key))
+		.map(|key| (key.clone(), None));
+	let set = vars
+		.iter()
+		.filter(|&(key, value)| parent.get(key) != Some(value))
+		.map(|(key, value)| (key.clone(), Some(value.clone())));
+	removed.chain(set).collect()
+}
+
+/// Match a variable name against a pattern, where `*` matches any run of characters and `?` any
+/// single character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let name: Vec<char> = name.chars().collect();
+
+	// on a mismatch, backtrack to the last `*` and have it match one more character
+	let (mut p, mut n) = (0, 0);
+	let mut star = None;
+	while n < name.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				star = Some((p, n));
+				p += 1;
+			}
+			Some(&c) if c == '?' || c == name[n] => {
+				p += 1;
+				n += 1;
+			}
+			_ => match star {
+				Some((star_p, star_n)) => {
+					star = Some((star_p, star_n + 1));
+					p = star_p + 1;
+					n = star_n + 1;
+				}
+				None => return false,
+			},
+		}
+	}
+
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parse the contents of a `.env` file.
+///
+/// Lines are `KEY=value`, optionally prefixed with `export`. Blank lines and lines starting with
+/// `#` are ignored. Values can be single-quoted (taken literally), double-quoted (with `\n`, `\t`,
+/// `\"`, and `\\` escapes), or bare (trimmed, with a trailing ` #` comment removed). Variables
+/// aren't interpolated.
+fn parse_dotenv(contents: &str) -> Result<Vec<(OsString, OsString)>> {
+	let invalid = |line: usize, message: &str| {
+		Error::new(ErrorKind::InvalidData, format!("line {line}: {message}"))
+	};
+
+	let mut vars = Vec::new();
+	for (n, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let line = line.strip_prefix("export ").unwrap_or(line);
+		let Some((key, value)) = line.split_once('=') else {
+			return Err(invalid(n + 1, "expected KEY=value"));
+		};
+		let key = key.trim();
+		if key.is_empty() || key.contains(char::is_whitespace) {
+			return Err(invalid(n + 1, "invalid variable name"));
+		}
+
+		let value = value.trim();
+		let value = if let Some(quoted) = value.strip_prefix('\'') {
+			quoted
+				.strip_suffix('\'')
+				.ok_or_else(|| invalid(n + 1, "unterminated single quote"))?
+				.to_owned()
+		} else if let Some(quoted) = value.strip_prefix('"') {
+			unescape(
+				quoted
+					.strip_suffix('"')
+					.ok_or_else(|| invalid(n + 1, "unterminated double quote"))?,
+			)
+		} else {
+			match value.find(" #") {
+				Some(comment) => value[..comment].trim_end().to_owned(),
+				None => value.to_owned(),
+			}
+		};
+
+		vars.push((key.into(), value.into()));
+	}
+
+	Ok(vars)
+}
+
+fn unescape(value: &str) -> String {
+	let mut unescaped = String::with_capacity(value.len());
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			unescaped.push(c);
+			continue;
+		}
+
+		match chars.next() {
+			Some('n') => unescaped.push('\n'),
+			Some('t') => unescaped.push('\t'),
+			Some(c @ ('"' | '\\')) => unescaped.push(c),
+			Some(c) => {
+				unescaped.push('\\');
+				unescaped.push(c);
+			}
+			None => unescaped.push('\\'),
+		}
+	}
+	unescaped
+}
@@ -0,0 +1,139 @@
+//! Output matching and transcripts for the `Expect` wrappers.
+
+use std::{
+	fmt,
+	io::{Error, ErrorKind, Result},
+	time::Duration,
+};
+
+use regex::bytes::Regex;
+
+/// How much of the unmatched output to include in errors.
+const RECENT_OUTPUT: usize = 1024;
+
+/// One side of an exchange with a child under `Expect`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Exchange {
+	/// Input sent to the child's stdin.
+	Sent(Vec<u8>),
+
+	/// Output received from the child.
+	///
+	/// Output received in several reads is gathered into one entry until the next input is sent.
+	Received(Vec<u8>),
+}
+
+impl fmt::Display for Exchange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (prefix, data) = match self {
+			Self::Sent(data) => ("> ", data),
+			Self::Received(data) => ("< ", data),
+		};
+		for line in String::from_utf8_lossy(data).lines() {
+			writeln!(f, "{prefix}{line}")?;
+		}
+		Ok(())
+	}
+}
+
+/// What an expectation matched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectMatch {
+	/// The output received before the match, since the previous match.
+	pub before: String,
+
+	/// The output which matched the pattern.
+	pub matched: String,
+}
+
+/// The output received and not yet matched, and the transcript so far.
+#[derive(Debug, Default)]
+pub(crate) struct Expecter {
+	unmatched: Vec<u8>,
+	transcript: Vec<Exchange>,
+	eof: bool,
+}
+
+impl Expecter {
+	pub fn compile(pattern: &str) -> Result<Regex> {
+		Regex::new(pattern).map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+	}
+
+	/// Record output from the child.
+	pub fn received(&mut self, data: &[u8]) {
+		self.unmatched.extend_from_slice(data);
+		if let Some(Exchange::Received(last)) = self.transcript.last_mut() {
+			last.extend_from_slice(data);
+		} else {
+			self.transcript.push(Exchange::Received(data.to_vec()));
+		}
+	}
+
+	/// Record input to the child.
+	pub fn sent(&mut self, data: &[u8]) {
+		self.transcript.push(Exchange::Sent(data.to_vec()));
+	}
+
+	/// Record that the child's output was closed.
+	pub fn closed(&mut self) {
+		self.eof = true;
+	}
+
+	pub fn is_closed(&self) -> bool {
+		self.eof
+	}
+
+	pub fn transcript(&self) -> &[Exchange] {
+		&self.transcript
+	}
+
+	/// Look for the pattern in the unmatched output, consuming it up to the end of the match.
+	pub fn find(&mut self, regex: &Regex) -> Option<ExpectMatch> {
+		let found = regex.find(&self.unmatched)?;
+		let (start, end) = (found.start(), found.end());
+		let consumed: Vec<u8> = self.unmatched.drain(..end).collect();
+		Some(ExpectMatch {
+			before: String::from_utf8_lossy(&consumed[..start]).into_owned(),
+			matched: String::from_utf8_lossy(&consumed[start..]).into_owned(),
+		})
+	}
+
+	/// Take all of the unmatched output.
+	pub fn take_unmatched(&mut self) -> String {
+		String::from_utf8_lossy(&std::mem::take(&mut self.unmatched)).into_owned()
+	}
+
+	/// The error for an expectation which didn't match in time.
+	pub fn timed_out(&self, expecting: &str, timeout: Duration) -> Error {
+		Error::new(
+			ErrorKind::TimedOut,
+			format!(
+				"timed out after {timeout:?} waiting for {expecting}; {}",
+				self.recent_output()
+			),
+		)
+	}
+
+	/// The error for an expectation which can no longer match as the output was closed.
+	pub fn ended(&self, expecting: &str) -> Error {
+		Error::new(
+			ErrorKind::UnexpectedEof,
+			format!(
+				"output ended while waiting for {expecting}; {}",
+				self.recent_output()
+			),
+		)
+	}
+
+	fn recent_output(&self) -> String {
+		if self.unmatched.is_empty() {
+			return "no unmatched output".into();
+		}
+
+		let start = self.unmatched.len().saturating_sub(RECENT_OUTPUT);
+		format!(
+			"recent output: {:?}",
+			String::from_utf8_lossy(&self.unmatched[start..])
+		)
+	}
+}
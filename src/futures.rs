@@ -0,0 +1,47 @@
+//! Executor-agnostic async process-wrap API.
+//!
+//! See the [crate-level doc](crate) for more information.
+//!
+//! This frontend spawns std `Command`s, but its [`Child`] can be waited on and its standard I/O
+//! used asynchronously from any executor (smol, async-std, `futures::executor`, Tokio...). Pipes
+//! implement the [`futures::io`](https://docs.rs/futures/0.3/futures/io/) traits. Readiness is
+//! provided by a small reactor thread internal to this crate, which is started on first use.
+//!
+//! Process exit is detected with a pidfd where supported (Linux 5.3+), and otherwise by installing
+//! a `SIGCHLD` handler which chains to any handler that was previously installed.
+//!
+//! This frontend is only available on Unix.
+//!
+//! The recommended usage is to star-import this module:
+//!
+//! ```rust
+//! use process_wrap::futures::*;
+//! ```
+
+#[doc(inline)]
+pub use crate::{
+	ChildLayers, CommandPlan, ExitOutcome, Framing, OutputEvent, WrapperConstraint, WrapperPlan,
+};
+#[doc(inline)]
+pub use child::{Child, ChildStderr, ChildStdin, ChildStdout};
+#[doc(inline)]
+pub use core::{ChildWrapper, CommandWrap, CommandWrapper};
+#[cfg(feature = "process-group")]
+#[doc(inline)]
+pub use process_group::{ProcessGroup, ProcessGroupChild};
+#[cfg(feature = "process-session")]
+#[doc(inline)]
+pub use process_session::ProcessSession;
+#[cfg(feature = "reset-sigmask")]
+#[doc(inline)]
+pub use reset_sigmask::ResetSigmask;
+
+mod child;
+mod core;
+#[cfg(feature = "process-group")]
+mod process_group;
+#[cfg(feature = "process-session")]
+mod process_session;
+mod reactor;
+#[cfg(feature = "reset-sigmask")]
+mod reset_sigmask;
@@ -0,0 +1,187 @@
+use std::{
+	future::poll_fn,
+	io::{Error, ErrorKind, Read, Result, Write},
+	os::fd::{AsFd, AsRawFd},
+	pin::Pin,
+	process::{self, ExitStatus},
+	task::{Context, Poll},
+};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+use super::reactor::{ExitWatch, Interest, reactor, set_nonblocking};
+
+/// A child process, with asynchronous waiting and I/O.
+///
+/// This is the equivalent of Tokio's `Child` for the futures frontend: it wraps a
+/// [`std::process::Child`], and implements waiting and the standard I/O pipes without depending on
+/// a particular executor.
+#[derive(Debug)]
+pub struct Child {
+	inner: process::Child,
+	exit: ExitWatch,
+
+	/// The handle for writing to the child's standard input, if it was piped.
+	pub stdin: Option<ChildStdin>,
+
+	/// The handle for reading from the child's standard output, if it was piped.
+	pub stdout: Option<ChildStdout>,
+
+	/// The handle for reading from the child's standard error, if it was piped.
+	pub stderr: Option<ChildStderr>,
+}
+
+impl Child {
+	pub(crate) fn new(mut inner: process::Child) -> Self {
+		Self {
+			exit: ExitWatch::new(inner.id()),
+			stdin: inner.stdin.take().map(|io| ChildStdin(Pipe::new(io))),
+			stdout: inner.stdout.take().map(|io| ChildStdout(Pipe::new(io))),
+			stderr: inner.stderr.take().map(|io| ChildStderr(Pipe::new(io))),
+			inner,
+		}
+	}
+
+	/// Obtain the underlying std `Child`.
+	///
+	/// Its standard I/O handles have been moved to this struct, so they are always `None`.
+	pub fn as_std(&self) -> &process::Child {
+		&self.inner
+	}
+
+	/// Obtain the process ID of the child.
+	pub fn id(&self) -> u32 {
+		self.inner.id()
+	}
+
+	/// Send a `SIGKILL` to the child, without waiting for it to exit.
+	pub fn start_kill(&mut self) -> Result<()> {
+		self.inner.kill()
+	}
+
+	/// Check if the child has exited without waiting, and if it has, return its exit status.
+	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		self.inner.try_wait()
+	}
+
+	/// Wait for the child to exit and return its exit status.
+	pub async fn wait(&mut self) -> Result<ExitStatus> {
+		poll_fn(|cx| self.poll_wait(cx)).await
+	}
+
+	/// Poll for the child to exit.
+	///
+	/// This is the building block of [`wait()`](Self::wait), for use in manual `Future` impls.
+	pub fn poll_wait(&mut self, cx: &mut Context<'_>) -> Poll<Result<ExitStatus>> {
+		if let Some(status) = self.inner.try_wait()? {
+			return Poll::Ready(Ok(status));
+		}
+
+		self.exit.register(cx.waker())?;
+
+		// the child may have exited before we started watching
+		match self.inner.try_wait()? {
+			Some(status) => Poll::Ready(Ok(status)),
+			None => Poll::Pending,
+		}
+	}
+}
+
+/// A pipe end driven by the reactor.
+#[derive(Debug)]
+struct Pipe<T> {
+	io: Option<T>,
+	nonblocking: bool,
+}
+
+impl<T: AsFd> Pipe<T> {
+	fn new(io: T) -> Self {
+		Self {
+			io: Some(io),
+			nonblocking: false,
+		}
+	}
+
+	fn poll_io<R>(
+		&mut self,
+		cx: &mut Context<'_>,
+		interest: Interest,
+		mut op: impl FnMut(&mut T) -> Result<R>,
+	) -> Poll<Result<R>> {
+		let Some(io) = self.io.as_mut() else {
+			return Poll::Ready(Err(Error::new(ErrorKind::BrokenPipe, "pipe was closed")));
+		};
+
+		if !self.nonblocking {
+			set_nonblocking(io.as_fd())?;
+			self.nonblocking = true;
+		}
+
+		loop {
+			match op(io) {
+				Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+				Err(err) if err.kind() == ErrorKind::WouldBlock => {
+					reactor()?.register(io.as_fd().as_raw_fd(), interest, cx.waker());
+					return Poll::Pending;
+				}
+				res => return Poll::Ready(res),
+			}
+		}
+	}
+}
+
+/// The handle for writing to a child's standard input.
+///
+/// Closing it (or dropping it) closes the pipe, which signals end of input to the child.
+#[derive(Debug)]
+pub struct ChildStdin(Pipe<process::ChildStdin>);
+
+/// The handle for reading from a child's standard output.
+#[derive(Debug)]
+pub struct ChildStdout(Pipe<process::ChildStdout>);
+
+/// The handle for reading from a child's standard error.
+#[derive(Debug)]
+pub struct ChildStderr(Pipe<process::ChildStderr>);
+
+impl AsyncWrite for ChildStdin {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+		self.get_mut()
+			.0
+			.poll_io(cx, Interest::Write, |io| io.write(buf))
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+		// pipes aren't buffered on our side
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+		self.get_mut().0.io = None;
+		Poll::Ready(Ok(()))
+	}
+}
+
+impl AsyncRead for ChildStdout {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut [u8],
+	) -> Poll<Result<usize>> {
+		self.get_mut()
+			.0
+			.poll_io(cx, Interest::Read, |io| io.read(buf))
+	}
+}
+
+impl AsyncRead for ChildStderr {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut [u8],
+	) -> Poll<Result<usize>> {
+		self.get_mut()
+			.0
+			.poll_io(cx, Interest::Read, |io| io.read(buf))
+	}
+}
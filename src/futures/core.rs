@@ -0,0 +1,537 @@
+use std::{
+	any::{Any, TypeId},
+	future::Future,
+	io::{Error, ErrorKind, Result},
+	pin::Pin,
+	process::{Command, ExitStatus, Output},
+};
+
+use futures::{
+	future::{try_join3, try_join4},
+	io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+	stream::{self, Stream, StreamExt},
+};
+use nix::{
+	sys::signal::{Signal, kill},
+	unistd::Pid,
+};
+
+use crate::{
+	ChildLayers, ExitOutcome, Framing, OutputEvent,
+	output_events::{CHUNK_SIZE, Framer},
+};
+
+use super::{Child, ChildStderr, ChildStdin, ChildStdout};
+
+crate::generic_wrap::Wrap!(Command, std::process::Child, ChildWrapper, Child::new);
+
+/// Wrapper for the futures frontend's [`Child`].
+///
+/// This trait exposes most of the functionality of the underlying [`Child`]. It is implemented for
+/// [`Child`] and by wrappers.
+///
+/// The required methods are `inner`, `inner_mut`, and `into_inner`. That provides access to the
+/// underlying `Child` and allows the wrapper to be dropped and the `Child` to be used directly if
+/// necessary.
+///
+/// It also makes it possible for all the other methods to have default implementations. Some are
+/// direct passthroughs to the underlying `Child`, while others are more complex.
+///
+/// Here's a simple example of a wrapper:
+///
+/// ```rust
+/// use process_wrap::futures::*;
+///
+/// #[derive(Debug)]
+/// pub struct YourChildWrapper(Child);
+///
+/// impl ChildWrapper for YourChildWrapper {
+///     fn inner(&self) -> &dyn ChildWrapper {
+///         &self.0
+///     }
+///
+///     fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+///         &mut self.0
+///     }
+///
+///     fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+///         Box::new((*self).0)
+///     }
+/// }
+/// ```
+pub trait ChildWrapper: Any + std::fmt::Debug + Send + Sync {
+	/// Obtain a reference to the wrapped child.
+	fn inner(&self) -> &dyn ChildWrapper;
+
+	/// Obtain a mutable reference to the wrapped child.
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper;
+
+	/// Consume the current wrapper and return the wrapped child.
+	///
+	/// Note that this may disrupt whatever the current wrapper was doing. However, wrappers must
+	/// ensure that the wrapped child is in a consistent state when this is called or they are
+	/// dropped, so that this is always safe.
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper>;
+
+	/// Obtain a clone if possible.
+	///
+	/// Some implementations may make it possible to clone the implementing structure, even though
+	/// the `Child` isn't `Clone`. In those cases, this method should be overridden.
+	fn try_clone(&self) -> Option<Box<dyn ChildWrapper>> {
+		None
+	}
+
+	/// The name of the wrapper, for `layers()` and logs.
+	///
+	/// By default this is the wrapper's type name.
+	fn name(&self) -> &'static str {
+		std::any::type_name::<Self>()
+	}
+
+	/// Obtain the `Child`'s stdin.
+	///
+	/// By default this is a passthrough to the wrapped child.
+	fn stdin(&mut self) -> &mut Option<ChildStdin> {
+		self.inner_mut().stdin()
+	}
+
+	/// Obtain the `Child`'s stdout.
+	///
+	/// By default this is a passthrough to the wrapped child.
+	fn stdout(&mut self) -> &mut Option<ChildStdout> {
+		self.inner_mut().stdout()
+	}
+
+	/// Obtain the `Child`'s stderr.
+	///
+	/// By default this is a passthrough to the wrapped child.
+	fn stderr(&mut self) -> &mut Option<ChildStderr> {
+		self.inner_mut().stderr()
+	}
+
+	/// Obtain the `Child`'s process ID.
+	///
+	/// In general this should be the PID of the top-level spawned process that was spawned
+	/// However, that may vary depending on what a wrapper does.
+	fn id(&self) -> u32 {
+		self.inner().id()
+	}
+
+	/// Kill the `Child` and wait for it to exit.
+	///
+	/// By default this calls `start_kill()` and then `wait()`, which is the same way it is done on
+	/// the underlying `Child`, but that way implementing either or both of those methods will use
+	/// them when calling `kill()`, instead of requiring a stub implementation.
+	fn kill(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+		Box::pin(async {
+			self.start_kill()?;
+			self.wait().await?;
+			Ok(())
+		})
+	}
+
+	/// Kill the `Child` without waiting for it to exit.
+	///
+	/// By default this is a passthrough to the underlying `Child`, which sends a `SIGKILL` signal
+	/// to the process.
+	fn start_kill(&mut self) -> Result<()> {
+		self.inner_mut().start_kill()
+	}
+
+	/// Check if the `Child` has exited without waiting, and if it has, return its exit status.
+	///
+	/// Wrappers must ensure that repeatedly calling this (or other wait methods) after the child
+	/// has exited will always return the same result.
+	///
+	/// By default this is a passthrough to the underlying `Child`.
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		self.inner_mut().try_wait()
+	}
+
+	/// Wait for the `Child` to exit and return its exit status.
+	///
+	/// Wrappers must ensure that repeatedly calling this (or other wait methods) after the child
+	/// has exited will always return the same result.
+	///
+	/// By default this is a passthrough to the underlying `Child`.
+	fn wait(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitStatus>> + Send + '_>> {
+		Box::pin(self.inner_mut().wait())
+	}
+
+	/// Wait for the `Child` to exit and return how it ended.
+	///
+	/// This is like [`wait()`](ChildWrapper::wait), but returns an [`ExitOutcome`], which tells
+	/// apart exiting with a code, being terminated by a signal, and being killed by a wrapper.
+	///
+	/// By default this calls `wait()` and then [`kill_reason()`](ChildWrapper::kill_reason).
+	fn wait_outcome(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitOutcome>> + Send + '_>> {
+		Box::pin(async {
+			let status = self.wait().await?;
+			Ok(ExitOutcome::new(status, self.kill_reason()))
+		})
+	}
+
+	/// Why a wrapper killed the `Child`, if one did.
+	///
+	/// Wrappers which kill the child should override this to say so, and pass through to the
+	/// wrapped child otherwise. This is used by [`wait_outcome()`](ChildWrapper::wait_outcome).
+	///
+	/// By default this is a passthrough to the wrapped child.
+	fn kill_reason(&self) -> Option<String> {
+		self.inner().kill_reason()
+	}
+
+	/// Wait for the `Child` to exit and return its exit status and outputs.
+	///
+	/// Note that this method reads the child's stdout and stderr to completion into memory.
+	///
+	/// By default this closes stdin, then reads stdout and stderr concurrently with the wrapper's
+	/// `wait()` method.
+	fn wait_with_output(mut self: Box<Self>) -> Pin<Box<dyn Future<Output = Result<Output>> + Send>>
+	where
+		Self: 'static,
+	{
+		Box::pin(async move {
+			async fn read_to_end<A: AsyncRead + Unpin>(io: &mut Option<A>) -> Result<Vec<u8>> {
+				let mut vec = Vec::new();
+				if let Some(io) = io.as_mut() {
+					io.read_to_end(&mut vec).await?;
+				}
+				Ok(vec)
+			}
+
+			drop(self.stdin().take());
+			let mut stdout_pipe = self.stdout().take();
+			let mut stderr_pipe = self.stderr().take();
+
+			let stdout_fut = read_to_end(&mut stdout_pipe);
+			let stderr_fut = read_to_end(&mut stderr_pipe);
+
+			let (status, stdout, stderr) = try_join3(self.wait(), stdout_fut, stderr_fut).await?;
+
+			Ok(Output {
+				status,
+				stdout,
+				stderr,
+			})
+		})
+	}
+
+	/// Write input to the `Child`'s stdin, then wait for it to exit and return its exit status and
+	/// outputs.
+	///
+	/// This is like [`wait_with_output()`](ChildWrapper::wait_with_output), but writes `input` to
+	/// stdin concurrently with reading from stdout and stderr, so that a child which writes output
+	/// before it has read all of its input can't deadlock on full pipe buffers. Stdin is closed once
+	/// all of the input is written, or as soon as the child closes it.
+	///
+	/// Returns an error without waiting if there's input but stdin isn't piped.
+	fn wait_with_input_output(
+		mut self: Box<Self>,
+		input: Vec<u8>,
+	) -> Pin<Box<dyn Future<Output = Result<Output>> + Send>>
+	where
+		Self: 'static,
+	{
+		Box::pin(async move {
+			async fn read_to_end<A: AsyncRead + Unpin>(io: &mut Option<A>) -> Result<Vec<u8>> {
+				let mut vec = Vec::new();
+				if let Some(io) = io.as_mut() {
+					io.read_to_end(&mut vec).await?;
+				}
+				Ok(vec)
+			}
+
+			async fn write_all(io: Option<ChildStdin>, input: &[u8]) -> Result<()> {
+				let Some(mut io) = io else {
+					return Ok(());
+				};
+
+				// the child won't read any more, which isn't our problem
+				match io.write_all(input).await {
+					Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+					res => res,
+				}
+			}
+
+			let stdin_pipe = self.stdin().take();
+			if stdin_pipe.is_none() && !input.is_empty() {
+				return Err(Error::new(ErrorKind::InvalidInput, "stdin isn't piped"));
+			}
+			let mut stdout_pipe = self.stdout().take();
+			let mut stderr_pipe = self.stderr().take();
+
+			let stdin_fut = write_all(stdin_pipe.filter(|_| !input.is_empty()), &input);
+			let stdout_fut = read_to_end(&mut stdout_pipe);
+			let stderr_fut = read_to_end(&mut stderr_pipe);
+
+			let (status, (), stdout, stderr) =
+				try_join4(self.wait(), stdin_fut, stdout_fut, stderr_fut).await?;
+
+			Ok(Output {
+				status,
+				stdout,
+				stderr,
+			})
+		})
+	}
+
+	/// Send a signal to the `Child`.
+	///
+	/// This method doesn't exist on std's `Child`. It was introduced by command-group to abstract
+	/// over the signal behaviour between process groups and unwrapped processes.
+	fn signal(&self, sig: i32) -> Result<()> {
+		self.inner().signal(sig)
+	}
+}
+
+impl ChildWrapper for Child {
+	fn inner(&self) -> &dyn ChildWrapper {
+		self
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		self
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self
+	}
+	fn stdin(&mut self) -> &mut Option<ChildStdin> {
+		&mut self.stdin
+	}
+	fn stdout(&mut self) -> &mut Option<ChildStdout> {
+		&mut self.stdout
+	}
+	fn stderr(&mut self) -> &mut Option<ChildStderr> {
+		&mut self.stderr
+	}
+	fn id(&self) -> u32 {
+		Child::id(self)
+	}
+	fn start_kill(&mut self) -> Result<()> {
+		Child::start_kill(self)
+	}
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		Child::try_wait(self)
+	}
+	fn wait(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitStatus>> + Send + '_>> {
+		Box::pin(Child::wait(self))
+	}
+	fn kill_reason(&self) -> Option<String> {
+		None
+	}
+	fn signal(&self, sig: i32) -> Result<()> {
+		kill(
+			Pid::from_raw(i32::try_from(self.id()).map_err(std::io::Error::other)?),
+			Signal::try_from(sig)?,
+		)
+		.map_err(std::io::Error::from)
+	}
+}
+
+impl dyn ChildWrapper {
+	fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+		(self as &dyn Any).downcast_ref()
+	}
+
+	fn is_raw_child(&self) -> bool {
+		self.downcast_ref::<Child>().is_some()
+	}
+
+	/// List the layers of the wrapper chain, from this wrapper down to the bottom of the chain.
+	///
+	/// The bottom is normally the [`Child`], but can also be an adapter.
+	pub fn layers(&self) -> ChildLayers {
+		let mut layers = vec![self.name()];
+		let mut inner = self;
+		loop {
+			let next = inner.inner();
+			if layer_id(inner) == layer_id(next) {
+				break ChildLayers(layers);
+			}
+			layers.push(next.name());
+			inner = next;
+		}
+	}
+
+	/// Find the outermost layer of the wrapper chain of a given type.
+	///
+	/// This walks `inner()` from this wrapper down to the bottom of the chain, so it can reach a
+	/// wrapper underneath others, e.g. a `ProcessGroupChild` under a custom wrapper.
+	///
+	/// Returns `None` if there's no layer of this type.
+	pub fn find_layer<T: ChildWrapper>(&self) -> Option<&T> {
+		// UNWRAP: layer_depth() found a T at this depth
+		self.layer_depth::<T>()
+			.map(|depth| self.layer(depth).downcast_ref().unwrap())
+	}
+
+	/// Find the outermost layer of the wrapper chain of a given type, mutably.
+	///
+	/// Like [`find_layer()`](Self::find_layer), this walks `inner_mut()` down the chain.
+	pub fn find_layer_mut<T: ChildWrapper>(&mut self) -> Option<&mut T> {
+		let depth = self.layer_depth::<T>()?;
+		let mut inner = self;
+		for _ in 0..depth {
+			inner = inner.inner_mut();
+		}
+
+		// UNWRAP: layer_depth() found a T at this depth
+		Some((inner as &mut dyn Any).downcast_mut().unwrap())
+	}
+
+	/// How many layers down the outermost layer of a given type is.
+	fn layer_depth<T: ChildWrapper>(&self) -> Option<usize> {
+		let mut inner = self;
+		for depth in 0.. {
+			if inner.downcast_ref::<T>().is_some() {
+				return Some(depth);
+			}
+
+			let next = inner.inner();
+			if layer_id(inner) == layer_id(next) {
+				break;
+			}
+			inner = next;
+		}
+		None
+	}
+
+	fn layer(&self, depth: usize) -> &dyn ChildWrapper {
+		let mut inner = self;
+		for _ in 0..depth {
+			inner = inner.inner();
+		}
+		inner
+	}
+
+	/// Obtain a reference to the underlying [`Child`].
+	///
+	/// Panics if the bottom of the wrapper chain isn't a `Child`.
+	pub fn inner_child(&self) -> &Child {
+		let mut inner = self;
+		while !inner.is_raw_child() {
+			let next = inner.inner();
+			assert_not_bottom(layer_id(inner), next);
+			inner = next;
+		}
+
+		// UNWRAP: we've just checked that it's Some with is_raw_child()
+		inner.downcast_ref().unwrap()
+	}
+
+	/// Obtain a mutable reference to the underlying [`Child`].
+	///
+	/// # Safety
+	///
+	/// Modifying the raw child may be unsound depending on the layering of wrappers. The caller
+	/// must not use it to break the invariants of the wrappers above it, e.g. by reaping it.
+	pub unsafe fn inner_child_mut(&mut self) -> &mut Child {
+		let mut inner = self;
+		while !inner.is_raw_child() {
+			let prev = layer_id(inner);
+			inner = inner.inner_mut();
+			assert_not_bottom(prev, inner);
+		}
+
+		// UNWRAP: we've just checked that with is_raw_child()
+		(inner as &mut dyn Any).downcast_mut().unwrap()
+	}
+
+	/// Obtain the underlying [`Child`].
+	///
+	/// # Safety
+	///
+	/// Unwrapping everything may be unsound depending on the state of the wrappers. The caller
+	/// must make sure that dropping the wrappers doesn't leave the child in an unexpected state.
+	pub unsafe fn into_inner_child(self: Box<Self>) -> Child {
+		let mut inner = self;
+		while !inner.is_raw_child() {
+			let prev = layer_id(&*inner);
+			inner = inner.into_inner();
+			assert_not_bottom(prev, &*inner);
+		}
+
+		// UNWRAP: we've just checked that with is_raw_child()
+		*(inner as Box<dyn Any>).downcast().unwrap()
+	}
+
+	/// Stream the child's output as it runs, and then its exit.
+	///
+	/// This takes the child's stdout and stderr, if they're piped, and yields their contents as
+	/// [`OutputEvent`]s split according to the [`Framing`], in the order they're read. Once both are
+	/// closed, it waits for the child with the wrapper chain's `wait()`, so wrappers can do their
+	/// cleanup as usual, and yields [`OutputEvent::Exited`] last.
+	///
+	/// The outputs are only read while the stream is polled. Dropping the stream stops reading, but
+	/// doesn't kill the child.
+	pub fn output_events(
+		mut self: Box<Self>,
+		framing: Framing,
+	) -> impl Stream<Item = Result<OutputEvent>> + Send {
+		let stdout = read_events(self.stdout().take(), framing, OutputEvent::Stdout);
+		let stderr = read_events(self.stderr().take(), framing, OutputEvent::Stderr);
+
+		// the child is only waited for once both outputs are done
+		stream::select(stdout, stderr).chain(stream::once(async move {
+			self.wait().await.map(OutputEvent::Exited)
+		}))
+	}
+}
+
+/// Read a pipe to the end, as events.
+fn read_events<R: AsyncRead + Unpin + Send>(
+	pipe: Option<R>,
+	framing: Framing,
+	event: fn(Vec<u8>) -> OutputEvent,
+) -> impl Stream<Item = Result<OutputEvent>> + Send {
+	let state = (pipe, Framer::new(framing), vec![0; CHUNK_SIZE], None);
+	stream::unfold(
+		state,
+		move |(mut pipe, mut framer, mut buf, mut error)| async move {
+			loop {
+				if let Some(frame) = framer.next_frame() {
+					return Some((Ok(event(frame)), (pipe, framer, buf, error)));
+				}
+				if let Some(err) = error.take() {
+					return Some((Err(err), (pipe, framer, buf, None)));
+				}
+
+				match pipe.as_mut()?.read(&mut buf).await {
+					Ok(0) => {
+						framer.finish();
+						pipe = None;
+					}
+					Ok(n) => framer.push(&buf[..n]),
+					// stop reading after an error, once what was read before it is out
+					Err(err) => {
+						framer.finish();
+						pipe = None;
+						error = Some(err);
+					}
+				}
+			}
+		},
+	)
+}
+
+/// Identify a layer, to find the bottom of the chain, which returns itself from `inner()`.
+///
+/// The type is included as a wrapper's first field may be at the same address as the wrapper.
+fn layer_id(layer: &dyn ChildWrapper) -> (*const (), TypeId) {
+	(
+		(layer as *const dyn ChildWrapper).cast(),
+		(layer as &dyn Any).type_id(),
+	)
+}
+
+fn assert_not_bottom(prev: (*const (), TypeId), next: &dyn ChildWrapper) {
+	assert!(
+		prev != layer_id(next),
+		"the bottom of the wrapper chain isn't a Child"
+	);
+}
+
+const _: () = {
+	const fn assert_sync<T: ?Sized + Sync>() {}
+	assert_sync::<dyn ChildWrapper>();
+};
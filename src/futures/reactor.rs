@@ -0,0 +1,362 @@
+//! Minimal readiness reactor for the futures frontend.
+//!
+//! As this frontend can't rely on any particular executor's reactor, it runs its own: a single
+//! background thread which `poll(2)`s the file descriptors that futures are waiting on, and wakes
+//! them when they become ready. Registrations are one-shot and level-triggered: a future registers
+//! when an operation would block, is woken once, and retries the operation.
+//!
+//! Process exit is watched either through a pidfd (on Linux 5.3+), which becomes readable when the
+//! process exits, or through a `SIGCHLD` handler writing to a self-pipe, which wakes every future
+//! waiting on a process exit so that they can check whether it was theirs.
+
+use std::{
+	io::{Error, Result},
+	os::fd::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd, RawFd},
+	sync::{
+		Mutex, MutexGuard, OnceLock, PoisonError,
+		atomic::{AtomicI32, Ordering},
+	},
+	task::Waker,
+	thread,
+	time::Duration,
+};
+
+use nix::{
+	errno::Errno,
+	fcntl::{FcntlArg, FdFlag, OFlag, fcntl},
+	libc,
+	poll::{PollFd, PollFlags, PollTimeout, poll},
+	sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction},
+	unistd::{pipe, read, write},
+};
+#[cfg(feature = "tracing")]
+use tracing::{debug, trace};
+
+static REACTOR: OnceLock<Reactor> = OnceLock::new();
+
+/// The write end of the SIGCHLD self-pipe, for the signal handler.
+static SIGCHLD_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// The SIGCHLD disposition that was in place before ours, to be chained to.
+static PREVIOUS_SIGCHLD: OnceLock<SigAction> = OnceLock::new();
+
+/// How long the reactor waits before polling again after polling failed, at first and at most.
+const POLL_BACKOFF: (Duration, Duration) = (Duration::from_millis(10), Duration::from_secs(1));
+
+/// Which readiness a future is waiting for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Interest {
+	Read,
+	Write,
+}
+
+impl Interest {
+	fn flags(self) -> PollFlags {
+		match self {
+			Self::Read => PollFlags::POLLIN,
+			Self::Write => PollFlags::POLLOUT,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Registration {
+	fd: RawFd,
+	interest: Interest,
+	waker: Waker,
+}
+
+#[derive(Debug)]
+pub(crate) struct Reactor {
+	registrations: Mutex<Vec<Registration>>,
+	sigchld: OnceLock<OwnedFd>,
+	sigchld_waiters: Mutex<Vec<Waker>>,
+	install_lock: Mutex<()>,
+	notify: OwnedFd,
+}
+
+/// Get the reactor, starting it if needed.
+pub(crate) fn reactor() -> Result<&'static Reactor> {
+	if let Some(reactor) = REACTOR.get() {
+		return Ok(reactor);
+	}
+
+	let (wakeup, notify) = nonblocking_pipe()?;
+	let reactor = Reactor {
+		registrations: Mutex::default(),
+		sigchld: OnceLock::new(),
+		sigchld_waiters: Mutex::default(),
+		install_lock: Mutex::default(),
+		notify,
+	};
+
+	// if another thread got there first, ours is dropped along with its pipe
+	if REACTOR.set(reactor).is_ok() {
+		#[cfg(feature = "tracing")]
+		debug!("starting reactor thread");
+		thread::Builder::new()
+			.name("process-wrap reactor".into())
+			.spawn(move || run(wakeup))?;
+	}
+
+	Ok(REACTOR.get().expect("the reactor was just set"))
+}
+
+impl Reactor {
+	/// Wake the waker when the descriptor is ready for the given interest.
+	///
+	/// The descriptor must stay open until then, or the waker may be woken spuriously.
+	pub fn register(&self, fd: RawFd, interest: Interest, waker: &Waker) {
+		{
+			let mut registrations = lock(&self.registrations);
+			if let Some(existing) = registrations
+				.iter_mut()
+				.find(|r| r.fd == fd && r.interest == interest && r.waker.will_wake(waker))
+			{
+				existing.waker.clone_from(waker);
+				return;
+			}
+
+			registrations.push(Registration {
+				fd,
+				interest,
+				waker: waker.clone(),
+			});
+		}
+
+		self.notify();
+	}
+
+	/// Wake the waker on the next `SIGCHLD`, installing the handler if needed.
+	pub fn register_sigchld(&self, waker: &Waker) -> Result<()> {
+		self.install_sigchld()?;
+
+		let mut waiters = lock(&self.sigchld_waiters);
+		if !waiters.iter().any(|w| w.will_wake(waker)) {
+			waiters.push(waker.clone());
+		}
+
+		Ok(())
+	}
+
+	fn install_sigchld(&self) -> Result<()> {
+		let _guard = lock(&self.install_lock);
+		if self.sigchld.get().is_some() {
+			return Ok(());
+		}
+
+		#[cfg(feature = "tracing")]
+		debug!("installing SIGCHLD handler");
+
+		let (read_end, write_end) = nonblocking_pipe()?;
+		// the write end is never closed, as the handler may use it for the rest of the process
+		SIGCHLD_PIPE.store(write_end.into_raw_fd(), Ordering::SeqCst);
+
+		let action = SigAction::new(
+			SigHandler::SigAction(on_sigchld),
+			SaFlags::SA_SIGINFO | SaFlags::SA_RESTART,
+			SigSet::empty(),
+		);
+		// SAFETY: the handler only uses async-signal-safe functions
+		let previous = unsafe { sigaction(Signal::SIGCHLD, &action) }?;
+		PREVIOUS_SIGCHLD.set(previous).ok();
+
+		self.sigchld.set(read_end).ok();
+		self.notify();
+		Ok(())
+	}
+
+	/// Wake every registered future, whether its descriptor is ready or not.
+	fn wake_all(&self) {
+		let woken: Vec<Waker> = std::mem::take(&mut *lock(&self.registrations))
+			.into_iter()
+			.map(|r| r.waker)
+			.chain(std::mem::take(&mut *lock(&self.sigchld_waiters)))
+			.collect();
+		for waker in woken {
+			waker.wake();
+		}
+	}
+
+	fn notify(&self) {
+		// if the pipe is full, the reactor is already due to wake up
+		write(&self.notify, &[0]).ok();
+	}
+}
+
+fn run(wakeup: OwnedFd) {
+	let reactor = REACTOR
+		.get()
+		.expect("the reactor is set before its thread starts");
+	let mut backoff = Duration::ZERO;
+	loop {
+		let watched: Vec<(RawFd, Interest)> = lock(&reactor.registrations)
+			.iter()
+			.map(|r| (r.fd, r.interest))
+			.collect();
+		let sigchld = reactor.sigchld.get();
+
+		let mut fds = Vec::with_capacity(watched.len() + 2);
+		fds.push(PollFd::new(wakeup.as_fd(), PollFlags::POLLIN));
+		if let Some(sigchld) = sigchld {
+			fds.push(PollFd::new(sigchld.as_fd(), PollFlags::POLLIN));
+		}
+		let offset = fds.len();
+		for (fd, interest) in &watched {
+			// SAFETY: closed descriptors are reported as POLLNVAL, which wakes their future
+			let fd = unsafe { BorrowedFd::borrow_raw(*fd) };
+			fds.push(PollFd::new(fd, interest.flags()));
+		}
+
+		match poll(&mut fds, PollTimeout::NONE) {
+			Ok(_) | Err(Errno::EINTR) => backoff = Duration::ZERO,
+			Err(_err) => {
+				// failures like ENOMEM, or EINVAL with more descriptors than allowed, won't go away
+				// by polling again straight away: instead, every future is woken to retry its own
+				// operation (and re-register if it would still block), and polling backs off
+				backoff = (backoff * 2).clamp(POLL_BACKOFF.0, POLL_BACKOFF.1);
+				#[cfg(feature = "tracing")]
+				debug!(?_err, ?backoff, "reactor poll failed, backing off");
+				drop(fds);
+				reactor.wake_all();
+				thread::sleep(backoff);
+				continue;
+			}
+		}
+
+		let is_ready = |fd: &PollFd| fd.revents().is_some_and(|r| !r.is_empty());
+		let ready: Vec<(RawFd, Interest)> = fds[offset..]
+			.iter()
+			.zip(&watched)
+			.filter(|(fd, _)| is_ready(fd))
+			.map(|(_, watched)| *watched)
+			.collect();
+		let sigchld_ready = sigchld.is_some() && is_ready(&fds[1]);
+		if is_ready(&fds[0]) {
+			drain(&wakeup);
+		}
+		drop(fds);
+
+		if sigchld_ready {
+			drain(sigchld.expect("checked above"));
+			let waiters = std::mem::take(&mut *lock(&reactor.sigchld_waiters));
+			#[cfg(feature = "tracing")]
+			trace!(waiters = waiters.len(), "SIGCHLD received");
+			for waker in waiters {
+				waker.wake();
+			}
+		}
+
+		if !ready.is_empty() {
+			let mut woken = Vec::new();
+			lock(&reactor.registrations).retain(|r| {
+				if ready.contains(&(r.fd, r.interest)) {
+					woken.push(r.waker.clone());
+					false
+				} else {
+					true
+				}
+			});
+
+			for waker in woken {
+				waker.wake();
+			}
+		}
+	}
+}
+
+extern "C" fn on_sigchld(sig: libc::c_int, info: *mut libc::siginfo_t, context: *mut libc::c_void) {
+	let errno = Errno::last_raw();
+
+	let fd = SIGCHLD_PIPE.load(Ordering::SeqCst);
+	if fd >= 0 {
+		// SAFETY: write(2) is async-signal-safe, and the descriptor is never closed
+		unsafe { libc::write(fd, [0_u8].as_ptr().cast(), 1) };
+	}
+
+	if let Some(previous) = PREVIOUS_SIGCHLD.get() {
+		match previous.handler() {
+			SigHandler::Handler(handler) => handler(sig),
+			SigHandler::SigAction(handler) => handler(sig, info, context),
+			SigHandler::SigDfl | SigHandler::SigIgn => {}
+		}
+	}
+
+	Errno::set_raw(errno);
+}
+
+fn nonblocking_pipe() -> Result<(OwnedFd, OwnedFd)> {
+	let (read_end, write_end) = pipe()?;
+	for fd in [&read_end, &write_end] {
+		set_nonblocking(fd.as_fd())?;
+		fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+	}
+	Ok((read_end, write_end))
+}
+
+/// Put a descriptor in non-blocking mode.
+pub(crate) fn set_nonblocking(fd: BorrowedFd<'_>) -> Result<()> {
+	let mut flags = OFlag::from_bits_truncate(fcntl(fd, FcntlArg::F_GETFL)?);
+	flags.insert(OFlag::O_NONBLOCK);
+	fcntl(fd, FcntlArg::F_SETFL(flags))
+		.map_err(Error::from)
+		.map(drop)
+}
+
+fn drain(fd: &OwnedFd) {
+	let mut buf = [0; 64];
+	while matches!(read(fd, &mut buf), Ok(n) if n > 0) {}
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Watches for a process to exit.
+#[derive(Debug)]
+pub(crate) struct ExitWatch {
+	pidfd: Option<OwnedFd>,
+}
+
+impl ExitWatch {
+	/// Start watching a process.
+	///
+	/// This should be done before the process can be reaped, so its pidfd can be obtained.
+	pub fn new(pid: u32) -> Self {
+		Self {
+			pidfd: pidfd_open(pid),
+		}
+	}
+
+	/// Wake the waker when the process may have exited.
+	pub fn register(&self, waker: &Waker) -> Result<()> {
+		let reactor = reactor()?;
+		match &self.pidfd {
+			Some(pidfd) => {
+				reactor.register(pidfd.as_raw_fd(), Interest::Read, waker);
+				Ok(())
+			}
+			None => reactor.register_sigchld(waker),
+		}
+	}
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn pidfd_open(pid: u32) -> Option<OwnedFd> {
+	use std::os::fd::FromRawFd;
+
+	// SAFETY: the syscall has no memory side-effects, and returns a new descriptor we then own
+	match unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) } {
+		-1 => {
+			#[cfg(feature = "tracing")]
+			debug!(errno = ?Errno::last(), "pidfd_open failed, falling back to SIGCHLD");
+			None
+		}
+		fd => Some(unsafe { OwnedFd::from_raw_fd(fd as RawFd) }),
+	}
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn pidfd_open(_pid: u32) -> Option<OwnedFd> {
+	None
+}
@@ -0,0 +1,44 @@
+use std::{io::Result, os::unix::process::CommandExt, process::Command};
+
+use nix::sys::signal::{SigSet, SigmaskHow, sigprocmask};
+#[cfg(feature = "tracing")]
+use tracing::trace;
+
+use crate::unix::sigmask_is_empty;
+
+use super::{CommandWrap, CommandWrapper};
+
+/// Wrapper which resets the process signal mask.
+///
+/// By default a Command on Unix inherits its parent's [signal mask]. However, in some cases this
+/// is not what you want. This wrapper resets the command's sigmask by unblocking all signals.
+///
+/// When the spawning thread doesn't block any signal, the command inherits an empty sigmask, so
+/// there's nothing to do in the child, and the standard library can spawn it without `fork`.
+#[derive(Clone, Copy, Debug)]
+pub struct ResetSigmask;
+
+impl CommandWrapper for ResetSigmask {
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		if sigmask_is_empty()? {
+			return Ok(());
+		}
+
+		unsafe {
+			command.pre_exec(|| {
+				let mut oldset = SigSet::empty();
+				let newset = SigSet::all();
+
+				#[cfg(feature = "tracing")]
+				trace!(unblocking=?newset, "resetting process sigmask");
+
+				sigprocmask(SigmaskHow::SIG_UNBLOCK, Some(&newset), Some(&mut oldset))?;
+
+				#[cfg(feature = "tracing")]
+				trace!(?oldset, "sigmask reset");
+				Ok(())
+			});
+		}
+		Ok(())
+	}
+}
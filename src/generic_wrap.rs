@@ -1,5 +1,5 @@
 #![cfg_attr(
-	not(any(feature = "std", feature = "tokio1")),
+	not(any(feature = "std", feature = "tokio1", feature = "futures")),
 	allow(unused_macros, unused_imports)
 )]
 
@@ -61,8 +61,19 @@ macro_rules! Wrap {
 			///
 			/// Returns `&mut self` for chaining.
 			pub fn wrap<W: CommandWrapper + 'static>(&mut self, wrapper: W) -> &mut Self {
-				let typeid = ::std::any::TypeId::of::<W>();
-				let mut wrapper = Some(Box::new(wrapper));
+				self.wrap_boxed(Box::new(wrapper))
+			}
+
+			/// Add a boxed wrapper to the command.
+			///
+			/// This is like [`wrap`](Self::wrap), for when the wrapper is chosen at runtime. It's
+			/// keyed by the concrete type of the boxed wrapper, so it can then be accessed with
+			/// [`get_wrap`](Self::get_wrap) and friends as if it had been added with `wrap`.
+			///
+			/// Returns `&mut self` for chaining.
+			pub fn wrap_boxed(&mut self, wrapper: Box<dyn CommandWrapper>) -> &mut Self {
+				let typeid = (&*wrapper as &dyn ::std::any::Any).type_id();
+				let mut wrapper = Some(wrapper);
 				let extant = self
 					.wrappers
 					.entry(typeid)
@@ -74,25 +85,122 @@ macro_rules! Wrap {
 				self
 			}
 
+			/// Run a wrapper's hook, with the wrapper taken out of the stack.
+			///
+			/// This gives the hook mutable access to its wrapper and shared access to the rest, with
+			/// a placeholder standing in for the wrapper in the meantime.
+			fn with_wrapper<T>(
+				&mut self,
+				index: usize,
+				hook: impl FnOnce(&mut dyn CommandWrapper, &Self) -> T,
+			) -> T {
+				let mut wrapper =
+					::std::mem::replace(&mut self.wrappers[index], Box::new(VacantWrapper));
+				let res = hook(&mut *wrapper, self);
+				self.wrappers[index] = wrapper;
+				res
+			}
+
+			/// Check the wrappers' constraints, reordering them where that's allowed.
+			fn apply_constraints(&mut self) -> ::std::io::Result<()> {
+				use crate::constraint::ConstraintKind;
+
+				let constraints: Vec<_> = self
+					.wrappers
+					.iter()
+					.flat_map(|(id, wrapper)| {
+						let name = wrapper.name();
+						wrapper
+							.constraints()
+							.into_iter()
+							.map(move |constraint| (*id, name, constraint))
+					})
+					.collect();
+
+				let invalid = |message: String| {
+					::std::io::Error::new(::std::io::ErrorKind::InvalidInput, message)
+				};
+				for (_, name, constraint) in &constraints {
+					if constraint.kind == ConstraintKind::ConflictsWith
+						&& self.wrappers.contains_key(&constraint.other)
+					{
+						return Err(invalid(format!(
+							"{name} conflicts with {}",
+							constraint.other_name
+						)));
+					}
+				}
+
+				// each pass moves at least one wrapper, so more passes than constraints means a cycle
+				for _ in 0..=constraints.len() {
+					let mut moved = false;
+					for (id, name, constraint) in &constraints {
+						let (Some(this), Some(other)) = (
+							self.wrappers.get_index_of(id),
+							self.wrappers.get_index_of(&constraint.other),
+						) else {
+							continue;
+						};
+
+						let (met, relation) = match constraint.kind {
+							ConstraintKind::ConflictsWith => continue,
+							ConstraintKind::Before => (this < other, "before"),
+							ConstraintKind::After => (this > other, "after"),
+						};
+						if met {
+							continue;
+						}
+
+						if !constraint.reorder {
+							return Err(invalid(format!(
+								"{name} must be added {relation} {}",
+								constraint.other_name
+							)));
+						}
+
+						#[cfg(feature = "tracing")]
+						::tracing::debug!(
+							wrapper = name,
+							relation,
+							other = constraint.other_name,
+							"reordering wrapper"
+						);
+						// moving to the other's index puts it just before it if it was after,
+						// and just after it if it was before
+						self.wrappers.move_index(this, other);
+						moved = true;
+					}
+
+					if !moved {
+						return Ok(());
+					}
+				}
+
+				Err(invalid("wrapper ordering constraints can't all be met".into()))
+			}
+
 			// poor man's try..finally block
 			#[inline]
 			fn spawn_inner(
-				&self,
+				&mut self,
 				command: &mut $command,
-				wrappers: &mut ::indexmap::IndexMap<::std::any::TypeId, Box<dyn CommandWrapper>>,
 				spawner: impl FnOnce(&mut $command) -> ::std::io::Result<$child>,
 			) -> ::std::io::Result<Box<dyn $childer>> {
-				for (id, wrapper) in wrappers.iter_mut() {
+				self.apply_constraints()?;
+
+				for index in 0..self.wrappers.len() {
 					#[cfg(feature = "tracing")]
-					::tracing::debug!(?id, "pre_spawn");
-					wrapper.pre_spawn(command, self)?;
+					::tracing::debug!(wrapper = self.wrappers[index].name(), "pre_spawn");
+					self.with_wrapper(index, |wrapper, core| wrapper.pre_spawn(command, core))?;
 				}
 
 				let mut child = spawner(command)?;
-				for (id, wrapper) in wrappers.iter_mut() {
+				for index in 0..self.wrappers.len() {
 					#[cfg(feature = "tracing")]
-					::tracing::debug!(?id, "post_spawn");
-					wrapper.post_spawn(command, &mut child, self)?;
+					::tracing::debug!(wrapper = self.wrappers[index].name(), "post_spawn");
+					self.with_wrapper(index, |wrapper, core| {
+						wrapper.post_spawn(command, &mut child, core)
+					})?;
 				}
 
 				let mut child = Box::new(
@@ -100,10 +208,10 @@ macro_rules! Wrap {
 					$first_child_wrapper(child),
 				) as Box<dyn $childer>;
 
-				for (id, wrapper) in wrappers.iter_mut() {
+				for index in 0..self.wrappers.len() {
 					#[cfg(feature = "tracing")]
-					::tracing::debug!(?id, "wrap_child");
-					child = wrapper.wrap_child(child, self)?;
+					::tracing::debug!(wrapper = self.wrappers[index].name(), "wrap_child");
+					child = self.with_wrapper(index, |wrapper, core| wrapper.wrap_child(child, core))?;
 				}
 
 				Ok(child)
@@ -111,10 +219,15 @@ macro_rules! Wrap {
 
 			/// Spawn the command, returning a `Child` that can be interacted with.
 			///
-			/// In order, this runs all the `pre_spawn` hooks, then spawns the command, then runs
-			/// all the `post_spawn` hooks, then stacks all the `wrap_child`s. As it returns a boxed
-			/// trait object, only the methods from the trait are available directly; however you
-			/// may downcast to the concrete type of the last applied wrapper if you need to.
+			/// First, the wrappers' [`constraints`](CommandWrapper::constraints) are checked, which
+			/// may fail or reorder the wrappers. Then in order, this runs all the `pre_spawn` hooks,
+			/// then spawns the command, then runs all the `post_spawn` hooks, then stacks all the
+			/// `wrap_child`s. As it returns a boxed trait object, only the methods from the trait are
+			/// available directly; however you may downcast to the concrete type of the last applied
+			/// wrapper if you need to.
+			///
+			/// If any of these steps fail, all the `spawn_failed` hooks are run before returning the
+			/// error.
 			pub fn spawn(&mut self) -> ::std::io::Result<Box<dyn $childer>> {
 				self.spawn_with(|command| command.spawn())
 			}
@@ -127,18 +240,27 @@ macro_rules! Wrap {
 			///
 			/// The lifecycle is the same as `spawn`: all `pre_spawn` hooks run first, then
 			/// the provided closure is called, then `post_spawn` hooks, then `wrap_child`.
+			///
+			/// While the hooks run, the `core` they're given has all the wrappers, but the command is
+			/// moved out of it: use the hooks' `command` argument instead.
 			pub fn spawn_with(
 				&mut self,
 				spawner: impl FnOnce(&mut $command) -> ::std::io::Result<$child>,
 			) -> ::std::io::Result<Box<dyn $childer>> {
 				let mut command = ::std::mem::replace(&mut self.command, <$command>::new(""));
-				let mut wrappers = ::std::mem::take(&mut self.wrappers);
 
-				let res = self.spawn_inner(&mut command, &mut wrappers, spawner);
+				let res = self.spawn_inner(&mut command, spawner);
+				if let Err(err) = &res {
+					for index in 0..self.wrappers.len() {
+						#[cfg(feature = "tracing")]
+						::tracing::debug!(wrapper = self.wrappers[index].name(), "spawn_failed");
+						self.with_wrapper(index, |wrapper, core| {
+							wrapper.spawn_failed(&mut command, err, core)
+						});
+					}
+				}
 
 				self.command = command;
-				self.wrappers = wrappers;
-
 				res
 			}
 
@@ -154,15 +276,69 @@ macro_rules! Wrap {
 			/// another wrapper.
 			///
 			/// Returns `None` if the wrapper is not present. To merely check if a wrapper is
-			/// present, use `has_wrap` instead.
+			/// present, use `has_wrap` instead. During spawn, this also returns `None` for the
+			/// wrapper whose hook is running, as it's taken out to be given to the hook.
 			pub fn get_wrap<W: CommandWrapper + 'static>(&self) -> Option<&W> {
 				let typeid = ::std::any::TypeId::of::<W>();
-				self.wrappers.get(&typeid).map(|w| {
-					let w_any = w as &dyn ::std::any::Any;
-					w_any
-						.downcast_ref()
-						.expect("downcasting is guaranteed to succeed due to wrap()'s internals")
-				})
+				// this only fails for the wrapper whose hook is running, which is vacant
+				self.wrappers
+					.get(&typeid)
+					.and_then(|w| (&**w as &dyn ::std::any::Any).downcast_ref())
+			}
+
+			/// Get a mutable reference to a wrapper of a given type.
+			///
+			/// This can be used to reconfigure a wrapper after it's been added.
+			///
+			/// Returns `None` if the wrapper is not present.
+			pub fn get_wrap_mut<W: CommandWrapper + 'static>(&mut self) -> Option<&mut W> {
+				let typeid = ::std::any::TypeId::of::<W>();
+				self.wrappers
+					.get_mut(&typeid)
+					.and_then(|w| (&mut **w as &mut dyn ::std::any::Any).downcast_mut())
+			}
+
+			/// Remove a wrapper of a given type, and return it.
+			///
+			/// The remaining wrappers keep their order.
+			///
+			/// Returns `None` if the wrapper is not present.
+			pub fn unwrap<W: CommandWrapper + 'static>(&mut self) -> Option<W> {
+				let typeid = ::std::any::TypeId::of::<W>();
+				self.wrappers
+					.shift_remove(&typeid)
+					.and_then(|w| (w as Box<dyn ::std::any::Any>).downcast().ok())
+					.map(|w| *w)
+			}
+
+			/// Describe what will be spawned: the command, and the wrappers in their current order.
+			///
+			/// Wrappers may still be reordered at spawn to meet their constraints.
+			pub fn describe(&self) -> crate::CommandPlan {
+				use crate::describe::AsStdCommand;
+
+				let wrappers = self
+					.wrappers
+					.values()
+					.map(|wrapper| crate::WrapperPlan {
+						name: wrapper.name(),
+						config: format!("{wrapper:?}"),
+					})
+					.collect();
+				crate::CommandPlan::new(self.command.as_std_command(), wrappers)
+			}
+
+			/// Replace a wrapper of a given type, and return the previous one.
+			///
+			/// The new wrapper takes the place of the previous one in the order of wrappers, and
+			/// unlike with [`wrap`](Self::wrap), the previous one's `extend` hook isn't called. If
+			/// there was no wrapper of this type, the new one is added last, and `None` is returned.
+			pub fn replace<W: CommandWrapper + 'static>(&mut self, wrapper: W) -> Option<W> {
+				let typeid = ::std::any::TypeId::of::<W>();
+				self.wrappers
+					.insert(typeid, Box::new(wrapper))
+					.and_then(|w| (w as Box<dyn ::std::any::Any>).downcast().ok())
+					.map(|w| *w)
 			}
 		}
 
@@ -186,7 +362,7 @@ macro_rules! Wrap {
 		/// #[derive(Debug)]
 		/// pub struct YourWrapper;
 		#[doc = concat!("impl ", stringify!(CommandWrapper), " for YourWrapper {}\n```")]
-		pub trait CommandWrapper: ::std::fmt::Debug + Send + Sync {
+		pub trait CommandWrapper: ::std::any::Any + ::std::fmt::Debug + Send + Sync {
 			/// Called on a first instance if a second of the same type is added.
 			///
 			/// Only one of a wrapper type can exist within a Wrap at a time. The default behaviour
@@ -202,6 +378,26 @@ macro_rules! Wrap {
 			/// Default impl: no-op.
 			fn extend(&mut self, _other: Box<dyn CommandWrapper>) {}
 
+			/// The name of the wrapper, for errors and logs.
+			///
+			/// Default impl: the wrapper's type name.
+			fn name(&self) -> &'static str {
+				::std::any::type_name::<Self>()
+			}
+
+			/// Called before any `pre_spawn`, to declare how this wrapper can be combined with others.
+			///
+			/// Wrappers may conflict with other wrapper types, or need to be applied before or
+			/// after them. The `spawn` method fails with an `InvalidInput` error when a conflict or
+			/// an ordering constraint isn't met, unless the constraint is marked as reorderable,
+			/// in which case this wrapper is moved to meet it. See
+			/// [`WrapperConstraint`](crate::WrapperConstraint).
+			///
+			/// Default impl: no constraints.
+			fn constraints(&self) -> Vec<crate::WrapperConstraint> {
+				Vec::new()
+			}
+
 			/// Called before the command is spawned, to mutate it as needed.
 			///
 			/// This is where to modify the command before it is spawned. It also gives mutable
@@ -242,7 +438,29 @@ macro_rules! Wrap {
 			) -> Result<Box<dyn $childer>> {
 				Ok(child)
 			}
+
+			/// Called if spawning failed, at any stage.
+			///
+			/// This is called on every wrapper, whether the failure came from the spawn itself or
+			/// from another wrapper's hook, and whether or not this wrapper's own hooks have run.
+			/// It's meant for reporting and cleaning up any state stored by `pre_spawn`, including
+			/// changes it made to the command.
+			///
+			/// Default: no-op.
+			fn spawn_failed(
+				&mut self,
+				_command: &mut $command,
+				_error: &::std::io::Error,
+				_core: &CommandWrap,
+			) {
+			}
 		}
+
+		/// Stands in for a wrapper while its hook runs.
+		#[derive(Debug)]
+		struct VacantWrapper;
+
+		impl CommandWrapper for VacantWrapper {}
 	};
 }
 
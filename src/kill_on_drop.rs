@@ -0,0 +1,166 @@
+//! Shared policy and background reaping for the `KillOnDrop` wrappers.
+
+use std::{
+	thread,
+	time::{Duration, Instant},
+};
+
+#[cfg(feature = "tracing")]
+use tracing::debug;
+
+/// How often the background reaper checks whether the child has exited during the grace period.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What to do to a child when it's dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct DropKill {
+	/// The signal sent first, if there's a grace period.
+	pub signal: i32,
+
+	/// How long to wait after the first signal before sending `SIGKILL`.
+	///
+	/// With no grace period, `SIGKILL` is sent straight away.
+	pub grace: Option<Duration>,
+}
+
+impl DropKill {
+	#[cfg(unix)]
+	const SIGTERM: i32 = nix::libc::SIGTERM;
+	#[cfg(not(unix))]
+	const SIGTERM: i32 = 15;
+
+	pub const fn immediate() -> Self {
+		Self {
+			signal: Self::SIGTERM,
+			grace: None,
+		}
+	}
+
+	pub const fn graceful(grace: Duration) -> Self {
+		Self {
+			signal: Self::SIGTERM,
+			grace: Some(grace),
+		}
+	}
+
+	/// Wait out the grace period, or until `exited` returns true.
+	///
+	/// Returns whether the child exited within the grace period.
+	pub fn wait_grace(&self, mut exited: impl FnMut() -> bool) -> bool {
+		let Some(grace) = self.grace else {
+			return false;
+		};
+
+		let deadline = Instant::now() + grace;
+		loop {
+			if exited() {
+				return true;
+			}
+			if Instant::now() >= deadline {
+				return false;
+			}
+			thread::sleep(POLL_INTERVAL);
+		}
+	}
+
+	/// Poll until `exited` returns true, after the child was sent `SIGKILL`.
+	#[cfg(any(
+		feature = "tokio1",
+		all(unix, any(feature = "process-group", feature = "process-session"))
+	))]
+	pub fn wait_killed(&self, mut exited: impl FnMut() -> bool) {
+		while !exited() {
+			thread::sleep(POLL_INTERVAL);
+		}
+	}
+}
+
+/// Run a reaper in the background.
+pub(crate) fn in_background(reaper: impl FnOnce() + Send + 'static) {
+	if let Err(_err) = thread::Builder::new()
+		.name("process-wrap reaper".into())
+		.spawn(reaper)
+	{
+		#[cfg(feature = "tracing")]
+		debug!(
+			?_err,
+			"failed to start the reaper thread, the child won't be killed"
+		);
+	}
+}
+
+#[cfg(all(unix, any(feature = "process-group", feature = "process-session")))]
+pub(crate) use group::GroupKillGuard;
+
+#[cfg(all(unix, any(feature = "process-group", feature = "process-session")))]
+mod group {
+	use std::mem;
+
+	use nix::{
+		errno::Errno,
+		sys::{
+			signal::{Signal, killpg},
+			wait::{WaitPidFlag, WaitStatus, waitpid},
+		},
+		unistd::Pid,
+	};
+	#[cfg(feature = "tracing")]
+	use tracing::debug;
+
+	use super::{DropKill, in_background};
+
+	/// Kills a process group in the background when dropped, unless disarmed.
+	#[derive(Debug)]
+	pub(crate) struct GroupKillGuard {
+		pgid: Pid,
+		policy: DropKill,
+	}
+
+	impl GroupKillGuard {
+		pub fn new(pgid: Pid, policy: DropKill) -> Self {
+			Self { pgid, policy }
+		}
+
+		/// Don't kill the group after all, e.g. because it's already been reaped.
+		pub fn disarm(self) {
+			mem::forget(self);
+		}
+	}
+
+	impl Drop for GroupKillGuard {
+		fn drop(&mut self) {
+			let Self { pgid, policy } = *self;
+			#[cfg(feature = "tracing")]
+			debug!(?pgid, ?policy, "killing process group on drop");
+
+			in_background(move || {
+				if policy.grace.is_some() {
+					let signal = Signal::try_from(policy.signal).unwrap_or(Signal::SIGTERM);
+					killpg(pgid, signal).ok();
+					if policy.wait_grace(|| reap(pgid)) {
+						return;
+					}
+				}
+
+				killpg(pgid, Signal::SIGKILL).ok();
+				policy.wait_killed(|| reap(pgid));
+			});
+		}
+	}
+
+	/// Reap whichever members of the group are our children and have exited.
+	///
+	/// Returns whether the group is now empty. Members that aren't our children are reaped by
+	/// their own parents, or by init.
+	fn reap(pgid: Pid) -> bool {
+		loop {
+			match waitpid(Pid::from_raw(-pgid.as_raw()), Some(WaitPidFlag::WNOHANG)) {
+				Ok(WaitStatus::StillAlive) | Err(Errno::ECHILD) => break,
+				Ok(_) | Err(Errno::EINTR) => continue,
+				Err(_) => break,
+			}
+		}
+
+		killpg(pgid, None) == Err(Errno::ESRCH)
+	}
+}
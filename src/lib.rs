@@ -9,6 +9,7 @@
 //!
 //! ```rust,no_run
 //! # fn main() -> std::io::Result<()> {
+//! # #[cfg(feature = "std")] {
 //! use process_wrap::std::*;
 //!
 //! let mut command = CommandWrap::with_new("watch", |command| { command.arg("ls"); });
@@ -17,6 +18,7 @@
 //! let mut child = command.spawn()?;
 //! let status = child.wait()?;
 //! dbg!(status);
+//! # }
 //! # Ok(()) }
 //! ```
 //!
@@ -40,6 +42,7 @@
 //! which can be constructed either directly from an existing `process::Command`:
 //!
 //! ```rust
+//! # #[cfg(feature = "std")] {
 //! use process_wrap::std::*;
 //! use std::process::Command;
 //! let mut command = Command::new("ls");
@@ -47,23 +50,28 @@
 //! let mut command = CommandWrap::from(command);
 //! #[cfg(unix)] { command.wrap(ProcessGroup::leader()); }
 //! #[cfg(windows)] { command.wrap(JobObject); }
+//! # }
 //! ```
 //!
 //! ...or with a somewhat more ergonomic closure pattern:
 //!
 //! ```rust
+//! # #[cfg(feature = "std")] {
 //! use process_wrap::std::*;
 //! let mut command = CommandWrap::with_new("ls", |command| { command.arg("-l"); });
 //! #[cfg(unix)] { command.wrap(ProcessGroup::leader()); }
 //! #[cfg(windows)] { command.wrap(JobObject); }
+//! # }
 //! ```
 //!
 //! If targetting a single platform, then a fluent style is possible:
 //!
 //! ```rust
+//! # #[cfg(feature = "std")] {
 //! use process_wrap::std::*;
 //! CommandWrap::with_new("ls", |command| { command.arg("-l"); })
 //!    .wrap(ProcessGroup::leader());
+//! # }
 //! ```
 //!
 //! The `wrap` method can be called multiple times to add multiple wrappers. The order of the
@@ -88,9 +96,11 @@
 //! ## Instead of `.kill_on_drop(true)` (Tokio-only):
 //!
 //! ```rust
+//! # #[cfg(feature = "tokio1")] {
 //! use process_wrap::tokio::*;
 //! let mut command = CommandWrap::with_new("ls", |command| { command.arg("-l"); });
 //! command.wrap(KillOnDrop);
+//! # }
 //! ```
 //!
 //! ## Instead of `.creation_flags(CREATE_NO_WINDOW)` (Windows-only):
@@ -118,10 +128,12 @@
 //! Here's the most basic impl (shown for Tokio):
 //!
 //! ```rust
+//! # #[cfg(feature = "tokio1")] {
 //! use process_wrap::tokio::*;
 //! #[derive(Debug)]
 //! pub struct YourWrapper;
 //! impl CommandWrapper for YourWrapper {}
+//! # }
 //! ```
 //!
 //! The trait provides extension or hook points into the lifecycle of a `Command`:
@@ -131,6 +143,11 @@
 //!   opportunity to incorporate all or part of the second wrapper instance into the first. By
 //!   default, this does nothing (ie only the first registered wrapper instance of a type applies).
 //!
+//! - **`fn constraints(&self) -> Vec<WrapperConstraint>`** is called before any `pre_spawn()`, to
+//!   declare conflicts with other wrapper types, and whether this wrapper must be applied before or
+//!   after them. Spawning fails if these aren't met, unless they're declared as safe to reorder
+//!   automatically. By default there are no constraints.
+//!
 //! - **`fn pre_spawn(&mut self, command: &mut Command, core: &CommandWrap)`** is called before
 //!   the command is spawned, and gives mutable access to it. It also gives mutable access to the
 //!   wrapper instance, so state can be stored if needed. The `core` reference gives access to data
@@ -149,6 +166,11 @@
 //!   If your functionality is order-dependent, make sure to specify so in your documentation! By
 //!   default does nothing: no wrapping is performed and the input `child` is returned as-is.
 //!
+//! - **`fn spawn_failed(&mut self, command: &mut Command, error: &io::Error, core: &CommandWrap)`**
+//!   is called on every wrapper if spawning fails at any stage, including in another wrapper's
+//!   hook. It's meant for reporting, and for cleaning up state from `pre_spawn()`, including
+//!   changes made to the command. By default does nothing.
+//!
 //! ## An Example Logging Wrapper
 //!
 //! Let's implement a logging wrapper that redirects a `Command`'s `stdout` and `stderr` into a
@@ -157,6 +179,7 @@
 //! in.
 //!
 //! ```rust
+//! # #[cfg(feature = "std")] {
 //! # use process_wrap::std::{CommandWrap, CommandWrapper};
 //! # use std::{fs::File, io, path::PathBuf, process::Command, thread};
 //! #[derive(Debug)]
@@ -183,6 +206,7 @@
 //!         Ok(())
 //!     }
 //! }
+//! # }
 //! ```
 //!
 //! That's a great start, but it's actually introduced a resource leak: if the main thread of your
@@ -192,6 +216,7 @@
 //! when calling `.wait()` on the `ChildWrapper`.
 //!
 //! ```rust
+//! # #[cfg(feature = "std")] {
 //! # use process_wrap::std::{ChildWrapper, CommandWrap, CommandWrapper};
 //! # use std::{
 //! #     fs::File,
@@ -270,6 +295,7 @@
 //!         exit_status
 //!     }
 //! }
+//! # }
 //! ```
 //!
 //! Now we're cleaning up after ourselves, but there is one last issue: if you actually call
@@ -283,6 +309,7 @@
 //! setting its `stdin` and `stdout` to `Stdio::null()` in `CommandWrapper::post_spawn()`.
 //!
 //! ```rust
+//! # #[cfg(feature = "std")] {
 //! # use process_wrap::std::{CommandWrap, CommandWrapper};
 //! # use std::{
 //! #     io,
@@ -310,11 +337,13 @@
 //!     }
 //!     // ... snip ...
 //! }
+//! # }
 //! ```
 //!
 //! Finally, we can test that our new command-wrapper works:
 //!
 //! ```rust
+//! # #[cfg(feature = "std")]
 //! # use process_wrap::std::{ChildWrapper, CommandWrap, CommandWrapper};
 //! # use std::{
 //! #     error::Error,
@@ -325,12 +354,14 @@
 //! #     thread::{self, JoinHandle},
 //! # };
 //! # use tempfile::NamedTempFile;
+//! # #[cfg(feature = "std")]
 //! # #[derive(Debug)]
 //! # struct LogFile {
 //! #     path: PathBuf,
 //! #     thread: Option<JoinHandle<()>>,
 //! # }
 //! #
+//! # #[cfg(feature = "std")]
 //! # impl LogFile {
 //! #     fn new(path: impl Into<PathBuf>) -> Self {
 //! #         Self {
@@ -340,6 +371,7 @@
 //! #     }
 //! # }
 //! #
+//! # #[cfg(feature = "std")]
 //! # impl CommandWrapper for LogFile {
 //! #     fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> io::Result<()> {
 //! #         let mut logfile = File::create(&self.path)?;
@@ -377,12 +409,14 @@
 //! #     }
 //! # }
 //! #
+//! # #[cfg(feature = "std")]
 //! # #[derive(Debug)]
 //! # struct LogFileChild {
 //! #     inner: Box<dyn ChildWrapper>,
 //! #     thread: Option<JoinHandle<()>>,
 //! # }
 //! #
+//! # #[cfg(feature = "std")]
 //! # impl ChildWrapper for LogFileChild {
 //! #     fn inner(&self) -> &dyn ChildWrapper {
 //! #         &*self.inner
@@ -407,6 +441,7 @@
 //! #     }
 //! # }
 //! #
+//! # #[cfg(feature = "std")]
 //! fn main() -> Result<(), Box<dyn Error>> {
 //!     #[cfg(windows)]
 //!     let mut command = CommandWrap::with_new("cmd", |command| {
@@ -430,6 +465,8 @@
 //!
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
 //! # Features
@@ -440,17 +477,34 @@
 //!
 //! - `std`: enables the std-based API.
 //! - `tokio1`: enables the Tokio-based API.
+//! - `futures`: enables the executor-agnostic async API (Unix-only), for smol and other runtimes.
 //!
-//! Both can exist at the same time, but generally you'll want to use one or the other.
+//! They can all exist at the same time, but generally you'll want to use only one.
 //!
 //! ## Wrappers
 //!
+//! - `asciicast`: enables the wrapper recording the child's terminal session to an asciicast file
+//!   (Unix-only).
 //! - `creation-flags`: **default**, enables the creation flags wrapper (Windows-only).
+//! - `environment`: enables the environment policy wrapper.
+//! - `expect`: enables the wrapper scripting interactions with the child, like `expect(1)`.
+//! - `foreground`: enables the foreground (terminal job control) wrapper (Unix-only).
+//! - `init`: enables the wrapper acting as a minimal init for the child, reaping orphans
+//!   (Unix-only).
 //! - `job-object`: **default**, enables the job object wrapper (Windows-only).
-//! - `kill-on-drop`: **default**, enables the kill on drop wrapper (Tokio-only).
+//! - `kill-on-drop`: **default**, enables the kill on drop wrapper.
+//! - `kill-tree`: enables the wrapper killing the whole process tree (Linux-only).
+//! - `observe`: enables the lifecycle observer wrapper.
+//! - `oom-score-adj`: enables the OOM score adjustment wrapper (Linux-only).
 //! - `process-group`: **default**, enables the process group wrapper (Unix-only).
 //! - `process-session`: **default**, enables the process session wrapper (Unix-only).
+//! - `pty`: enables the helper relaying our terminal to a child on a pseudo-terminal (Unix-only).
 //! - `reset-sigmask`: enables the sigmask reset wrapper (Unix-only).
+//! - `scheduling`: enables the scheduling (niceness, I/O priority, CPU affinity) wrapper
+//!   (Linux-only).
+//! - `trace-output`: enables the wrapper emitting child output as tracing events.
+//! - `virtual-terminal`: enables the wrapper keeping a virtual screen of the child's terminal
+//!   (Unix-only).
 //!
 #![doc(html_favicon_url = "https://watchexec.github.io/logo:command-group.svg")]
 #![doc(html_logo_url = "https://watchexec.github.io/logo:command-group.svg")]
@@ -459,12 +513,82 @@
 
 pub(crate) mod generic_wrap;
 
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+mod constraint;
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+pub use constraint::{ConstraintKind, WrapperConstraint};
+
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+mod describe;
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+pub use describe::{ChildLayers, CommandPlan, WrapperPlan};
+
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+mod outcome;
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+pub use outcome::ExitOutcome;
+
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+mod output_events;
+#[cfg(any(feature = "std", feature = "tokio1", all(unix, feature = "futures")))]
+pub use output_events::{Framing, OutputEvent};
+
+#[cfg(all(feature = "observe", any(feature = "std", feature = "tokio1")))]
+mod observer;
+#[cfg(all(feature = "observe", any(feature = "std", feature = "tokio1")))]
+pub use observer::Observer;
+
 #[cfg(feature = "std")]
 pub mod std;
 
 #[cfg(feature = "tokio1")]
 pub mod tokio;
 
+#[cfg(all(unix, feature = "futures"))]
+pub mod futures;
+
+#[cfg(all(unix, feature = "asciicast", any(feature = "std", feature = "tokio1")))]
+mod asciicast;
+#[cfg(all(unix, feature = "asciicast", any(feature = "std", feature = "tokio1")))]
+pub use asciicast::replay_asciicast;
+
+#[cfg(all(feature = "environment", any(feature = "std", feature = "tokio1")))]
+mod environment;
+
+#[cfg(all(feature = "expect", any(feature = "std", feature = "tokio1")))]
+mod expect;
+#[cfg(all(feature = "expect", any(feature = "std", feature = "tokio1")))]
+pub use expect::{Exchange, ExpectMatch};
+
+#[cfg(all(feature = "kill-on-drop", any(feature = "std", feature = "tokio1")))]
+mod kill_on_drop;
+
+#[cfg(all(
+	unix,
+	feature = "process-group",
+	any(feature = "std", feature = "tokio1", feature = "futures")
+))]
+mod process_group;
+
+#[cfg(all(unix, any(feature = "std", feature = "tokio1")))]
+mod unix;
+
+#[cfg(all(
+	unix,
+	feature = "virtual-terminal",
+	any(feature = "std", feature = "tokio1")
+))]
+mod virtual_terminal;
+#[cfg(all(
+	unix,
+	feature = "virtual-terminal",
+	any(feature = "std", feature = "tokio1")
+))]
+pub use virtual_terminal::{Cell, CellAttrs, Color, Screen};
+
+#[cfg(all(feature = "trace-output", any(feature = "std", feature = "tokio1")))]
+mod trace_output;
+
 #[cfg(all(
 	windows,
 	feature = "job-object",
@@ -0,0 +1,67 @@
+use std::{
+	fmt::Debug,
+	io::Error,
+	process::{Command, ExitStatus},
+	time::Duration,
+};
+
+/// A receiver of lifecycle events for wrapped commands.
+///
+/// Observers are attached to a command with the `Observe` wrapper of either frontend, and are
+/// shared between the command wrapper and its child wrapper, so the same observer sees the whole
+/// lifecycle of the process. The same observer can also be attached to many commands, to gather
+/// metrics or audit logs across a program.
+///
+/// All methods have default no-op implementations, so you only need to implement the ones you're
+/// interested in. They're called synchronously, so they should be quick.
+///
+/// ```rust
+/// use process_wrap::Observer;
+/// use std::{process::ExitStatus, sync::atomic::{AtomicUsize, Ordering}, time::Duration};
+///
+/// #[derive(Debug, Default)]
+/// struct Metrics {
+///     spawned: AtomicUsize,
+///     failed: AtomicUsize,
+/// }
+///
+/// impl Observer for Metrics {
+///     fn on_spawned(&self, _pid: u32) {
+///         self.spawned.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn on_exit(&self, _pid: u32, status: ExitStatus, _duration: Duration) {
+///         if !status.success() {
+///             self.failed.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+/// ```
+pub trait Observer: Debug + Send + Sync {
+	/// Called before the command is spawned.
+	///
+	/// The command is as configured by the wrappers which ran their `pre_spawn` hooks before the
+	/// `Observe` wrapper did.
+	fn on_pre_spawn(&self, _command: &Command) {}
+
+	/// Called once the command has been spawned, with the process ID of the child.
+	fn on_spawned(&self, _pid: u32) {}
+
+	/// Called when a signal is sent to the child through the wrapper stack.
+	///
+	/// This is only called on Unix.
+	fn on_signal(&self, _pid: u32, _signal: i32) {}
+
+	/// Called when the child is killed through the wrapper stack.
+	fn on_kill(&self, _pid: u32) {}
+
+	/// Called once when the child is first observed to have exited.
+	///
+	/// The duration is measured from just after the spawn.
+	fn on_exit(&self, _pid: u32, _status: ExitStatus, _duration: Duration) {}
+
+	/// Called when spawning fails, or when an operation on the child returns an error.
+	///
+	/// The `pid` is `None` if the error happened while spawning.
+	fn on_error(&self, _pid: Option<u32>, _error: &Error) {}
+}
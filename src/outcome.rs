@@ -0,0 +1,128 @@
+use std::{fmt, process::ExitStatus};
+
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+
+/// How a child process ended, in more structured terms than an [`ExitStatus`].
+///
+/// This is obtained from the `wait_outcome()` method of either frontend's `ChildWrapper`, or can be
+/// converted from an `ExitStatus` directly (in which case it will never be `KilledByWrapper`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExitOutcome {
+	/// The process exited on its own with this code.
+	Exited(i32),
+
+	/// The process was terminated by a signal that didn't come from a wrapper.
+	///
+	/// This is only produced on Unix.
+	Signaled {
+		/// The signal number.
+		signal: i32,
+
+		/// Whether the process dumped core.
+		core_dumped: bool,
+	},
+
+	/// The process was killed by one of the wrappers in the stack.
+	///
+	/// Wrappers that kill the child report why through `ChildWrapper::kill_reason()`.
+	KilledByWrapper {
+		/// The raw exit status.
+		status: ExitStatus,
+
+		/// Why the wrapper killed the child.
+		reason: String,
+	},
+}
+
+impl ExitOutcome {
+	/// Build an outcome from an exit status and the reason a wrapper killed the child, if any.
+	///
+	/// The kill reason is only taken into account if the child didn't exit on its own, as it may
+	/// have completed before the kill reached it: on Unix, that's if it was terminated by a signal,
+	/// and on Windows, where killing a process sets its exit code, if it didn't exit successfully.
+	pub fn new(status: ExitStatus, kill_reason: Option<String>) -> Self {
+		match kill_reason {
+			Some(reason) if killed(status) => Self::KilledByWrapper { status, reason },
+			_ => Self::from(status),
+		}
+	}
+
+	/// The exit code, as a shell would report it.
+	///
+	/// That is the exit code if the process exited, and 128 plus the signal number if it was
+	/// terminated by a signal. On Windows, killing a process sets its exit code, so that is used.
+	pub fn code(&self) -> i32 {
+		match self {
+			Self::Exited(code) => *code,
+			Self::Signaled { signal, .. } => 128 + signal,
+			Self::KilledByWrapper { status, .. } => status_code(*status),
+		}
+	}
+
+	/// The signal that terminated the process, if any.
+	pub fn signal(&self) -> Option<i32> {
+		match self {
+			Self::Exited(_) => None,
+			Self::Signaled { signal, .. } => Some(*signal),
+			#[cfg(unix)]
+			Self::KilledByWrapper { status, .. } => status.signal(),
+			#[cfg(not(unix))]
+			Self::KilledByWrapper { .. } => None,
+		}
+	}
+
+	/// Whether the process exited successfully.
+	pub fn success(&self) -> bool {
+		matches!(self, Self::Exited(0))
+	}
+}
+
+impl From<ExitStatus> for ExitOutcome {
+	fn from(status: ExitStatus) -> Self {
+		#[cfg(unix)]
+		if let Some(signal) = status.signal() {
+			return Self::Signaled {
+				signal,
+				core_dumped: status.core_dumped(),
+			};
+		}
+
+		Self::Exited(status_code(status))
+	}
+}
+
+impl fmt::Display for ExitOutcome {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Exited(code) => write!(f, "exited with code {code}"),
+			Self::Signaled {
+				signal,
+				core_dumped: false,
+			} => write!(f, "terminated by signal {signal}"),
+			Self::Signaled {
+				signal,
+				core_dumped: true,
+			} => write!(f, "terminated by signal {signal} (core dumped)"),
+			Self::KilledByWrapper { reason, .. } => write!(f, "killed: {reason}"),
+		}
+	}
+}
+
+fn killed(status: ExitStatus) -> bool {
+	#[cfg(unix)]
+	return status.signal().is_some();
+
+	#[cfg(not(unix))]
+	return !status.success();
+}
+
+fn status_code(status: ExitStatus) -> i32 {
+	#[cfg(unix)]
+	if let Some(signal) = status.signal() {
+		return 128 + signal;
+	}
+
+	// the only other case on unix is stopped/continued statuses, which wait() never returns
+	status.code().unwrap_or(1)
+}
@@ -0,0 +1,90 @@
+use std::{collections::VecDeque, process::ExitStatus};
+
+/// An event from a running child, as produced by the `output_events()` method of the frontends'
+/// `dyn ChildWrapper`.
+///
+/// Output is bytes rather than strings, as programs can write anything at all; use
+/// [`String::from_utf8_lossy`] to display it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputEvent {
+	/// A line or chunk of the child's stdout, depending on the [`Framing`].
+	Stdout(Vec<u8>),
+
+	/// A line or chunk of the child's stderr, depending on the [`Framing`].
+	Stderr(Vec<u8>),
+
+	/// The child exited, after both of its outputs were closed.
+	///
+	/// This is always the last event.
+	Exited(ExitStatus),
+}
+
+/// How a child's output is split into [`OutputEvent`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Framing {
+	/// One event per line, without the line ending (`\n` or `\r\n`).
+	///
+	/// A partial line is held back until the rest of it arrives. If the output ends without a
+	/// final line ending, what's left is emitted as the last line.
+	#[default]
+	Lines,
+
+	/// One event per read from the pipe, as soon as it arrives.
+	///
+	/// Chunks can start or end anywhere, including in the middle of a line or of a UTF-8 character.
+	Chunks,
+}
+
+/// How much to read from a pipe at a time.
+pub(crate) const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Splits bytes read from a pipe into frames.
+#[derive(Debug)]
+pub(crate) struct Framer {
+	framing: Framing,
+	partial: Vec<u8>,
+	ready: VecDeque<Vec<u8>>,
+}
+
+impl Framer {
+	pub fn new(framing: Framing) -> Self {
+		Self {
+			framing,
+			partial: Vec::new(),
+			ready: VecDeque::new(),
+		}
+	}
+
+	/// Add bytes read from the pipe.
+	pub fn push(&mut self, data: &[u8]) {
+		match self.framing {
+			Framing::Chunks => {
+				if !data.is_empty() {
+					self.ready.push_back(data.to_vec());
+				}
+			}
+			Framing::Lines => {
+				for piece in data.split_inclusive(|&b| b == b'\n') {
+					self.partial.extend_from_slice(piece);
+					if let Some(line) = self.partial.strip_suffix(b"\n") {
+						let line = line.strip_suffix(b"\r").unwrap_or(line).to_vec();
+						self.ready.push_back(line);
+						self.partial.clear();
+					}
+				}
+			}
+		}
+	}
+
+	/// Mark the end of the output, releasing any partial line.
+	pub fn finish(&mut self) {
+		if !self.partial.is_empty() {
+			self.ready.push_back(std::mem::take(&mut self.partial));
+		}
+	}
+
+	/// Take the next complete frame.
+	pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+		self.ready.pop_front()
+	}
+}
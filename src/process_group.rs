@@ -0,0 +1,58 @@
+//! Statuses of the members reaped by the `ProcessGroupChild` wrappers.
+
+use std::{
+	fmt,
+	process::ExitStatus,
+	sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Callback for each group member as it's reaped.
+type OnReaped = Arc<dyn Fn(u32, ExitStatus) + Send + Sync>;
+
+/// The group members reaped so far, shared with background reapers.
+#[derive(Clone, Default)]
+pub(crate) struct ReapedMembers(Arc<Mutex<Reaped>>);
+
+#[derive(Default)]
+struct Reaped {
+	statuses: Vec<(u32, ExitStatus)>,
+	on_reaped: Option<OnReaped>,
+}
+
+impl ReapedMembers {
+	/// Record a reaped member, and call the callback if there's one.
+	pub fn record(&self, pid: u32, status: ExitStatus) {
+		let on_reaped = {
+			let mut reaped = self.lock();
+			reaped.statuses.push((pid, status));
+			reaped.on_reaped.clone()
+		};
+
+		// outside the lock, in case the callback takes its time
+		if let Some(on_reaped) = on_reaped {
+			on_reaped(pid, status);
+		}
+	}
+
+	pub fn statuses(&self) -> Vec<(u32, ExitStatus)> {
+		self.lock().statuses.clone()
+	}
+
+	pub fn set_callback(&self, on_reaped: impl Fn(u32, ExitStatus) + Send + Sync + 'static) {
+		self.lock().on_reaped = Some(Arc::new(on_reaped));
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Reaped> {
+		self.0.lock().unwrap_or_else(|err| err.into_inner())
+	}
+}
+
+impl fmt::Debug for ReapedMembers {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let reaped = self.lock();
+		f.debug_struct("ReapedMembers")
+			.field("statuses", &reaped.statuses)
+			.field("on_reaped", &reaped.on_reaped.is_some())
+			.finish()
+	}
+}
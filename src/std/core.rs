@@ -1,7 +1,15 @@
 use std::{
-	any::Any,
-	io::{Read, Result},
+	any::{Any, TypeId},
+	io::{Error, ErrorKind, Read, Result},
+	iter,
 	process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Output},
+	sync::mpsc::{SyncSender, sync_channel},
+	thread,
+};
+
+use crate::{
+	ChildLayers, ExitOutcome, Framing, OutputEvent,
+	output_events::{CHUNK_SIZE, Framer},
 };
 
 #[cfg(unix)]
@@ -70,6 +78,13 @@ pub trait ChildWrapper: Any + std::fmt::Debug + Send + Sync {
 		None
 	}
 
+	/// The name of the wrapper, for `layers()` and logs.
+	///
+	/// By default this is the wrapper's type name.
+	fn name(&self) -> &'static str {
+		std::any::type_name::<Self>()
+	}
+
 	/// Obtain the `Child`'s stdin.
 	///
 	/// By default this is a passthrough to the wrapped child.
@@ -143,6 +158,27 @@ pub trait ChildWrapper: Any + std::fmt::Debug + Send + Sync {
 		self.inner_mut().wait()
 	}
 
+	/// Wait for the `Child` to exit and return how it ended.
+	///
+	/// This is like [`wait()`](ChildWrapper::wait), but returns an [`ExitOutcome`], which tells
+	/// apart exiting with a code, being terminated by a signal, and being killed by a wrapper.
+	///
+	/// By default this calls `wait()` and then [`kill_reason()`](ChildWrapper::kill_reason).
+	fn wait_outcome(&mut self) -> Result<ExitOutcome> {
+		let status = self.wait()?;
+		Ok(ExitOutcome::new(status, self.kill_reason()))
+	}
+
+	/// Why a wrapper killed the `Child`, if one did.
+	///
+	/// Wrappers which kill the child should override this to say so, and pass through to the
+	/// wrapped child otherwise. This is used by [`wait_outcome()`](ChildWrapper::wait_outcome).
+	///
+	/// By default this is a passthrough to the wrapped child.
+	fn kill_reason(&self) -> Option<String> {
+		self.inner().kill_reason()
+	}
+
 	/// Wait for the `Child` to exit and return its exit status and outputs.
 	///
 	/// Note that this method reads the child's stdout and stderr to completion into memory.
@@ -170,7 +206,7 @@ pub trait ChildWrapper: Any + std::fmt::Debug + Send + Sync {
 				res.unwrap();
 			}
 			(Some(out), Some(err)) => {
-				let res = read2(out, &mut stdout, err, &mut stderr);
+				let res = read3(None, Some((out, &mut stdout)), Some((err, &mut stderr)));
 				res.unwrap();
 			}
 		}
@@ -183,6 +219,44 @@ pub trait ChildWrapper: Any + std::fmt::Debug + Send + Sync {
 		})
 	}
 
+	/// Write input to the `Child`'s stdin, then wait for it to exit and return its exit status and
+	/// outputs.
+	///
+	/// This is like [`wait_with_output()`](ChildWrapper::wait_with_output), but writes `input` to
+	/// stdin at the same time as reading from stdout and stderr, so that a child which writes output
+	/// before it has read all of its input can't deadlock on full pipe buffers. Stdin is closed once
+	/// all of the input is written, or as soon as the child closes it.
+	///
+	/// On Unix, this writes and reads simultaneously with a single poll loop. On other platforms, it
+	/// writes from a separate thread, and reads stdout first, then stderr.
+	///
+	/// Returns an error without waiting if there's input but stdin isn't piped.
+	fn wait_with_input_output(mut self: Box<Self>, input: &[u8]) -> Result<Output>
+	where
+		Self: 'static,
+	{
+		let stdin = self.stdin().take();
+		if stdin.is_none() && !input.is_empty() {
+			return Err(Error::new(ErrorKind::InvalidInput, "stdin isn't piped"));
+		}
+
+		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+		read3(
+			stdin
+				.filter(|_| !input.is_empty())
+				.map(|stdin| (stdin, input)),
+			self.stdout().take().map(|out| (out, &mut stdout)),
+			self.stderr().take().map(|err| (err, &mut stderr)),
+		)?;
+
+		let status = self.wait()?;
+		Ok(Output {
+			status,
+			stdout,
+			stderr,
+		})
+	}
+
 	/// Send a signal to the `Child`.
 	///
 	/// This method is only available on Unix. It doesn't exist on std's `Child`, nor on Tokio's. It
@@ -233,6 +307,9 @@ impl ChildWrapper for Child {
 	fn wait(&mut self) -> Result<ExitStatus> {
 		Child::wait(self)
 	}
+	fn kill_reason(&self) -> Option<String> {
+		None
+	}
 	#[cfg(unix)]
 	fn signal(&self, sig: i32) -> Result<()> {
 		kill(
@@ -252,11 +329,82 @@ impl dyn ChildWrapper {
 		self.downcast_ref::<Child>().is_some()
 	}
 
+	/// List the layers of the wrapper chain, from this wrapper down to the bottom of the chain.
+	///
+	/// The bottom is normally the [`Child`], but can also be an adapter.
+	pub fn layers(&self) -> ChildLayers {
+		let mut layers = vec![self.name()];
+		let mut inner = self;
+		loop {
+			let next = inner.inner();
+			if layer_id(inner) == layer_id(next) {
+				break ChildLayers(layers);
+			}
+			layers.push(next.name());
+			inner = next;
+		}
+	}
+
+	/// Find the outermost layer of the wrapper chain of a given type.
+	///
+	/// This walks `inner()` from this wrapper down to the bottom of the chain, so it can reach a
+	/// wrapper underneath others, e.g. a `ProcessGroupChild` under a custom wrapper.
+	///
+	/// Returns `None` if there's no layer of this type.
+	pub fn find_layer<T: ChildWrapper>(&self) -> Option<&T> {
+		// UNWRAP: layer_depth() found a T at this depth
+		self.layer_depth::<T>()
+			.map(|depth| self.layer(depth).downcast_ref().unwrap())
+	}
+
+	/// Find the outermost layer of the wrapper chain of a given type, mutably.
+	///
+	/// Like [`find_layer()`](Self::find_layer), this walks `inner_mut()` down the chain.
+	pub fn find_layer_mut<T: ChildWrapper>(&mut self) -> Option<&mut T> {
+		let depth = self.layer_depth::<T>()?;
+		let mut inner = self;
+		for _ in 0..depth {
+			inner = inner.inner_mut();
+		}
+
+		// UNWRAP: layer_depth() found a T at this depth
+		Some((inner as &mut dyn Any).downcast_mut().unwrap())
+	}
+
+	/// How many layers down the outermost layer of a given type is.
+	fn layer_depth<T: ChildWrapper>(&self) -> Option<usize> {
+		let mut inner = self;
+		for depth in 0.. {
+			if inner.downcast_ref::<T>().is_some() {
+				return Some(depth);
+			}
+
+			let next = inner.inner();
+			if layer_id(inner) == layer_id(next) {
+				break;
+			}
+			inner = next;
+		}
+		None
+	}
+
+	fn layer(&self, depth: usize) -> &dyn ChildWrapper {
+		let mut inner = self;
+		for _ in 0..depth {
+			inner = inner.inner();
+		}
+		inner
+	}
+
 	/// Obtain a reference to the underlying [`Child`].
+	///
+	/// Panics if the bottom of the wrapper chain isn't a `Child`, as is the case for adapters.
 	pub fn inner_child(&self) -> &Child {
 		let mut inner = self;
 		while !inner.is_raw_child() {
-			inner = inner.inner();
+			let next = inner.inner();
+			assert_not_bottom(layer_id(inner), next);
+			inner = next;
 		}
 
 		// UNWRAP: we've just checked that it's Some with is_raw_child()
@@ -265,11 +413,17 @@ impl dyn ChildWrapper {
 
 	/// Obtain a mutable reference to the underlying [`Child`].
 	///
+	/// Like [`inner_child()`](Self::inner_child), this panics if there's no `Child` at the bottom.
+	///
+	/// # Safety
+	///
 	/// Modifying the raw child may be unsound depending on the layering of wrappers.
 	pub unsafe fn inner_child_mut(&mut self) -> &mut Child {
 		let mut inner = self;
 		while !inner.is_raw_child() {
+			let prev = layer_id(inner);
 			inner = inner.inner_mut();
+			assert_not_bottom(prev, inner);
 		}
 
 		// UNWRAP: we've just checked that with is_raw_child()
@@ -278,24 +432,119 @@ impl dyn ChildWrapper {
 
 	/// Obtain the underlying [`Child`].
 	///
+	/// Like [`inner_child()`](Self::inner_child), this panics if there's no `Child` at the bottom.
+	///
+	/// # Safety
+	///
 	/// Unwrapping everything may be unsound depending on the state of the wrappers.
 	pub unsafe fn into_inner_child(self: Box<Self>) -> Child {
 		let mut inner = self;
 		while !inner.is_raw_child() {
+			let prev = layer_id(&*inner);
 			inner = inner.into_inner();
+			assert_not_bottom(prev, &*inner);
 		}
 
 		// UNWRAP: we've just checked that with is_raw_child()
 		*(inner as Box<dyn Any>).downcast().unwrap()
 	}
+
+	/// Iterate over the child's output as it runs, and then its exit.
+	///
+	/// This takes the child's stdout and stderr, if they're piped, and yields their contents as
+	/// [`OutputEvent`]s split according to the [`Framing`], in the order they're read. Once both are
+	/// closed, it waits for the child with the wrapper chain's `wait()`, so wrappers can do their
+	/// cleanup as usual, and yields [`OutputEvent::Exited`] last.
+	///
+	/// The outputs are read on background threads, which pause while too many events are waiting
+	/// to be consumed. Dropping the iterator stops reading, but doesn't kill the child.
+	pub fn output_events(
+		mut self: Box<Self>,
+		framing: Framing,
+	) -> impl Iterator<Item = Result<OutputEvent>> + Send {
+		let (tx, rx) = sync_channel(OUTPUT_EVENTS_BACKLOG);
+		if let Some(stdout) = self.stdout().take() {
+			read_events(stdout, framing, OutputEvent::Stdout, tx.clone());
+		}
+		if let Some(stderr) = self.stderr().take() {
+			read_events(stderr, framing, OutputEvent::Stderr, tx.clone());
+		}
+		drop(tx);
+
+		let mut child = Some(self);
+		iter::from_fn(move || match rx.recv() {
+			Ok(event) => Some(event),
+			// both readers are done
+			Err(_) => child
+				.take()
+				.map(|mut child| child.wait().map(OutputEvent::Exited)),
+		})
+	}
+}
+
+/// How many output events can be waiting to be consumed before the readers pause.
+const OUTPUT_EVENTS_BACKLOG: usize = 64;
+
+/// Read a pipe to the end on a background thread, sending it as events.
+fn read_events(
+	mut pipe: impl Read + Send + 'static,
+	framing: Framing,
+	event: fn(Vec<u8>) -> OutputEvent,
+	tx: SyncSender<Result<OutputEvent>>,
+) {
+	let spawn_tx = tx.clone();
+	let reader = move || {
+		let mut framer = Framer::new(framing);
+		let mut buf = vec![0; CHUNK_SIZE];
+		loop {
+			let done = match pipe.read(&mut buf) {
+				Ok(0) => {
+					framer.finish();
+					Some(Ok(()))
+				}
+				Ok(n) => {
+					framer.push(&buf[..n]);
+					None
+				}
+				Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+				// stop reading after an error, once what was read before it is out
+				Err(err) => {
+					framer.finish();
+					Some(Err(err))
+				}
+			};
+
+			while let Some(frame) = framer.next_frame() {
+				if tx.send(Ok(event(frame))).is_err() {
+					// the iterator was dropped
+					return;
+				}
+			}
+			if let Some(done) = done {
+				if let Err(err) = done {
+					tx.send(Err(err)).ok();
+				}
+				return;
+			}
+		}
+	};
+
+	if let Err(err) = thread::Builder::new()
+		.name("process-wrap output".into())
+		.spawn(reader)
+	{
+		spawn_tx.send(Err(err)).ok();
+	}
 }
 
+/// Write to stdin while reading from stdout and stderr, until all are done.
+///
+/// Stdin is closed once all of the input is written, or if the child closed its end.
 #[cfg(unix)]
-fn read2(
-	mut out_r: ChildStdout,
-	out_v: &mut Vec<u8>,
-	mut err_r: ChildStderr,
-	err_v: &mut Vec<u8>,
+fn read3(
+	mut input: Option<(ChildStdin, &[u8])>,
+	mut out: Option<(ChildStdout, &mut Vec<u8>)>,
+	mut err: Option<(ChildStderr, &mut Vec<u8>)>,
 ) -> Result<()> {
 	use nix::{
 		errno::Errno,
@@ -303,37 +552,99 @@ fn read2(
 		poll::{PollFd, PollFlags, PollTimeout, poll},
 	};
 	use std::{
-		io::Error,
-		os::fd::{AsRawFd, BorrowedFd},
+		io::Write,
+		os::fd::{AsFd, AsRawFd, BorrowedFd},
 	};
 
-	let out_fd = out_r.as_raw_fd();
-	let err_fd = err_r.as_raw_fd();
-	// SAFETY: these are dropped at the same time as all other FDs here
-	let out_bfd = unsafe { BorrowedFd::borrow_raw(out_fd) };
-	let err_bfd = unsafe { BorrowedFd::borrow_raw(err_fd) };
-
-	set_nonblocking(out_bfd, true)?;
-	set_nonblocking(err_bfd, true)?;
+	if let Some((stdin, _)) = &input {
+		set_nonblocking(stdin.as_fd(), true)?;
+	}
+	if let Some((stdout, _)) = &out {
+		set_nonblocking(stdout.as_fd(), true)?;
+	}
+	if let Some((stderr, _)) = &err {
+		set_nonblocking(stderr.as_fd(), true)?;
+	}
 
-	let mut fds = [
-		PollFd::new(out_bfd, PollFlags::POLLIN),
-		PollFd::new(err_bfd, PollFlags::POLLIN),
-	];
+	while input.is_some() || out.is_some() || err.is_some() {
+		let [in_ready, out_ready, err_ready] = {
+			let mut fds = Vec::with_capacity(3);
+			let watched = [
+				watch(
+					&mut fds,
+					input.as_ref().map(|(w, _)| w.as_fd()),
+					PollFlags::POLLOUT,
+				),
+				watch(
+					&mut fds,
+					out.as_ref().map(|(r, _)| r.as_fd()),
+					PollFlags::POLLIN,
+				),
+				watch(
+					&mut fds,
+					err.as_ref().map(|(r, _)| r.as_fd()),
+					PollFlags::POLLIN,
+				),
+			];
+
+			match poll(&mut fds, PollTimeout::NONE) {
+				Ok(_) => {}
+				Err(Errno::EINTR) => continue,
+				Err(e) => return Err(e.into()),
+			}
 
-	loop {
-		poll(&mut fds, PollTimeout::NONE)?;
+			watched.map(|index| {
+				index.is_some_and(|i| fds[i].revents().is_some_and(|events| !events.is_empty()))
+			})
+		};
 
-		if fds[0].revents().is_some() && read(&mut out_r, out_v)? {
-			set_nonblocking(err_bfd, false)?;
-			return err_r.read_to_end(err_v).map(drop);
+		if in_ready {
+			if let Some((stdin, rest)) = input.as_mut() {
+				let remaining: &[u8] = rest;
+				match stdin.write(remaining) {
+					Ok(n) => *rest = &remaining[n..],
+					Err(e)
+						if e.kind() == ErrorKind::WouldBlock
+							|| e.kind() == ErrorKind::Interrupted => {}
+					// the child won't read any more, which isn't our problem
+					Err(e) if e.kind() == ErrorKind::BrokenPipe => *rest = &[],
+					Err(e) => return Err(e),
+				}
+			}
+			if input.as_ref().is_some_and(|(_, rest)| rest.is_empty()) {
+				input = None;
+			}
+		}
+		if out_ready {
+			if let Some((stdout, dst)) = out.as_mut() {
+				if read(stdout, dst)? {
+					out = None;
+				}
+			}
 		}
-		if fds[1].revents().is_some() && read(&mut err_r, err_v)? {
-			set_nonblocking(out_bfd, false)?;
-			return out_r.read_to_end(out_v).map(drop);
+		if err_ready {
+			if let Some((stderr, dst)) = err.as_mut() {
+				if read(stderr, dst)? {
+					err = None;
+				}
+			}
 		}
 	}
 
+	return Ok(());
+
+	/// Add a pipe to poll if it's still open, returning its index.
+	fn watch<'fd>(
+		fds: &mut Vec<PollFd<'fd>>,
+		fd: Option<BorrowedFd<'fd>>,
+		flags: PollFlags,
+	) -> Option<usize> {
+		fd.map(|fd| {
+			fds.push(PollFd::new(fd, flags));
+			fds.len() - 1
+		})
+	}
+
 	fn read(r: &mut impl Read, dst: &mut Vec<u8>) -> Result<bool> {
 		match r.read_to_end(dst) {
 			Ok(_) => Ok(true),
@@ -371,17 +682,51 @@ fn read2(
 }
 
 // if you're reading this code and despairing, we'd love
-// your contribution of a proper read2 for your platform!
+// your contribution of a proper read3 for your platform!
 #[cfg(not(unix))]
-fn read2(
-	mut out_r: ChildStdout,
-	out_v: &mut Vec<u8>,
-	mut err_r: ChildStderr,
-	err_v: &mut Vec<u8>,
+fn read3(
+	input: Option<(ChildStdin, &[u8])>,
+	out: Option<(ChildStdout, &mut Vec<u8>)>,
+	err: Option<(ChildStderr, &mut Vec<u8>)>,
 ) -> Result<()> {
-	out_r.read_to_end(out_v)?;
-	err_r.read_to_end(err_v)?;
-	Ok(())
+	use std::{io::Write, thread};
+
+	thread::scope(|scope| {
+		let writer = input.map(|(mut stdin, input)| {
+			scope.spawn(move || match stdin.write_all(input) {
+				Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+				res => res,
+			})
+		});
+
+		if let Some((mut out_r, out_v)) = out {
+			out_r.read_to_end(out_v)?;
+		}
+		if let Some((mut err_r, err_v)) = err {
+			err_r.read_to_end(err_v)?;
+		}
+
+		writer.map_or(Ok(()), |writer| {
+			writer.join().expect("stdin writer thread panicked")
+		})
+	})
+}
+
+/// Identify a layer, to find the bottom of the chain, which returns itself from `inner()`.
+///
+/// The type is included as a wrapper's first field may be at the same address as the wrapper.
+fn layer_id(layer: &dyn ChildWrapper) -> (*const (), TypeId) {
+	(
+		(layer as *const dyn ChildWrapper).cast(),
+		(layer as &dyn Any).type_id(),
+	)
+}
+
+fn assert_not_bottom(prev: (*const (), TypeId), next: &dyn ChildWrapper) {
+	assert!(
+		prev != layer_id(next),
+		"the bottom of the wrapper chain isn't a Child"
+	);
 }
 
 const _: () = {
@@ -1,7 +1,11 @@
 use std::{io::Result, os::windows::process::CommandExt, process::Command};
 
+#[cfg(feature = "job-object")]
+use windows::Win32::System::Threading::CREATE_SUSPENDED;
 use windows::Win32::System::Threading::PROCESS_CREATION_FLAGS;
 
+#[cfg(feature = "job-object")]
+use super::WrapperConstraint;
 use super::{CommandWrap, CommandWrapper};
 
 /// Shim wrapper which sets Windows process creation flags.
@@ -15,10 +19,21 @@ use super::{CommandWrap, CommandWrapper};
 /// When both `CreationFlags` and `JobObject` are used together, either:
 /// - `CreationFlags` must come first, or
 /// - `CreationFlags` must include `CREATE_SUSPENDED`
+///
+/// If neither is the case, `CreationFlags` is moved before `JobObject` at spawn.
 #[derive(Clone, Copy, Debug)]
 pub struct CreationFlags(pub PROCESS_CREATION_FLAGS);
 
 impl CommandWrapper for CreationFlags {
+	#[cfg(feature = "job-object")]
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		if self.0.contains(CREATE_SUSPENDED) {
+			Vec::new()
+		} else {
+			vec![WrapperConstraint::before::<super::JobObject>().reorderable()]
+		}
+	}
+
 	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
 		command.creation_flags((self.0).0);
 		Ok(())
@@ -0,0 +1,242 @@
+use std::{
+	io::{Error, ErrorKind, Read, Result, Write},
+	process::{ChildStdin, Command, ExitStatus, Stdio},
+	sync::{
+		Mutex,
+		mpsc::{Receiver, RecvTimeoutError, Sender, channel},
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::expect::{Exchange, ExpectMatch, Expecter};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Wrapper which scripts an interaction with the child, like `expect(1)`.
+///
+/// This pipes the child's stdin and stdout, and optionally merges its stderr into the output to
+/// match against. The child wrapper, [`ExpectChild`], can then wait for output matching a regular
+/// expression with [`expect()`](ExpectChild::expect), send input with
+/// [`send_line()`](ExpectChild::send_line), and wait for the output to end with
+/// [`expect_eof()`](ExpectChild::expect_eof). Everything sent and received is kept in a
+/// [transcript](ExpectChild::transcript).
+///
+/// When an expectation isn't met in time, the error includes the most recent unmatched output.
+///
+/// As it sets the command's stdio, it can't be used with the wrappers which run the child on a
+/// pseudo-terminal: `Asciicast`, `VirtualTerminal`, or `PtySession`.
+///
+/// The output is read on background threads, so the child never blocks on a full pipe.
+///
+/// ```rust,no_run
+/// use process_wrap::std::*;
+/// use std::time::Duration;
+///
+/// let mut child = CommandWrap::with_new("python3", |command| { command.arg("-i"); })
+///     .wrap(Expect::new().with_stderr())
+///     .spawn()?;
+/// let session = child.find_layer_mut::<ExpectChild>().unwrap();
+/// session.expect(">>> ", Duration::from_secs(5))?;
+/// session.send_line("print(6 * 7)")?;
+/// let answer = session.expect(r"\d+", Duration::from_secs(5))?;
+/// assert_eq!(answer.matched, "42");
+/// session.send_line("exit()")?;
+/// session.expect_eof(Duration::from_secs(5))?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Expect {
+	stderr: bool,
+}
+
+impl Expect {
+	/// Create an expect wrapper which matches against the child's stdout.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Also match against the child's stderr, merged with stdout in the order it's read.
+	///
+	/// Returns `self` for chaining.
+	pub fn with_stderr(mut self) -> Self {
+		self.stderr = true;
+		self
+	}
+}
+
+/// Wrapper for `Child` which scripts an interaction with it.
+///
+/// See [`Expect`] for details.
+#[derive(Debug)]
+pub struct ExpectChild {
+	inner: Box<dyn ChildWrapper>,
+	stdin: Option<ChildStdin>,
+	// only to make this Sync, as it's only used through &mut self
+	output: Mutex<Receiver<Result<Vec<u8>>>>,
+	expecter: Expecter,
+}
+
+impl ExpectChild {
+	/// Wait for output matching a regular expression.
+	///
+	/// The pattern is matched against the output received since the previous match, and the
+	/// output up to the end of the match is consumed. Fails with [`ErrorKind::TimedOut`] if there's
+	/// no match in time, or [`ErrorKind::UnexpectedEof`] if the output ends without one.
+	pub fn expect(&mut self, pattern: &str, timeout: Duration) -> Result<ExpectMatch> {
+		let regex = Expecter::compile(pattern)?;
+		let expecting = format!("{pattern:?}");
+		self.receive_until(&expecting, timeout, |expecter| expecter.find(&regex))
+	}
+
+	/// Wait for the output to end, returning whatever wasn't matched yet.
+	pub fn expect_eof(&mut self, timeout: Duration) -> Result<String> {
+		self.receive_until("end of output", timeout, |expecter| {
+			expecter.is_closed().then(|| expecter.take_unmatched())
+		})
+	}
+
+	/// Send input to the child.
+	pub fn send(&mut self, input: impl AsRef<[u8]>) -> Result<()> {
+		let input = input.as_ref();
+		let stdin = self
+			.stdin
+			.as_mut()
+			.ok_or_else(|| Error::new(ErrorKind::BrokenPipe, "stdin was closed"))?;
+		stdin.write_all(input)?;
+		stdin.flush()?;
+		self.expecter.sent(input);
+		Ok(())
+	}
+
+	/// Send a line of input to the child.
+	pub fn send_line(&mut self, line: &str) -> Result<()> {
+		self.send(format!("{line}\n"))
+	}
+
+	/// Close the child's stdin, signaling the end of input.
+	pub fn close_stdin(&mut self) {
+		self.stdin = None;
+	}
+
+	/// Everything sent to and received from the child so far, in order.
+	pub fn transcript(&self) -> &[Exchange] {
+		self.expecter.transcript()
+	}
+
+	fn receive_until<T>(
+		&mut self,
+		expecting: &str,
+		timeout: Duration,
+		mut done: impl FnMut(&mut Expecter) -> Option<T>,
+	) -> Result<T> {
+		let deadline = Instant::now() + timeout;
+		loop {
+			if let Some(found) = done(&mut self.expecter) {
+				return Ok(found);
+			}
+			if self.expecter.is_closed() {
+				return Err(self.expecter.ended(expecting));
+			}
+
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			let output = self.output.get_mut().unwrap_or_else(|err| err.into_inner());
+			match output.recv_timeout(remaining) {
+				Ok(data) => self.expecter.received(&data?),
+				Err(RecvTimeoutError::Timeout) => {
+					return Err(self.expecter.timed_out(expecting, timeout));
+				}
+				Err(RecvTimeoutError::Disconnected) => self.expecter.closed(),
+			}
+		}
+	}
+}
+
+impl CommandWrapper for Expect {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![
+			#[cfg(all(unix, feature = "asciicast"))]
+			WrapperConstraint::conflicts_with::<super::Asciicast>(),
+			#[cfg(all(unix, feature = "virtual-terminal"))]
+			WrapperConstraint::conflicts_with::<super::VirtualTerminal>(),
+			#[cfg(all(unix, feature = "pty"))]
+			WrapperConstraint::conflicts_with::<super::pty::PtyTerminal>(),
+		]
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		command.stdin(Stdio::piped()).stdout(Stdio::piped());
+		if self.stderr {
+			command.stderr(Stdio::piped());
+		}
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		mut inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let (tx, output) = channel();
+		if let Some(stdout) = inner.stdout().take() {
+			read_output(stdout, tx.clone())?;
+		}
+		if self.stderr {
+			if let Some(stderr) = inner.stderr().take() {
+				read_output(stderr, tx)?;
+			}
+		}
+
+		Ok(Box::new(ExpectChild {
+			stdin: inner.stdin().take(),
+			inner,
+			output: Mutex::new(output),
+			expecter: Expecter::default(),
+		}))
+	}
+}
+
+/// Read a pipe to the end on a background thread.
+fn read_output(mut pipe: impl Read + Send + 'static, tx: Sender<Result<Vec<u8>>>) -> Result<()> {
+	thread::Builder::new()
+		.name("process-wrap expect".into())
+		.spawn(move || {
+			let mut buf = vec![0; 8 * 1024];
+			loop {
+				let data = match pipe.read(&mut buf) {
+					Ok(0) => return,
+					Ok(n) => Ok(buf[..n].to_vec()),
+					Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+					Err(err) => Err(err),
+				};
+				let failed = data.is_err();
+				if tx.send(data).is_err() || failed {
+					return;
+				}
+			}
+		})
+		.map(drop)
+}
+
+impl ChildWrapper for ExpectChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	fn wait(&mut self) -> Result<ExitStatus> {
+		// like the Child's own stdin, ours must be closed so the child doesn't wait for input
+		self.close_stdin();
+		self.inner.wait()
+	}
+}
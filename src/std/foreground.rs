@@ -0,0 +1,206 @@
+use std::{
+	io::{Error, Result},
+	os::unix::process::CommandExt,
+	process::{Command, ExitStatus},
+};
+
+use nix::{
+	sys::signal::{Signal, killpg},
+	unistd::Pid,
+};
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::unix::{ChildState, ForegroundEvent, Terminal, wait_stopped_or_exited};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Wrapper which gives the controlling terminal to the `Command`'s process group.
+///
+/// This wrapper is only available on Unix.
+///
+/// This is what shells do to run a job in the foreground: the child's process group becomes the
+/// foreground process group of the terminal (see [tcsetpgrp(3)]), so it can read from it and
+/// receive the keyboard signals (`^C`, `^Z`...), which is necessary for editors, pagers, and other
+/// interactive programs. When the child exits or is stopped, the terminal and its settings are
+/// given back to the parent's process group.
+///
+/// This wrapper must be used with [`ProcessGroup`](super::ProcessGroup), and is moved after it at
+/// spawn if needed; otherwise the child is in the parent's own group and there is nothing to hand
+/// over. It does nothing if there is no controlling terminal, or if the parent isn't itself in the
+/// foreground of its terminal.
+///
+/// It can't be used with [`ProcessSession`](super::ProcessSession): that moves the child out of our
+/// session, away from our controlling terminal, so there is no terminal to give it.
+///
+/// This wrapper provides a child wrapper: [`ForegroundChild`], which can report stops and resume
+/// the child in the foreground or background (to implement `fg` and `bg`).
+///
+/// ```rust,no_run
+/// use process_wrap::std::*;
+///
+/// let mut child = CommandWrap::with_new("vi", |_| {})
+///     .wrap(ProcessGroup::leader())
+///     .wrap(Foreground::default())
+///     .spawn()?;
+/// let foreground: &mut ForegroundChild = (child.as_mut() as &mut dyn std::any::Any)
+///     .downcast_mut()
+///     .unwrap();
+/// while let ForegroundEvent::Stopped(_) = foreground.wait_event()? {
+///     // the user pressed ^Z, we've got the terminal back
+///     foreground.resume_foreground()?;
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [tcsetpgrp(3)]: https://pubs.opengroup.org/onlinepubs/9699919799/functions/tcsetpgrp.html
+#[derive(Debug, Default)]
+pub struct Foreground {
+	terminal: Option<Terminal>,
+}
+
+/// Wrapper for `Child` which hands the terminal back and forth with its process group.
+#[derive(Debug)]
+pub struct ForegroundChild {
+	inner: Box<dyn ChildWrapper>,
+	pgid: Pid,
+	terminal: Option<Terminal>,
+}
+
+impl CommandWrapper for Foreground {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![
+			WrapperConstraint::after::<super::ProcessGroup>().reorderable(),
+			#[cfg(feature = "process-session")]
+			WrapperConstraint::conflicts_with::<super::ProcessSession>(),
+		]
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		self.terminal = Terminal::open()?;
+		if let Some(tty) = self.terminal.as_ref().map(Terminal::as_raw_fd) {
+			unsafe {
+				command.pre_exec(move || Terminal::claim(tty));
+			}
+		}
+
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let pgid = Pid::from_raw(i32::try_from(inner.id()).expect("Command PID > i32::MAX"));
+		let pgid = nix::unistd::getpgid(Some(pgid)).unwrap_or(pgid);
+
+		// the child also does this before exec, but we do it here as well so that it's
+		// in place by the time spawn() returns, no matter how the race goes
+		let mut terminal = self.terminal.take();
+		if let Some(terminal) = terminal.as_mut() {
+			terminal.hand_to(pgid)?;
+		}
+
+		Ok(Box::new(ForegroundChild {
+			inner,
+			pgid,
+			terminal,
+		}))
+	}
+}
+
+impl ForegroundChild {
+	/// Get the process group ID that is given the terminal.
+	pub fn pgid(&self) -> u32 {
+		self.pgid.as_raw() as _
+	}
+
+	/// Whether there is a controlling terminal being handed to the child.
+	///
+	/// If this is false, the wrapper is inert aside from reporting stops.
+	pub fn has_terminal(&self) -> bool {
+		self.terminal.is_some()
+	}
+
+	/// Wait until the child stops or exits.
+	///
+	/// Either way, the terminal is given back to the parent's process group before this returns.
+	/// On stop, the child's terminal settings are saved so they can be restored on resume.
+	///
+	/// Unlike [`wait()`](ChildWrapper::wait), which only returns on exit, this can be called in a
+	/// loop to implement job control. Once the child has exited, this keeps returning the same
+	/// `Exited` event.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	pub fn wait_event(&mut self) -> Result<ForegroundEvent> {
+		if let Some(status) = self.inner.try_wait()? {
+			self.reclaim(false)?;
+			return Ok(ForegroundEvent::Exited(status));
+		}
+
+		let pid = Pid::from_raw(i32::try_from(self.inner.id()).map_err(Error::other)?);
+		match wait_stopped_or_exited(pid)? {
+			ChildState::Stopped(signal) => {
+				self.reclaim(true)?;
+				Ok(ForegroundEvent::Stopped(signal))
+			}
+			ChildState::Exited => self.wait().map(ForegroundEvent::Exited),
+		}
+	}
+
+	/// Resume the child in the foreground, like a shell's `fg`.
+	///
+	/// This gives it the terminal (restoring its terminal settings) and sends it `SIGCONT`.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	pub fn resume_foreground(&mut self) -> Result<()> {
+		if let Some(terminal) = self.terminal.as_mut() {
+			terminal.hand_to(self.pgid)?;
+		}
+		killpg(self.pgid, Signal::SIGCONT).map_err(Error::from)
+	}
+
+	/// Resume the child in the background, like a shell's `bg`.
+	///
+	/// This sends it `SIGCONT` without giving it the terminal.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	pub fn resume_background(&mut self) -> Result<()> {
+		killpg(self.pgid, Signal::SIGCONT).map_err(Error::from)
+	}
+
+	fn reclaim(&mut self, save_child: bool) -> Result<()> {
+		match self.terminal.as_mut() {
+			Some(terminal) => terminal.reclaim(save_child),
+			None => Ok(()),
+		}
+	}
+}
+
+impl ChildWrapper for ForegroundChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wait(&mut self) -> Result<ExitStatus> {
+		let status = self.inner.wait()?;
+		self.reclaim(false)?;
+		Ok(status)
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		let status = self.inner.try_wait()?;
+		if status.is_some() {
+			self.reclaim(false)?;
+		}
+		Ok(status)
+	}
+}
@@ -0,0 +1,171 @@
+use std::{
+	convert::Infallible,
+	io::{Error, Result},
+	os::{fd::AsFd, unix::process::CommandExt},
+	process::{Command, ExitStatus},
+};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::unix::{Reaper, SignalPipe, exit_like, prepare_init, wait_wakeup};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Wrapper which makes us a minimal init for the child, like `tini`.
+///
+/// This wrapper is only available on Unix.
+///
+/// This is for when we're the entrypoint of a container, and so PID 1: processes orphaned there
+/// are reparented to us, and as nothing else will reap them, they pile up as zombies. Use
+/// [`run_init()`] to spawn the command, act as init until it exits, and then exit like it did.
+///
+/// Waiting on the child wrapper, [`InitChild`], reaps every process which exits, whether it's the
+/// child or an orphan, until the child exits. Meanwhile, the signals an init is likely to be sent
+/// to stop (`SIGHUP`, `SIGINT`, `SIGQUIT`, `SIGTERM`), and `SIGUSR1`, `SIGUSR2`, and `SIGWINCH`,
+/// are forwarded to the child's process group. The child is put in a new process group for this,
+/// unless there's a [`ProcessGroup`](super::ProcessGroup) or
+/// [`ProcessSession`](super::ProcessSession) wrapper. To do this, handlers for these signals are
+/// installed from when the child is spawned until it exits, so only one can run at a time.
+///
+/// When we're not PID 1, on Linux we become a child subreaper (see `PR_SET_CHILD_SUBREAPER` in
+/// [prctl(2)](https://man7.org/linux/man-pages/man2/prctl.2.html)), so orphans of the child are
+/// reparented to us all the same; this stays in effect afterwards.
+///
+/// As every process which exits is reaped, no other child of ours can be waited on while this
+/// runs: its exit status is taken, and waiting on it fails with `ECHILD`. So the command should be
+/// our only child, as it is for a container's entrypoint. For the same reason, the child is reaped
+/// without its inner wrappers knowing, so this conflicts with
+/// [`KillOnDrop`](super::KillOnDrop), which could then kill an unrelated process reusing its PID.
+///
+/// ```rust,no_run
+/// use process_wrap::std::*;
+///
+/// let command = CommandWrap::with_new("server", |command| { command.arg("--port=80"); });
+/// run_init(command)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Init {
+	signals: Option<SignalPipe>,
+}
+
+impl Init {
+	/// Create an init wrapper.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+/// Wrapper for `Child` which acts as its init.
+///
+/// See [`Init`] for details.
+#[derive(Debug)]
+pub struct InitChild {
+	inner: Box<dyn ChildWrapper>,
+	reaper: Reaper,
+}
+
+/// Spawn the command as our only child, act as its init until it exits, then exit like it did.
+///
+/// This adds an [`Init`] wrapper to the command. When the child exits with a code, we exit with
+/// the same code. When it's killed by a signal, we raise it on ourselves, without dumping core;
+/// as PID 1 can't be killed that way, in that case we exit with 128 plus the signal number
+/// instead, like a shell would report it.
+///
+/// This only returns if spawning or waiting for the command fails.
+#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+pub fn run_init(mut command: CommandWrap) -> Result<Infallible> {
+	command.wrap(Init::new());
+	let status = command.spawn()?.wait()?;
+	exit_like(status)
+}
+
+impl CommandWrapper for Init {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		#[cfg(feature = "kill-on-drop")]
+		{
+			vec![WrapperConstraint::conflicts_with::<super::KillOnDrop>()]
+		}
+
+		#[cfg(not(feature = "kill-on-drop"))]
+		{
+			Vec::new()
+		}
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, core: &CommandWrap) -> Result<()> {
+		#[cfg(feature = "process-group")]
+		let grouped = core.has_wrap::<super::ProcessGroup>();
+		#[cfg(not(feature = "process-group"))]
+		let grouped = false;
+		#[cfg(feature = "process-session")]
+		let grouped = grouped || core.has_wrap::<super::ProcessSession>();
+		let _ = core;
+
+		if !grouped {
+			command.process_group(0);
+		}
+
+		self.signals = Some(prepare_init()?);
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let signals = self
+			.signals
+			.take()
+			.ok_or_else(|| Error::other("init wasn't prepared before spawning"))?;
+
+		Ok(Box::new(InitChild {
+			reaper: Reaper::new(signals, inner.id()),
+			inner,
+		}))
+	}
+
+	fn spawn_failed(&mut self, _command: &mut Command, _error: &Error, _core: &CommandWrap) {
+		self.signals = None;
+	}
+}
+
+impl ChildWrapper for InitChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	fn start_kill(&mut self) -> Result<()> {
+		// once we've reaped the child, its PID may have been reused
+		if self.reaper.step()?.is_some() {
+			return Ok(());
+		}
+		self.inner.start_kill()
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wait(&mut self) -> Result<ExitStatus> {
+		loop {
+			if let Some(status) = self.reaper.step()? {
+				return Ok(status);
+			}
+			if let Some(wakeup) = self.reaper.wakeup_fd()? {
+				wait_wakeup(wakeup.as_fd())?;
+			}
+		}
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		self.reaper.step()
+	}
+}
@@ -19,6 +19,8 @@ use crate::{
 
 #[cfg(feature = "creation-flags")]
 use super::CreationFlags;
+#[cfg(feature = "kill-on-drop")]
+use super::KillOnDrop;
 use super::{ChildWrapper, CommandWrap, CommandWrapper};
 
 /// Wrapper which creates a job object context for a `Command`.
@@ -56,6 +58,11 @@ impl CommandWrapper for JobObject {
 		inner: Box<dyn ChildWrapper>,
 		core: &CommandWrap,
 	) -> Result<Box<dyn ChildWrapper>> {
+		#[cfg(feature = "kill-on-drop")]
+		let kill_on_drop = core.has_wrap::<KillOnDrop>();
+		#[cfg(not(feature = "kill-on-drop"))]
+		let kill_on_drop = false;
+
 		#[cfg(feature = "creation-flags")]
 		let create_suspended = core
 			.get_wrap::<CreationFlags>()
@@ -64,11 +71,15 @@ impl CommandWrapper for JobObject {
 		let create_suspended = false;
 
 		#[cfg(feature = "tracing")]
-		debug!(?create_suspended, "options from other wrappers");
+		debug!(
+			?kill_on_drop,
+			?create_suspended,
+			"options from other wrappers"
+		);
 
 		let handle = HANDLE(inner.inner_child().as_raw_handle() as _);
 
-		let job_port = make_job_object(handle, false)?;
+		let job_port = make_job_object(handle, kill_on_drop)?;
 
 		// only resume if the user didn't specify CREATE_SUSPENDED
 		if !create_suspended {
@@ -84,6 +95,7 @@ impl CommandWrapper for JobObject {
 pub struct JobObjectChild {
 	inner: Box<dyn ChildWrapper>,
 	exit_status: ChildExitStatus,
+	kill_reason: Option<String>,
 	job_port: JobPort,
 }
 
@@ -93,6 +105,7 @@ impl JobObjectChild {
 		Self {
 			inner,
 			exit_status: ChildExitStatus::Running,
+			kill_reason: None,
 			job_port,
 		}
 	}
@@ -100,10 +113,10 @@ impl JobObjectChild {
 
 impl ChildWrapper for JobObjectChild {
 	fn inner(&self) -> &dyn ChildWrapper {
-		self.inner.inner()
+		&*self.inner
 	}
 	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
-		self.inner.inner_mut()
+		&mut *self.inner
 	}
 	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
 		// manually drop the completion port
@@ -112,12 +125,20 @@ impl ChildWrapper for JobObjectChild {
 		// we leave the job handle unclosed, otherwise the Child is useless
 		// (as closing it will terminate the job)
 
-		self.inner.into_inner()
+		self.inner
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
 	fn start_kill(&mut self) -> Result<()> {
-		terminate_job(self.job_port.job, 1)
+		terminate_job(self.job_port.job, 1)?;
+		self.kill_reason = Some("job object terminated".into());
+		Ok(())
+	}
+
+	fn kill_reason(&self) -> Option<String> {
+		self.kill_reason
+			.clone()
+			.or_else(|| self.inner.kill_reason())
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
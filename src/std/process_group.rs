@@ -3,6 +3,7 @@ use std::{
 	ops::ControlFlow,
 	os::unix::process::{CommandExt, ExitStatusExt},
 	process::{Command, ExitStatus},
+	time::Duration,
 };
 
 use nix::{
@@ -17,7 +18,9 @@ use nix::{
 #[cfg(feature = "tracing")]
 use tracing::instrument;
 
-use crate::ChildExitStatus;
+#[cfg(feature = "kill-on-drop")]
+use crate::kill_on_drop::GroupKillGuard;
+use crate::{ChildExitStatus, process_group::ReapedMembers, unix::EmptyGroupWait};
 
 use super::{ChildWrapper, CommandWrap, CommandWrapper};
 
@@ -35,6 +38,8 @@ use super::{ChildWrapper, CommandWrap, CommandWrapper};
 #[derive(Clone, Copy, Debug)]
 pub struct ProcessGroup {
 	leader: Pid,
+	wait_for_empty: Option<Duration>,
+	kill_stragglers: bool,
 }
 
 impl ProcessGroup {
@@ -42,6 +47,8 @@ impl ProcessGroup {
 	pub fn leader() -> Self {
 		Self {
 			leader: Pid::from_raw(0),
+			wait_for_empty: None,
+			kill_stragglers: false,
 		}
 	}
 
@@ -49,16 +56,55 @@ impl ProcessGroup {
 	pub fn attach_to(leader: u32) -> Self {
 		Self {
 			leader: Pid::from_raw(leader as _),
+			wait_for_empty: None,
+			kill_stragglers: false,
 		}
 	}
+
+	/// Also wait for every process in the group to exit when waiting for the child.
+	///
+	/// Waiting for the child normally returns once it has exited and the group members which are
+	/// our own children were reaped, but other members, such as its own children which were
+	/// reparented when it exited, can still be running. With this, waiting also polls until the
+	/// group is empty, and fails with [`ErrorKind::TimedOut`](std::io::ErrorKind::TimedOut) if it
+	/// isn't after `timeout`. The exit status is kept, so waiting again waits for the group again.
+	/// Likewise, `try_wait()` only returns the status once the group is empty.
+	///
+	/// On Linux, the group is looked up in `/proc`, so zombies don't count. Elsewhere, it's
+	/// checked with `kill(-pgid, 0)`, which zombies still answer until they're reaped.
+	///
+	/// Returns `self` for chaining.
+	pub fn wait_for_empty(mut self, timeout: Duration) -> Self {
+		self.wait_for_empty = Some(timeout);
+		self
+	}
+
+	/// Kill the processes still in the group after the [`wait_for_empty()`](Self::wait_for_empty)
+	/// timeout, instead of failing.
+	///
+	/// They're sent `SIGKILL` and waited for again, for up to the same timeout.
+	///
+	/// Returns `self` for chaining.
+	pub fn kill_stragglers(mut self) -> Self {
+		self.kill_stragglers = true;
+		self
+	}
 }
 
 /// Wrapper for `Child` which ensures that all processes in the group are reaped.
+///
+/// The exit statuses of the other members of the group reaped along the way are recorded, see
+/// [`reaped_members()`](Self::reaped_members).
 #[derive(Debug)]
 pub struct ProcessGroupChild {
 	inner: Box<dyn ChildWrapper>,
 	exit_status: ChildExitStatus,
+	kill_reason: Option<String>,
 	pgid: Pid,
+	reaped: ReapedMembers,
+	empty_wait: Option<EmptyGroupWait>,
+	#[cfg(feature = "kill-on-drop")]
+	kill_on_drop: Option<GroupKillGuard>,
 }
 
 impl ProcessGroupChild {
@@ -67,16 +113,73 @@ impl ProcessGroupChild {
 		Self {
 			inner,
 			exit_status: ChildExitStatus::Running,
+			kill_reason: None,
 			pgid,
+			reaped: ReapedMembers::default(),
+			empty_wait: None,
+			#[cfg(feature = "kill-on-drop")]
+			kill_on_drop: None,
 		}
 	}
 
+	/// Kill the group when dropped, if there's a `KillOnDrop` wrapper.
+	pub(crate) fn kill_on_drop(self, core: &CommandWrap) -> Self {
+		#[cfg(feature = "kill-on-drop")]
+		return Self {
+			kill_on_drop: core
+				.get_wrap::<super::KillOnDrop>()
+				.map(|wrapper| GroupKillGuard::new(self.pgid, wrapper.policy)),
+			..self
+		};
+
+		#[cfg(not(feature = "kill-on-drop"))]
+		{
+			let _ = core;
+			self
+		}
+	}
+
+	/// Wait for the whole group to be empty when waiting for the child.
+	fn wait_for_empty(self, empty_wait: Option<EmptyGroupWait>) -> Self {
+		Self { empty_wait, ..self }
+	}
+
+	/// Stop killing the group on drop, e.g. because it's been handed over.
+	///
+	/// If `only_if_empty` is set, the group is left armed if it still has members, such as
+	/// grandchildren which we can't reap.
+	fn disarm(&mut self, only_if_empty: bool) {
+		#[cfg(feature = "kill-on-drop")]
+		if !only_if_empty || killpg(self.pgid, None) == Err(Errno::ESRCH) {
+			if let Some(guard) = self.kill_on_drop.take() {
+				guard.disarm();
+			}
+		}
+		let _ = only_if_empty;
+	}
+
 	/// Get the process group ID of this child process.
 	///
 	/// See: [`man 'setpgid(2)'`](https://www.man7.org/linux/man-pages/man2/setpgid.2.html)
 	pub fn pgid(&self) -> u32 {
 		self.pgid.as_raw() as _
 	}
+
+	/// Get the PIDs and exit statuses of the group members reaped so far, in order.
+	///
+	/// This doesn't include the child itself. Only our own children can be reaped, so these are
+	/// the other processes we spawned into the group, not the processes the child spawned.
+	/// Members are reaped when waiting on the child.
+	pub fn reaped_members(&self) -> Vec<(u32, ExitStatus)> {
+		self.reaped.statuses()
+	}
+
+	/// Call a function with the PID and exit status of each group member as it's reaped.
+	///
+	/// This may be called from a background thread.
+	pub fn on_reaped(&mut self, callback: impl Fn(u32, ExitStatus) + Send + Sync + 'static) {
+		self.reaped.set_callback(callback);
+	}
 }
 
 impl CommandWrapper for ProcessGroup {
@@ -90,11 +193,20 @@ impl CommandWrapper for ProcessGroup {
 	fn wrap_child(
 		&mut self,
 		inner: Box<dyn ChildWrapper>,
-		_core: &CommandWrap,
+		core: &CommandWrap,
 	) -> Result<Box<dyn ChildWrapper>> {
 		let pgid = Pid::from_raw(i32::try_from(inner.id()).expect("Command PID > i32::MAX"));
 
-		Ok(Box::new(ProcessGroupChild::new(inner, pgid)))
+		let empty_wait = self.wait_for_empty.map(|timeout| EmptyGroupWait {
+			timeout,
+			kill_stragglers: self.kill_stragglers,
+		});
+
+		Ok(Box::new(
+			ProcessGroupChild::new(inner, pgid)
+				.wait_for_empty(empty_wait)
+				.kill_on_drop(core),
+		))
 	}
 }
 
@@ -104,8 +216,36 @@ impl ProcessGroupChild {
 		killpg(self.pgid, sig).map_err(Error::from)
 	}
 
+	/// Wait for the members of the group which aren't our children, if asked to.
+	fn wait_empty(&mut self) -> Result<()> {
+		if let Some(empty_wait) = self.empty_wait {
+			empty_wait.wait(self.pgid)?;
+			self.disarm(true);
+		}
+		Ok(())
+	}
+
+	/// Like [`wait_empty()`](Self::wait_empty), but only checks once, for `try_wait()`.
+	///
+	/// The status is only returned once the group is empty, if asked to wait for that.
+	fn try_wait_empty(&mut self, status: ExitStatus) -> Result<Option<ExitStatus>> {
+		if let Some(empty_wait) = self.empty_wait {
+			if !empty_wait.is_empty(self.pgid)? {
+				return Ok(None);
+			}
+		}
+
+		// the group is gone, so its ID may be reused: don't kill it on drop
+		self.disarm(true);
+		Ok(Some(status))
+	}
+
 	#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
-	fn wait_imp(pgid: Pid, flag: WaitPidFlag) -> Result<ControlFlow<Option<ExitStatus>>> {
+	fn wait_imp(
+		pgid: Pid,
+		flag: WaitPidFlag,
+		reaped: &ReapedMembers,
+	) -> Result<ControlFlow<Option<ExitStatus>>> {
 		// wait for processes in a loop until every process in this group has
 		// exited (this ensures that we reap any zombies that may have been
 		// created if the parent exited after spawning children, but didn't wait
@@ -141,7 +281,7 @@ impl ProcessGroupChild {
 					if pgid == Pid::from_raw(pid) {
 						parent_exit_status = Some(ExitStatus::from_raw(status));
 					} else {
-						// reaped a zombie child; keep looping
+						reaped.record(pid as _, ExitStatus::from_raw(status));
 					}
 				}
 			};
@@ -151,24 +291,34 @@ impl ProcessGroupChild {
 
 impl ChildWrapper for ProcessGroupChild {
 	fn inner(&self) -> &dyn ChildWrapper {
-		self.inner.inner()
+		&*self.inner
 	}
 	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
-		self.inner.inner_mut()
+		&mut *self.inner
 	}
-	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
-		self.inner.into_inner()
+	fn into_inner(mut self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.disarm(false);
+		self.inner
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
 	fn start_kill(&mut self) -> Result<()> {
-		self.signal_imp(Signal::SIGKILL)
+		self.signal_imp(Signal::SIGKILL)?;
+		self.kill_reason = Some("process group killed".into());
+		Ok(())
+	}
+
+	fn kill_reason(&self) -> Option<String> {
+		self.kill_reason
+			.clone()
+			.or_else(|| self.inner.kill_reason())
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
 	fn wait(&mut self) -> Result<ExitStatus> {
-		if let ChildExitStatus::Exited(status) = &self.exit_status {
-			return Ok(*status);
+		if let ChildExitStatus::Exited(status) = self.exit_status {
+			self.wait_empty()?;
+			return Ok(status);
 		}
 
 		// always wait for parent to exit first, as by the time it does,
@@ -177,30 +327,29 @@ impl ChildWrapper for ProcessGroupChild {
 		self.exit_status = ChildExitStatus::Exited(status);
 
 		// nevertheless, now wait and make sure we reap all children.
-		let _ = Self::wait_imp(self.pgid, WaitPidFlag::empty())?;
+		let _ = Self::wait_imp(self.pgid, WaitPidFlag::empty(), &self.reaped)?;
+
+		self.wait_empty()?;
+		self.disarm(true);
 		Ok(status)
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
 	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-		if let ChildExitStatus::Exited(status) = &self.exit_status {
-			return Ok(Some(*status));
+		if let ChildExitStatus::Exited(status) = self.exit_status {
+			return self.try_wait_empty(status);
 		}
 
-		match Self::wait_imp(self.pgid, WaitPidFlag::WNOHANG)? {
-			ControlFlow::Break(res) => {
-				if let Some(status) = res {
-					self.exit_status = ChildExitStatus::Exited(status);
-				}
-				Ok(res)
-			}
-			ControlFlow::Continue(()) => {
-				let exited = self.inner.try_wait()?;
-				if let Some(exited) = exited {
-					self.exit_status = ChildExitStatus::Exited(exited);
-				}
-				Ok(exited)
+		let exited = match Self::wait_imp(self.pgid, WaitPidFlag::WNOHANG, &self.reaped)? {
+			ControlFlow::Break(res) => res,
+			ControlFlow::Continue(()) => self.inner.try_wait()?,
+		};
+		match exited {
+			Some(status) => {
+				self.exit_status = ChildExitStatus::Exited(status);
+				self.try_wait_empty(status)
 			}
+			None => Ok(None),
 		}
 	}
 
@@ -8,7 +8,7 @@ use nix::unistd::{Pid, setsid};
 #[cfg(feature = "tracing")]
 use tracing::instrument;
 
-use super::{CommandWrap, CommandWrapper};
+use super::{CommandWrap, CommandWrapper, WrapperConstraint};
 
 /// Wrapper which creates a new session and group for the `Command`.
 ///
@@ -21,11 +21,16 @@ use super::{CommandWrap, CommandWrapper};
 /// than a process group, or vice versa.
 ///
 /// This wrapper uses [the same child wrapper as `ProcessGroup`](super::ProcessGroupChild) and does
-/// the same setup (plus the session setup); using both together is unnecessary and may misbehave.
+/// the same setup (plus the session setup); using both together is unnecessary and may misbehave,
+/// so spawning fails if they are.
 #[derive(Clone, Copy, Debug)]
 pub struct ProcessSession;
 
 impl CommandWrapper for ProcessSession {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![WrapperConstraint::conflicts_with::<super::ProcessGroup>()]
+	}
+
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
 	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
 		unsafe {
@@ -39,10 +44,12 @@ impl CommandWrapper for ProcessSession {
 	fn wrap_child(
 		&mut self,
 		inner: Box<dyn super::core::ChildWrapper>,
-		_core: &CommandWrap,
+		core: &CommandWrap,
 	) -> Result<Box<dyn super::core::ChildWrapper>> {
 		let pgid = Pid::from_raw(i32::try_from(inner.id()).expect("Command PID > i32::MAX"));
 
-		Ok(Box::new(super::ProcessGroupChild::new(inner, pgid)))
+		Ok(Box::new(
+			super::ProcessGroupChild::new(inner, pgid).kill_on_drop(core),
+		))
 	}
 }
@@ -0,0 +1,162 @@
+use std::{
+	io::{Error, Result},
+	os::unix::process::CommandExt,
+	process::Command,
+};
+
+use nix::unistd::Pid;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::unix::{
+	IoPriority, SchedPolicy, SchedSettings, reaffine, reaffine_group, renice, renice_group,
+};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper};
+
+/// Wrapper which sets the scheduling parameters of a `Command`.
+///
+/// This sets the child's niceness, I/O scheduling class and priority, CPU affinity, and CPU
+/// scheduling policy, in that child before it execs. Settings which aren't specified are inherited
+/// as usual. Spawning fails if a setting can't be applied, e.g. lowering the niceness without the
+/// privilege to do so.
+///
+/// Adding this wrapper more than once merges the settings, with those of the later wrappers taking
+/// precedence.
+///
+/// This wrapper provides a child wrapper: [`SchedulingChild`], which can change the niceness and
+/// CPU affinity of the running child, or of its whole process group.
+///
+/// This wrapper is Linux-only.
+///
+/// ```rust,no_run
+/// use process_wrap::std::*;
+///
+/// let mut child = CommandWrap::with_new("make", |_| {})
+///     .wrap(
+///         Scheduling::new()
+///             .nice(10)
+///             .io_priority(IoPriority::Idle)
+///             .policy(SchedPolicy::Batch),
+///     )
+///     .spawn()?;
+/// child.wait()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Scheduling(SchedSettings);
+
+impl Scheduling {
+	/// Create a scheduling wrapper which doesn't change anything yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the niceness, from -20 (highest priority) to 19 (lowest).
+	///
+	/// Returns `self` for chaining.
+	pub fn nice(mut self, nice: i32) -> Self {
+		self.0.nice = Some(nice);
+		self
+	}
+
+	/// Set the I/O scheduling class and priority.
+	///
+	/// Returns `self` for chaining.
+	pub fn io_priority(mut self, priority: IoPriority) -> Self {
+		self.0.io_priority = Some(priority);
+		self
+	}
+
+	/// Restrict the child to these CPUs, by index.
+	///
+	/// Returns `self` for chaining.
+	pub fn affinity(mut self, cpus: impl IntoIterator<Item = usize>) -> Self {
+		self.0.affinity = Some(cpus.into_iter().collect());
+		self
+	}
+
+	/// Set the CPU scheduling policy.
+	///
+	/// Returns `self` for chaining.
+	pub fn policy(mut self, policy: SchedPolicy) -> Self {
+		self.0.policy = Some(policy);
+		self
+	}
+}
+
+/// Wrapper for `Child` which can change its scheduling parameters while it runs.
+///
+/// The group methods apply to every process in the child's process group, as found at the time of
+/// the call. They fail if the child isn't in its own group, e.g. when `ProcessGroup` isn't used, as
+/// that would change our own group's scheduling too.
+#[derive(Debug)]
+pub struct SchedulingChild {
+	inner: Box<dyn ChildWrapper>,
+}
+
+impl SchedulingChild {
+	/// Change the niceness of the child.
+	pub fn set_nice(&self, nice: i32) -> Result<()> {
+		renice(self.pid()?, nice)
+	}
+
+	/// Change the niceness of every process in the child's process group.
+	pub fn set_group_nice(&self, nice: i32) -> Result<()> {
+		renice_group(self.pid()?, nice)
+	}
+
+	/// Change the CPU affinity of the child.
+	pub fn set_affinity(&self, cpus: &[usize]) -> Result<()> {
+		reaffine(self.pid()?, cpus)
+	}
+
+	/// Change the CPU affinity of every process in the child's process group.
+	pub fn set_group_affinity(&self, cpus: &[usize]) -> Result<()> {
+		reaffine_group(self.pid()?, cpus)
+	}
+
+	fn pid(&self) -> Result<Pid> {
+		Ok(Pid::from_raw(
+			i32::try_from(self.inner.id()).map_err(Error::other)?,
+		))
+	}
+}
+
+impl CommandWrapper for Scheduling {
+	fn extend(&mut self, other: Box<dyn CommandWrapper>) {
+		let other = (other as Box<dyn std::any::Any>)
+			.downcast::<Self>()
+			.expect("extend() is only called with the same type");
+		self.0.merge(other.0);
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		let apply = self.0.pre_exec()?;
+		unsafe {
+			command.pre_exec(apply);
+		}
+		Ok(())
+	}
+
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		Ok(Box::new(SchedulingChild { inner }))
+	}
+}
+
+impl ChildWrapper for SchedulingChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+}
@@ -0,0 +1,181 @@
+use std::{
+	io::Result,
+	process::{ChildStderr, ChildStdin, ChildStdout, ExitStatus},
+};
+
+use tokio::runtime::Handle;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use super::ChildWrapper;
+use crate::tokio::ChildWrapper as TokioChildWrapper;
+
+/// Adapter which makes a child from the Tokio frontend usable from synchronous code.
+///
+/// This implements the std [`ChildWrapper`] over a `Box<dyn tokio::ChildWrapper>`, by driving its
+/// futures to completion on a Tokio runtime, through the provided [`Handle`]. Methods go through
+/// the whole Tokio wrapper chain, so e.g. `signal()` on a Tokio `ProcessGroupChild` still signals
+/// the entire group.
+///
+/// The child's piped stdin, stdout, and stderr are converted to their std equivalents when the
+/// adapter is created, and converted back by [`into_tokio()`](Self::into_tokio).
+///
+/// As with [`Handle::block_on`], the blocking methods (`wait()`, `kill()`...) must not be called
+/// from within an asynchronous execution context, or they will panic.
+///
+/// This adapter is the bottom of the std wrapper chain: its `inner()` is itself, and as it doesn't
+/// contain a std `Child`, `inner_child()` and friends will panic.
+///
+/// ```rust,no_run
+/// use process_wrap::{std::{ChildWrapper, TokioChild}, tokio::CommandWrap};
+///
+/// let runtime = tokio::runtime::Runtime::new()?;
+/// let child = runtime.block_on(async { CommandWrap::with_new("ls", |_| {}).spawn() })?;
+/// let mut child = TokioChild::new(child, runtime.handle().clone())?;
+/// child.wait()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct TokioChild {
+	inner: Box<dyn TokioChildWrapper>,
+	handle: Handle,
+	pid: u32,
+	stdin: Option<ChildStdin>,
+	stdout: Option<ChildStdout>,
+	stderr: Option<ChildStderr>,
+}
+
+impl TokioChild {
+	/// Adapt a Tokio child, driving it on the given runtime.
+	///
+	/// This fails if the child's pipes can't be converted.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(handle)))]
+	pub fn new(mut inner: Box<dyn TokioChildWrapper>, handle: Handle) -> Result<Self> {
+		#[cfg(unix)]
+		let (stdin, stdout, stderr) = (
+			inner
+				.stdin()
+				.take()
+				.map(|io| io.into_owned_fd())
+				.transpose()?,
+			inner
+				.stdout()
+				.take()
+				.map(|io| io.into_owned_fd())
+				.transpose()?,
+			inner
+				.stderr()
+				.take()
+				.map(|io| io.into_owned_fd())
+				.transpose()?,
+		);
+		#[cfg(windows)]
+		let (stdin, stdout, stderr) = (
+			inner
+				.stdin()
+				.take()
+				.map(|io| io.into_owned_handle())
+				.transpose()?,
+			inner
+				.stdout()
+				.take()
+				.map(|io| io.into_owned_handle())
+				.transpose()?,
+			inner
+				.stderr()
+				.take()
+				.map(|io| io.into_owned_handle())
+				.transpose()?,
+		);
+
+		Ok(Self {
+			pid: inner.id().unwrap_or_default(),
+			stdin: stdin.map(ChildStdin::from),
+			stdout: stdout.map(ChildStdout::from),
+			stderr: stderr.map(ChildStderr::from),
+			inner,
+			handle,
+		})
+	}
+
+	/// Obtain a reference to the adapted Tokio child.
+	pub fn tokio_child(&self) -> &dyn TokioChildWrapper {
+		&*self.inner
+	}
+
+	/// Obtain a mutable reference to the adapted Tokio child.
+	///
+	/// Note that its pipes have been moved to this adapter.
+	pub fn tokio_child_mut(&mut self) -> &mut dyn TokioChildWrapper {
+		&mut *self.inner
+	}
+
+	/// Get the Tokio child back, with whichever of its pipes haven't been taken.
+	///
+	/// This fails if the pipes can't be converted back, which requires the runtime to be running.
+	pub fn into_tokio(mut self) -> Result<Box<dyn TokioChildWrapper>> {
+		let _guard = self.handle.enter();
+		if let Some(io) = self.stdin.take() {
+			*self.inner.stdin() = Some(tokio::process::ChildStdin::from_std(io)?);
+		}
+		if let Some(io) = self.stdout.take() {
+			*self.inner.stdout() = Some(tokio::process::ChildStdout::from_std(io)?);
+		}
+		if let Some(io) = self.stderr.take() {
+			*self.inner.stderr() = Some(tokio::process::ChildStderr::from_std(io)?);
+		}
+
+		Ok(self.inner)
+	}
+}
+
+impl ChildWrapper for TokioChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		self
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		self
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self
+	}
+	fn stdin(&mut self) -> &mut Option<ChildStdin> {
+		&mut self.stdin
+	}
+	fn stdout(&mut self) -> &mut Option<ChildStdout> {
+		&mut self.stdout
+	}
+	fn stderr(&mut self) -> &mut Option<ChildStderr> {
+		&mut self.stderr
+	}
+	fn id(&self) -> u32 {
+		self.inner.id().unwrap_or(self.pid)
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn kill(&mut self) -> Result<()> {
+		self.handle.block_on(Box::into_pin(self.inner.kill()))
+	}
+
+	fn start_kill(&mut self) -> Result<()> {
+		self.inner.start_kill()
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		self.inner.try_wait()
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wait(&mut self) -> Result<ExitStatus> {
+		self.handle.block_on(self.inner.wait())
+	}
+
+	fn kill_reason(&self) -> Option<String> {
+		self.inner.kill_reason()
+	}
+
+	#[cfg(unix)]
+	fn signal(&self, sig: i32) -> Result<()> {
+		self.inner.signal(sig)
+	}
+}
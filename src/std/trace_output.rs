@@ -0,0 +1,160 @@
+use std::{
+	io::{BufRead, BufReader, Read, Result},
+	process::{Command, ExitStatus, Stdio},
+	thread::{self, JoinHandle},
+};
+
+use tracing::{Level, Span, dispatcher, instrument};
+
+use crate::trace_output::{CommandLine, exited, line};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper};
+
+/// Wrapper which emits a `Command`'s output as tracing events.
+///
+/// This opens a [span](tracing::Span) for the child, with its program, arguments, PID, and (on
+/// Unix) process group ID as fields. Each line the child writes to stdout or stderr is emitted as
+/// an event within that span, at the configured level, with a `stream` field. When the child is
+/// waited on, the span's `status` field is recorded, a final event is emitted, and the span is
+/// closed. All spans and events have the `process_wrap::output` target.
+///
+/// This wrapper pipes stdout and stderr, and consumes them in background threads, so they're not
+/// available to the caller. Output that isn't valid UTF-8 is converted lossily.
+///
+/// Waiting also waits for the output to be fully consumed, so that all lines are emitted before the
+/// span is closed; if the child leaves descendants running with its stdout or stderr, waiting will
+/// block until they exit too. `try_wait()` doesn't wait for the output, so lines may be emitted in
+/// the span after the exit event in that case.
+///
+/// This wrapper provides a child wrapper: [`TraceOutputChild`].
+#[derive(Clone, Debug)]
+pub struct TraceOutput {
+	level: Level,
+	command_line: CommandLine,
+}
+
+impl TraceOutput {
+	/// Create a trace output wrapper emitting events at the given level.
+	pub fn new(level: Level) -> Self {
+		Self {
+			level,
+			command_line: CommandLine::default(),
+		}
+	}
+}
+
+impl Default for TraceOutput {
+	/// Emits at the INFO level.
+	fn default() -> Self {
+		Self::new(Level::INFO)
+	}
+}
+
+impl CommandWrapper for TraceOutput {
+	#[instrument(level = "debug", skip(self))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		self.command_line = CommandLine::new(command.get_program(), command.get_args());
+		command.stdout(Stdio::piped()).stderr(Stdio::piped());
+		Ok(())
+	}
+
+	#[instrument(level = "debug", skip(self))]
+	fn wrap_child(
+		&mut self,
+		mut inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let span = self.command_line.span(self.level, Some(inner.id()));
+
+		let mut readers = Vec::with_capacity(2);
+		if let Some(stdout) = inner.stdout().take() {
+			readers.push(read_lines(self.level, span.clone(), "stdout", stdout));
+		}
+		if let Some(stderr) = inner.stderr().take() {
+			readers.push(read_lines(self.level, span.clone(), "stderr", stderr));
+		}
+
+		Ok(Box::new(TraceOutputChild {
+			inner,
+			level: self.level,
+			span: Some(span),
+			readers,
+		}))
+	}
+}
+
+fn read_lines(
+	level: Level,
+	span: Span,
+	stream: &'static str,
+	pipe: impl Read + Send + 'static,
+) -> JoinHandle<()> {
+	// emit to the subscriber that was current at spawn, which is the one that has the span
+	let dispatch = dispatcher::get_default(Clone::clone);
+	thread::spawn(move || {
+		dispatcher::with_default(&dispatch, || {
+			let mut reader = BufReader::new(pipe);
+			let mut buf = Vec::new();
+			while let Ok(n) = reader.read_until(b'\n', &mut buf) {
+				if n == 0 {
+					break;
+				}
+				line(level, &span, stream, &buf);
+				buf.clear();
+			}
+		})
+	})
+}
+
+/// Wrapper for `Child` which emits its output as tracing events.
+#[derive(Debug)]
+pub struct TraceOutputChild {
+	inner: Box<dyn ChildWrapper>,
+	level: Level,
+	span: Option<Span>,
+	readers: Vec<JoinHandle<()>>,
+}
+
+impl TraceOutputChild {
+	/// The span for this child.
+	///
+	/// Returns `None` once the child has been waited on and the span closed.
+	pub fn span(&self) -> Option<&Span> {
+		self.span.as_ref()
+	}
+
+	fn finish(&mut self, status: ExitStatus) {
+		if let Some(span) = self.span.take() {
+			exited(self.level, &span, status);
+		}
+	}
+}
+
+impl ChildWrapper for TraceOutputChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		let status = self.inner.try_wait()?;
+		if let Some(status) = status {
+			self.finish(status);
+		}
+		Ok(status)
+	}
+
+	fn wait(&mut self) -> Result<ExitStatus> {
+		let status = self.inner.wait()?;
+		for reader in self.readers.drain(..) {
+			reader.join().ok();
+		}
+		self.finish(status);
+		Ok(status)
+	}
+}
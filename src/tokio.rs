@@ -8,37 +8,121 @@
 //! use process_wrap::tokio::*;
 //! ```
 
+#[cfg(all(unix, feature = "asciicast"))]
+#[doc(inline)]
+pub use crate::replay_asciicast;
+#[cfg(all(unix, feature = "foreground"))]
+#[doc(inline)]
+pub use crate::unix::ForegroundEvent;
+#[cfg(all(target_os = "linux", feature = "scheduling"))]
+#[doc(inline)]
+pub use crate::unix::{IoPriority, SchedPolicy};
+#[cfg(all(unix, feature = "virtual-terminal"))]
+#[doc(inline)]
+pub use crate::{Cell, CellAttrs, Color, Screen};
+#[doc(inline)]
+pub use crate::{
+	ChildLayers, CommandPlan, ExitOutcome, Framing, OutputEvent, WrapperConstraint, WrapperPlan,
+};
+#[cfg(feature = "expect")]
+#[doc(inline)]
+pub use crate::{Exchange, ExpectMatch};
+#[cfg(all(unix, feature = "asciicast"))]
+#[doc(inline)]
+pub use asciicast::{Asciicast, AsciicastChild};
 #[doc(inline)]
 pub use core::{ChildWrapper, CommandWrap, CommandWrapper};
 #[cfg(all(windows, feature = "creation-flags"))]
 #[doc(inline)]
 pub use creation_flags::CreationFlags;
+#[cfg(feature = "environment")]
+#[doc(inline)]
+pub use environment::Environment;
+#[cfg(feature = "expect")]
+#[doc(inline)]
+pub use expect::{Expect, ExpectChild};
+#[cfg(all(unix, feature = "foreground"))]
+#[doc(inline)]
+pub use foreground::{Foreground, ForegroundChild};
+#[cfg(all(unix, feature = "init"))]
+#[doc(inline)]
+pub use init::{Init, InitChild, run_init};
 #[cfg(all(windows, feature = "job-object"))]
 #[doc(inline)]
 pub use job_object::{JobObject, JobObjectChild};
 #[cfg(feature = "kill-on-drop")]
 #[doc(inline)]
-pub use kill_on_drop::KillOnDrop;
+pub use kill_on_drop::{KillOnDrop, KillOnDropChild};
+#[cfg(all(target_os = "linux", feature = "kill-tree"))]
+#[doc(inline)]
+pub use kill_tree::{KillTree, KillTreeChild};
+#[cfg(feature = "observe")]
+#[doc(inline)]
+pub use observe::{Observe, ObserveChild};
+#[cfg(all(target_os = "linux", feature = "oom-score-adj"))]
+#[doc(inline)]
+pub use oom_score_adj::{OomScoreAdj, OomScoreAdjChild};
 #[cfg(all(unix, feature = "process-group"))]
 #[doc(inline)]
 pub use process_group::{ProcessGroup, ProcessGroupChild};
 #[cfg(all(unix, feature = "process-session"))]
 #[doc(inline)]
 pub use process_session::ProcessSession;
+#[cfg(all(unix, feature = "pty"))]
+#[doc(inline)]
+pub use pty::PtySession;
 #[cfg(all(unix, feature = "reset-sigmask"))]
 #[doc(inline)]
 pub use reset_sigmask::ResetSigmask;
+#[cfg(all(target_os = "linux", feature = "scheduling"))]
+#[doc(inline)]
+pub use scheduling::{Scheduling, SchedulingChild};
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use std_child::StdChild;
+#[cfg(feature = "trace-output")]
+#[doc(inline)]
+pub use trace_output::{TraceOutput, TraceOutputChild};
+#[cfg(all(unix, feature = "virtual-terminal"))]
+#[doc(inline)]
+pub use virtual_terminal::{VirtualTerminal, VirtualTerminalChild};
 
+#[cfg(all(unix, feature = "asciicast"))]
+mod asciicast;
 mod core;
 #[cfg(all(windows, feature = "creation-flags"))]
 mod creation_flags;
+#[cfg(feature = "environment")]
+mod environment;
+#[cfg(feature = "expect")]
+mod expect;
+#[cfg(all(unix, feature = "foreground"))]
+mod foreground;
+#[cfg(all(unix, feature = "init"))]
+mod init;
 #[cfg(all(windows, feature = "job-object"))]
 mod job_object;
 #[cfg(feature = "kill-on-drop")]
 mod kill_on_drop;
+#[cfg(all(target_os = "linux", feature = "kill-tree"))]
+mod kill_tree;
+#[cfg(feature = "observe")]
+mod observe;
+#[cfg(all(target_os = "linux", feature = "oom-score-adj"))]
+mod oom_score_adj;
 #[cfg(all(unix, feature = "process-group"))]
 mod process_group;
 #[cfg(all(unix, feature = "process-session"))]
 mod process_session;
+#[cfg(all(unix, feature = "pty"))]
+mod pty;
 #[cfg(all(unix, feature = "reset-sigmask"))]
 mod reset_sigmask;
+#[cfg(all(target_os = "linux", feature = "scheduling"))]
+mod scheduling;
+#[cfg(feature = "std")]
+mod std_child;
+#[cfg(feature = "trace-output")]
+mod trace_output;
+#[cfg(all(unix, feature = "virtual-terminal"))]
+mod virtual_terminal;
@@ -0,0 +1,253 @@
+use std::{
+	env,
+	fs::File,
+	future::Future,
+	io::{Error, Result, Write},
+	os::fd::{AsFd, OwnedFd},
+	path::PathBuf,
+	pin::Pin,
+	process::{ExitStatus, Stdio},
+};
+
+use tokio::{
+	process::{Child, Command},
+	task::spawn_blocking,
+};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::{
+	asciicast::{HEADER_ENV, Header, Recorder, Recording},
+	unix::{claim_pty, open_pty_sized, resize_pty},
+};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Wrapper which records the child's terminal session to an asciicast file.
+///
+/// This wrapper is only available on Unix.
+///
+/// The child is spawned on its own pseudo-terminal, as its controlling terminal and stdin, stdout
+/// and stderr, and everything it outputs is written to an [asciicast v2] file, as used by
+/// [asciinema]. The file starts with a header giving the terminal size and the `SHELL` and `TERM`
+/// environment variables, followed by timestamped output events.
+///
+/// The child wrapper, [`AsciicastChild`], sends input to the child, which is recorded as input
+/// events if [`record_input()`](Asciicast::record_input) is set, and resizes the terminal, which is
+/// recorded as resize events. The recording is complete once the child has been waited for.
+///
+/// The child is made the leader of a new session, so it can't be used with
+/// [`ProcessGroup`](super::ProcessGroup); use [`ProcessSession`](super::ProcessSession) instead.
+/// The stdio set on the command is ignored, and it can't be used with [`VirtualTerminal`](super::VirtualTerminal),
+/// [`Expect`](super::Expect), or [`PtySession`](super::PtySession), which also set it. Once the
+/// command is spawned, or fails to, its stdin, stdout, and stderr are set to null, so that it
+/// doesn't keep the terminal open: set them again to spawn it without this wrapper.
+///
+/// Recordings can be played back with [`replay_asciicast()`](crate::replay_asciicast).
+///
+/// ```rust,no_run
+/// use process_wrap::tokio::*;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let mut child = CommandWrap::with_new("make", |command| { command.arg("deploy"); })
+///     .wrap(Asciicast::new("deploy.cast").size(120, 40).title("deploy"))
+///     .spawn()?;
+/// child.wait().await?;
+/// # Ok(()) }
+/// ```
+///
+/// [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+/// [asciinema]: https://asciinema.org
+#[derive(Debug)]
+pub struct Asciicast {
+	path: PathBuf,
+	header: Header,
+	record_input: bool,
+	spawning: Option<(OwnedFd, Recording)>,
+}
+
+impl Asciicast {
+	/// Record to a file at this path, on an 80×24 terminal.
+	///
+	/// The file is created when the command is spawned, replacing any existing file.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self {
+			path: path.into(),
+			header: Header {
+				width: 80,
+				height: 24,
+				title: None,
+				env: Vec::new(),
+			},
+			record_input: false,
+			spawning: None,
+		}
+	}
+
+	/// Set the initial terminal size, in columns and rows.
+	///
+	/// Returns `self` for chaining.
+	pub fn size(mut self, width: u16, height: u16) -> Self {
+		self.header.width = width;
+		self.header.height = height;
+		self
+	}
+
+	/// Set the title of the recording.
+	///
+	/// Returns `self` for chaining.
+	pub fn title(mut self, title: impl Into<String>) -> Self {
+		self.header.title = Some(title.into());
+		self
+	}
+
+	/// Also record the input sent to the child.
+	///
+	/// This is off by default, as input may contain secrets.
+	///
+	/// Returns `self` for chaining.
+	pub fn record_input(mut self) -> Self {
+		self.record_input = true;
+		self
+	}
+}
+
+/// Wrapper for `Child` which records its terminal session.
+///
+/// Sending input writes to the terminal synchronously, as for a blocking pipe.
+///
+/// See [`Asciicast`] for details.
+#[derive(Debug)]
+pub struct AsciicastChild {
+	inner: Box<dyn ChildWrapper>,
+	terminal: File,
+	recorder: Recorder,
+}
+
+impl AsciicastChild {
+	/// Send input to the child through its terminal.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self, input)))]
+	pub fn send(&mut self, input: impl AsRef<[u8]>) -> Result<()> {
+		let input = input.as_ref();
+		self.terminal.write_all(input)?;
+		self.recorder.input(input);
+		Ok(())
+	}
+
+	/// Resize the child's terminal, in columns and rows.
+	///
+	/// The child's foreground process group is sent `SIGWINCH`.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	pub fn resize(&mut self, width: u16, height: u16) -> Result<()> {
+		resize_pty(self.terminal.as_fd(), width, height)?;
+		self.recorder.resize(width, height);
+		Ok(())
+	}
+}
+
+impl CommandWrapper for Asciicast {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![
+			#[cfg(feature = "process-group")]
+			WrapperConstraint::conflicts_with::<super::ProcessGroup>(),
+			#[cfg(feature = "process-session")]
+			WrapperConstraint::after::<super::ProcessSession>().reorderable(),
+			#[cfg(feature = "virtual-terminal")]
+			WrapperConstraint::conflicts_with::<super::VirtualTerminal>(),
+			#[cfg(feature = "expect")]
+			WrapperConstraint::conflicts_with::<super::Expect>(),
+			WrapperConstraint::conflicts_with::<super::pty::PtyTerminal>(),
+		]
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		let pty = open_pty_sized(self.header.width, self.header.height)?;
+		command
+			.stdin(pty.slave.try_clone()?)
+			.stdout(pty.slave.try_clone()?)
+			.stderr(pty.slave);
+		unsafe {
+			command.pre_exec(claim_pty);
+		}
+
+		self.header.env = HEADER_ENV
+			.into_iter()
+			.filter_map(|name| {
+				let value = match command.as_std().get_envs().find(|(key, _)| *key == name) {
+					Some((_, value)) => value?.to_owned(),
+					None => env::var_os(name)?,
+				};
+				Some((name.to_owned(), value.to_string_lossy().into_owned()))
+			})
+			.collect();
+		let recording = Recording::create(&self.path, &self.header, self.record_input)?;
+		self.spawning = Some((pty.master, recording));
+		Ok(())
+	}
+
+	fn post_spawn(
+		&mut self,
+		command: &mut Command,
+		_child: &mut Child,
+		_core: &CommandWrap,
+	) -> Result<()> {
+		release_terminal(command);
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let (master, recording) = self
+			.spawning
+			.take()
+			.expect("wrap_child is only called after pre_spawn");
+		Ok(Box::new(AsciicastChild {
+			recorder: Recorder::start(recording, master.try_clone()?)?,
+			terminal: File::from(master),
+			inner,
+		}))
+	}
+
+	fn spawn_failed(&mut self, command: &mut Command, _error: &Error, _core: &CommandWrap) {
+		self.spawning = None;
+		release_terminal(command);
+	}
+}
+
+/// Don't keep the child's terminal open through the command.
+fn release_terminal(command: &mut Command) {
+	command
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null());
+}
+
+impl ChildWrapper for AsciicastChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wait(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitStatus>> + Send + '_>> {
+		Box::pin(async {
+			let status = self.inner.wait().await?;
+			let finisher = self.recorder.finisher();
+			spawn_blocking(move || finisher.finish())
+				.await
+				.map_err(Error::other)??;
+			Ok(status)
+		})
+	}
+}
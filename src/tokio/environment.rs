@@ -0,0 +1,207 @@
+use std::{
+	ffi::{OsStr, OsString},
+	io::{Error, Result},
+	path::PathBuf,
+};
+
+use tokio::process::Command;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::environment::{CommandEnv, EnvMap, EnvPolicy, RestoredEnv, changes_from_parent};
+
+use super::{CommandWrap, CommandWrapper};
+
+/// Wrapper which applies an environment policy to a `Command`.
+///
+/// The child's environment is built, from lowest to highest precedence, from:
+///
+/// - the parent's environment, or if [`clear()`](Self::clear) is set, only the variables whose names
+///   match one of the [`allow()`](Self::allow) patterns;
+/// - the [env files](Self::env_file), in the order they were added;
+/// - the variables set on the `Command` itself;
+/// - the [pinned](Self::pin) variables.
+///
+/// Env files are read at spawn time, and spawning fails if one can't be read or parsed. They have
+/// `KEY=value` lines, with optional `export` prefixes, `#` comments, and single- or double-quoted
+/// values. Variables aren't interpolated.
+///
+/// Adding this wrapper more than once merges the policies: clearing applies if any of them clear,
+/// and allow patterns, env files, and pinned variables are all combined, with those of the later
+/// wrappers taking precedence.
+///
+/// The environment the child was spawned with is available from
+/// [`effective()`](Self::effective) afterwards, for auditing.
+///
+/// The policy is applied by setting and removing variables on the `Command`, which are put back
+/// once it's spawned, so the policy is applied afresh on every spawn. Variables which weren't set
+/// on the `Command` before can't be unset, so they're set to the parent's values (or removed if the
+/// parent doesn't have them) instead: spawning the `Command` without this wrapper still gives the
+/// child the parent's environment. Whether `env_clear()` was called on the `Command` can't be
+/// seen, so this wrapper doesn't support it: use [`clean()`](Self::clean) instead.
+///
+/// ```rust,no_run
+/// # #[tokio::main(flavor = "current_thread")] async fn main() -> std::io::Result<()> {
+/// use process_wrap::tokio::*;
+///
+/// let mut command = CommandWrap::with_new("make", |_| {});
+/// command.wrap(
+///     Environment::clean()
+///         .allow("PATH")
+///         .allow("CARGO_*")
+///         .env_file(".env")
+///         .pin("LANG", "C.UTF-8")
+///         .pin("TZ", "UTC"),
+/// );
+/// command.spawn()?.wait().await?;
+///
+/// let env = command.get_wrap::<Environment>().unwrap().effective().unwrap();
+/// eprintln!("built with {env:?}");
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+	policy: EnvPolicy,
+	effective: Option<EnvMap>,
+	saved: Option<(CommandEnv, Vec<OsString>)>,
+	restored: RestoredEnv,
+}
+
+impl Environment {
+	/// Create an environment wrapper which starts from the parent's environment.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create an environment wrapper which starts from an empty environment.
+	///
+	/// This is the same as `Environment::new().clear()`.
+	pub fn clean() -> Self {
+		Self::new().clear()
+	}
+
+	/// Don't inherit the parent's environment, except for allowed variables.
+	///
+	/// Returns `self` for chaining.
+	pub fn clear(mut self) -> Self {
+		self.policy.clear = true;
+		self
+	}
+
+	/// Inherit the parent's variables whose names match this pattern, when clearing.
+	///
+	/// In the pattern, `*` matches any run of characters and `?` any single character.
+	///
+	/// Returns `self` for chaining.
+	pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+		self.policy.allow.push(pattern.into());
+		self
+	}
+
+	/// Load variables from an env file.
+	///
+	/// Returns `self` for chaining.
+	pub fn env_file(mut self, path: impl Into<PathBuf>) -> Self {
+		self.policy.files.push(path.into());
+		self
+	}
+
+	/// Set a variable, overriding any other source.
+	///
+	/// Returns `self` for chaining.
+	pub fn pin(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+		self.policy
+			.pinned
+			.push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+		self
+	}
+
+	/// Work out the environment the command would be spawned with, without spawning it.
+	///
+	/// This fails if an env file can't be read or parsed.
+	pub fn resolve(&self, command: &Command) -> Result<Vec<(OsString, OsString)>> {
+		self.policy
+			.resolve(&self.command_env(command))
+			.map(|vars| vars.into_iter().collect())
+	}
+
+	/// The environment the command was last spawned with, sorted by name.
+	///
+	/// Returns `None` if it hasn't been spawned yet.
+	pub fn effective(&self) -> Option<Vec<(&OsStr, &OsStr)>> {
+		self.effective.as_ref().map(|vars| {
+			vars.iter()
+				.map(|(key, value)| (key.as_os_str(), value.as_os_str()))
+				.collect()
+		})
+	}
+
+	fn command_env(&self, command: &Command) -> CommandEnv {
+		CommandEnv::of(command.as_std(), &self.restored)
+	}
+
+	/// Undo the changes made to the command's variables when the policy was applied for a spawn.
+	fn restore(&mut self, command: &mut Command) {
+		let Some((env, changed)) = self.saved.take() else {
+			return;
+		};
+
+		for key in changed {
+			let value = match env.get(&key) {
+				Some(value) => {
+					self.restored.remove(&key);
+					value.map(ToOwned::to_owned)
+				}
+				None => {
+					let value = std::env::var_os(&key);
+					self.restored.insert(key.clone(), value.clone());
+					value
+				}
+			};
+			match value {
+				Some(value) => command.env(key, value),
+				None => command.env_remove(key),
+			};
+		}
+	}
+}
+
+impl CommandWrapper for Environment {
+	fn extend(&mut self, other: Box<dyn CommandWrapper>) {
+		let other = (other as Box<dyn std::any::Any>)
+			.downcast::<Self>()
+			.expect("extend() is only called with the same type");
+		self.policy.merge(other.policy);
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		let env = self.command_env(command);
+		let vars = self.policy.resolve(&env)?;
+		let changes = changes_from_parent(&vars);
+		for (key, value) in &changes {
+			match value {
+				Some(value) => command.env(key, value),
+				None => command.env_remove(key),
+			};
+		}
+		let changed = changes.into_iter().map(|(key, _)| key).collect();
+		self.effective = Some(vars);
+		self.saved = Some((env, changed));
+		Ok(())
+	}
+
+	fn post_spawn(
+		&mut self,
+		command: &mut Command,
+		_child: &mut tokio::process::Child,
+		_core: &CommandWrap,
+	) -> Result<()> {
+		self.restore(command);
+		Ok(())
+	}
+
+	fn spawn_failed(&mut self, command: &mut Command, _error: &Error, _core: &CommandWrap) {
+		self.restore(command);
+	}
+}
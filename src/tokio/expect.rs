@@ -0,0 +1,248 @@
+use std::{
+	future::{Future, pending},
+	io::{Error, ErrorKind, Result},
+	pin::Pin,
+	process::{ExitStatus, Stdio},
+	time::Duration,
+};
+
+use tokio::{
+	io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+	process::{ChildStderr, ChildStdin, ChildStdout, Command},
+	time::{Instant, timeout_at},
+};
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::expect::{Exchange, ExpectMatch, Expecter};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Wrapper which scripts an interaction with the child, like `expect(1)`.
+///
+/// This pipes the child's stdin and stdout, and optionally merges its stderr into the output to
+/// match against. The child wrapper, [`ExpectChild`], can then wait for output matching a regular
+/// expression with [`expect()`](ExpectChild::expect), send input with
+/// [`send_line()`](ExpectChild::send_line), and wait for the output to end with
+/// [`expect_eof()`](ExpectChild::expect_eof). Everything sent and received is kept in a
+/// [transcript](ExpectChild::transcript).
+///
+/// When an expectation isn't met in time, the error includes the most recent unmatched output.
+///
+/// As it sets the command's stdio, it can't be used with the wrappers which run the child on a
+/// pseudo-terminal: `Asciicast`, `VirtualTerminal`, or `PtySession`.
+///
+/// The output is only read while waiting for an expectation, so a child which writes a lot of
+/// output in between may block on a full pipe until then. Timeouts need Tokio's timer, which is
+/// enabled on the runtime with `enable_time()` or `enable_all()`.
+///
+/// ```rust,no_run
+/// # #[tokio::main(flavor = "current_thread")] async fn main() -> std::io::Result<()> {
+/// use process_wrap::tokio::*;
+/// use std::time::Duration;
+///
+/// let mut child = CommandWrap::with_new("python3", |command| { command.arg("-i"); })
+///     .wrap(Expect::new().with_stderr())
+///     .spawn()?;
+/// let session = child.find_layer_mut::<ExpectChild>().unwrap();
+/// session.expect(">>> ", Duration::from_secs(5)).await?;
+/// session.send_line("print(6 * 7)").await?;
+/// let answer = session.expect(r"\d+", Duration::from_secs(5)).await?;
+/// assert_eq!(answer.matched, "42");
+/// session.send_line("exit()").await?;
+/// session.expect_eof(Duration::from_secs(5)).await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Expect {
+	stderr: bool,
+}
+
+impl Expect {
+	/// Create an expect wrapper which matches against the child's stdout.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Also match against the child's stderr, merged with stdout in the order it's read.
+	///
+	/// Returns `self` for chaining.
+	pub fn with_stderr(mut self) -> Self {
+		self.stderr = true;
+		self
+	}
+}
+
+/// Wrapper for `Child` which scripts an interaction with it.
+///
+/// See [`Expect`] for details.
+#[derive(Debug)]
+pub struct ExpectChild {
+	inner: Box<dyn ChildWrapper>,
+	stdin: Option<ChildStdin>,
+	stdout: Option<ChildStdout>,
+	stderr: Option<ChildStderr>,
+	expecter: Expecter,
+}
+
+impl ExpectChild {
+	/// Wait for output matching a regular expression.
+	///
+	/// The pattern is matched against the output received since the previous match, and the
+	/// output up to the end of the match is consumed. Fails with [`ErrorKind::TimedOut`] if there's
+	/// no match in time, or [`ErrorKind::UnexpectedEof`] if the output ends without one.
+	pub async fn expect(&mut self, pattern: &str, timeout: Duration) -> Result<ExpectMatch> {
+		let regex = Expecter::compile(pattern)?;
+		let expecting = format!("{pattern:?}");
+		self.receive_until(&expecting, timeout, |expecter| expecter.find(&regex))
+			.await
+	}
+
+	/// Wait for the output to end, returning whatever wasn't matched yet.
+	pub async fn expect_eof(&mut self, timeout: Duration) -> Result<String> {
+		self.receive_until("end of output", timeout, |expecter| {
+			expecter.is_closed().then(|| expecter.take_unmatched())
+		})
+		.await
+	}
+
+	/// Send input to the child.
+	pub async fn send(&mut self, input: impl AsRef<[u8]>) -> Result<()> {
+		let input = input.as_ref();
+		let stdin = self
+			.stdin
+			.as_mut()
+			.ok_or_else(|| Error::new(ErrorKind::BrokenPipe, "stdin was closed"))?;
+		stdin.write_all(input).await?;
+		stdin.flush().await?;
+		self.expecter.sent(input);
+		Ok(())
+	}
+
+	/// Send a line of input to the child.
+	pub async fn send_line(&mut self, line: &str) -> Result<()> {
+		self.send(format!("{line}\n")).await
+	}
+
+	/// Close the child's stdin, signaling the end of input.
+	pub fn close_stdin(&mut self) {
+		self.stdin = None;
+	}
+
+	/// Everything sent to and received from the child so far, in order.
+	pub fn transcript(&self) -> &[Exchange] {
+		self.expecter.transcript()
+	}
+
+	async fn receive_until<T>(
+		&mut self,
+		expecting: &str,
+		timeout: Duration,
+		mut done: impl FnMut(&mut Expecter) -> Option<T>,
+	) -> Result<T> {
+		let deadline = Instant::now() + timeout;
+		loop {
+			if let Some(found) = done(&mut self.expecter) {
+				return Ok(found);
+			}
+			if self.expecter.is_closed() {
+				return Err(self.expecter.ended(expecting));
+			}
+
+			match timeout_at(deadline, self.read_output()).await {
+				Ok(Ok(Some(data))) => self.expecter.received(&data),
+				Ok(Ok(None)) => self.expecter.closed(),
+				Ok(Err(err)) => return Err(err),
+				Err(_) => return Err(self.expecter.timed_out(expecting, timeout)),
+			}
+		}
+	}
+
+	/// Read whatever output comes first, or `None` once all of it has ended.
+	async fn read_output(&mut self) -> Result<Option<Vec<u8>>> {
+		let mut out_buf = vec![0; 8 * 1024];
+		let mut err_buf = vec![0; 8 * 1024];
+		while self.stdout.is_some() || self.stderr.is_some() {
+			tokio::select! {
+				read = read_from(self.stdout.as_mut(), &mut out_buf) => match read? {
+					0 => self.stdout = None,
+					n => return Ok(Some(out_buf[..n].to_vec())),
+				},
+				read = read_from(self.stderr.as_mut(), &mut err_buf) => match read? {
+					0 => self.stderr = None,
+					n => return Ok(Some(err_buf[..n].to_vec())),
+				},
+			}
+		}
+		Ok(None)
+	}
+}
+
+/// Read from a pipe, or never finish if there isn't one.
+async fn read_from(pipe: Option<&mut (impl AsyncRead + Unpin)>, buf: &mut [u8]) -> Result<usize> {
+	match pipe {
+		Some(pipe) => pipe.read(buf).await,
+		None => pending().await,
+	}
+}
+
+impl CommandWrapper for Expect {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![
+			#[cfg(all(unix, feature = "asciicast"))]
+			WrapperConstraint::conflicts_with::<super::Asciicast>(),
+			#[cfg(all(unix, feature = "virtual-terminal"))]
+			WrapperConstraint::conflicts_with::<super::VirtualTerminal>(),
+			#[cfg(all(unix, feature = "pty"))]
+			WrapperConstraint::conflicts_with::<super::pty::PtyTerminal>(),
+		]
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		command.stdin(Stdio::piped()).stdout(Stdio::piped());
+		if self.stderr {
+			command.stderr(Stdio::piped());
+		}
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		mut inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let stderr = if self.stderr {
+			inner.stderr().take()
+		} else {
+			None
+		};
+
+		Ok(Box::new(ExpectChild {
+			stdin: inner.stdin().take(),
+			stdout: inner.stdout().take(),
+			stderr,
+			inner,
+			expecter: Expecter::default(),
+		}))
+	}
+}
+
+impl ChildWrapper for ExpectChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	fn wait(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitStatus>> + Send + '_>> {
+		// like the Child's own stdin, ours must be closed so the child doesn't wait for input
+		self.close_stdin();
+		self.inner.wait()
+	}
+}
@@ -96,6 +96,7 @@ impl CommandWrapper for JobObject {
 pub struct JobObjectChild {
 	inner: Box<dyn ChildWrapper>,
 	exit_status: ChildExitStatus,
+	kill_reason: Option<String>,
 	job_port: JobPort,
 }
 
@@ -105,6 +106,7 @@ impl JobObjectChild {
 		Self {
 			inner,
 			exit_status: ChildExitStatus::Running,
+			kill_reason: None,
 			job_port,
 		}
 	}
@@ -112,10 +114,10 @@ impl JobObjectChild {
 
 impl ChildWrapper for JobObjectChild {
 	fn inner(&self) -> &dyn ChildWrapper {
-		self.inner.inner()
+		&*self.inner
 	}
 	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
-		self.inner.inner_mut()
+		&mut *self.inner
 	}
 	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
 		// manually drop the completion port
@@ -124,12 +126,20 @@ impl ChildWrapper for JobObjectChild {
 		// we leave the job handle unclosed, otherwise the Child is useless
 		// (as closing it will terminate the job)
 
-		self.inner.into_inner()
+		self.inner
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
 	fn start_kill(&mut self) -> Result<()> {
-		terminate_job(self.job_port.job, 1)
+		terminate_job(self.job_port.job, 1)?;
+		self.kill_reason = Some("job object terminated".into());
+		Ok(())
+	}
+
+	fn kill_reason(&self) -> Option<String> {
+		self.kill_reason
+			.clone()
+			.or_else(|| self.inner.kill_reason())
 	}
 
 	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
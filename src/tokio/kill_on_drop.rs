@@ -1,20 +1,165 @@
-use std::io::Result;
+use std::{io::Result, time::Duration};
 
 use tokio::process::Command;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
 
-use super::{CommandWrap, CommandWrapper};
+use crate::kill_on_drop::{DropKill, in_background};
 
-/// Shim wrapper which sets kill-on-drop on a `Command`.
+use super::{ChildWrapper, CommandWrap, CommandWrapper};
+
+/// Wrapper which kills the child when it's dropped.
+///
+/// By default, this sets kill-on-drop on the `Command`, so Tokio sends the child `SIGKILL` (or
+/// terminates it, on Windows) when it's dropped while still running, and reaps it in the
+/// background. The flag is also stored in the wrapper, so it can be used by other wrappers.
+///
+/// With [`graceful()`](KillOnDrop::graceful), it's sent `SIGTERM` (or [another
+/// signal](KillOnDrop::signal)) first, and only `SIGKILL` if it hasn't exited after the grace
+/// period. As Windows has no signals to ask politely with, the grace period is ignored there.
+///
+/// When used together with `ProcessGroup` or `ProcessSession`, the whole process group is killed
+/// and reaped instead of only the child; with `JobObject`, the whole job is.
+///
+/// ```rust,no_run
+/// use process_wrap::tokio::*;
+/// use std::time::Duration;
 ///
-/// This wrapper exists to be able to set the kill-on-drop flag on a `Command` and also store that
-/// fact in the wrapper, so that it can be used by other wrappers. Notably this is used by the
-/// `JobObject` wrapper.
+/// let child = CommandWrap::with_new("watch", |command| { command.arg("ls"); })
+///     .wrap(ProcessGroup::leader())
+///     .wrap(KillOnDrop::graceful(Duration::from_secs(5)))
+///     .spawn()?;
+/// drop(child);
+/// # Ok::<(), std::io::Error>(())
+/// ```
 #[derive(Clone, Copy, Debug)]
-pub struct KillOnDrop;
+pub struct KillOnDrop {
+	pub(crate) policy: DropKill,
+}
+
+/// Kill the child with `SIGKILL` when it's dropped.
+///
+/// This is a constant rather than a unit struct so that `.wrap(KillOnDrop)` keeps working now that
+/// the wrapper can be configured.
+#[allow(non_upper_case_globals)]
+pub const KillOnDrop: KillOnDrop = KillOnDrop {
+	policy: DropKill::immediate(),
+};
+
+impl KillOnDrop {
+	/// Kill the child with `SIGTERM` when it's dropped, then with `SIGKILL` after a grace period.
+	pub const fn graceful(grace: Duration) -> Self {
+		Self {
+			policy: DropKill::graceful(grace),
+		}
+	}
+
+	/// Change the first signal sent when there's a grace period.
+	///
+	/// This method is only available on Unix.
+	///
+	/// Returns `self` for chaining.
+	#[cfg(unix)]
+	pub const fn signal(mut self, sig: i32) -> Self {
+		self.policy.signal = sig;
+		self
+	}
+
+	/// Whether the child is killed by a process group or job object wrapper instead.
+	fn handled_elsewhere(core: &CommandWrap) -> bool {
+		#[cfg(all(unix, feature = "process-group"))]
+		if core.has_wrap::<super::ProcessGroup>() {
+			return true;
+		}
+		#[cfg(all(unix, feature = "process-session"))]
+		if core.has_wrap::<super::ProcessSession>() {
+			return true;
+		}
+		#[cfg(all(windows, feature = "job-object"))]
+		if core.has_wrap::<super::JobObject>() {
+			return true;
+		}
+
+		let _ = core;
+		false
+	}
+}
+
+/// Wrapper for `Child` which kills it when dropped, after a grace period.
+#[derive(Debug)]
+pub struct KillOnDropChild {
+	inner: Option<Box<dyn ChildWrapper>>,
+	policy: DropKill,
+}
 
 impl CommandWrapper for KillOnDrop {
 	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
-		command.kill_on_drop(true);
+		// Tokio can't wait out a grace period, so that's left to the child wrapper
+		command.kill_on_drop(cfg!(windows) || self.policy.grace.is_none());
 		Ok(())
 	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		if Self::handled_elsewhere(core) || cfg!(windows) || self.policy.grace.is_none() {
+			return Ok(inner);
+		}
+
+		Ok(Box::new(KillOnDropChild {
+			inner: Some(inner),
+			policy: self.policy,
+		}))
+	}
+}
+
+impl KillOnDropChild {
+	fn child(&self) -> &dyn ChildWrapper {
+		self.inner.as_deref().expect("only taken on drop")
+	}
+
+	fn child_mut(&mut self) -> &mut dyn ChildWrapper {
+		self.inner.as_deref_mut().expect("only taken on drop")
+	}
+}
+
+impl ChildWrapper for KillOnDropChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		self.child()
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		self.child_mut()
+	}
+	fn into_inner(mut self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner.take().expect("only taken on drop")
+	}
+}
+
+impl Drop for KillOnDropChild {
+	fn drop(&mut self) {
+		let Some(mut inner) = self.inner.take() else {
+			return;
+		};
+		if !matches!(inner.try_wait(), Ok(None)) {
+			return;
+		}
+
+		let policy = self.policy;
+		in_background(move || {
+			#[cfg(unix)]
+			if policy.grace.is_some() {
+				inner.signal(policy.signal).ok();
+				if policy.wait_grace(|| !matches!(inner.try_wait(), Ok(None))) {
+					return;
+				}
+			}
+
+			// there may not be a runtime to wait on, but try_wait() doesn't need one
+			inner.start_kill().ok();
+			policy.wait_killed(|| !matches!(inner.try_wait(), Ok(None)));
+		});
+	}
 }
@@ -0,0 +1,94 @@
+use std::io::{Error, Result};
+
+use nix::unistd::Pid;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::unix::kill_tree;
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper};
+
+/// Wrapper which kills every descendant of the child along with it.
+///
+/// Killing a process group with `ProcessGroup` or `ProcessSession` misses descendants which moved
+/// to another group or session, such as daemons which call `setsid()`. Instead, this finds all of
+/// the child's descendants in `/proc`, freezing each one with `SIGSTOP` so it can't fork any
+/// further, then kills them all with `SIGKILL`, and reaps those which are our own children.
+///
+/// Descendants which were orphaned before the kill, e.g. by a daemon double-forking, have been
+/// reparented away from the child and can't be found this way.
+///
+/// This wrapper provides a child wrapper: [`KillTreeChild`].
+///
+/// This wrapper is Linux-only.
+///
+/// ```rust,no_run
+/// # #[tokio::main(flavor = "current_thread")] async fn main() -> std::io::Result<()> {
+/// use process_wrap::tokio::*;
+///
+/// let mut child = CommandWrap::with_new("service", |command| { command.arg("start"); })
+///     .wrap(ProcessGroup::leader())
+///     .wrap(KillTree)
+///     .spawn()?;
+/// Box::into_pin(child.kill()).await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct KillTree;
+
+/// Wrapper for `Child` which kills every descendant along with it.
+#[derive(Debug)]
+pub struct KillTreeChild {
+	inner: Box<dyn ChildWrapper>,
+	killed: bool,
+}
+
+impl CommandWrapper for KillTree {
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		Ok(Box::new(KillTreeChild {
+			inner,
+			killed: false,
+		}))
+	}
+}
+
+impl ChildWrapper for KillTreeChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn start_kill(&mut self) -> Result<()> {
+		// once reaped, the PID may have been reused by an unrelated process
+		let tree = if let (None, Some(pid)) = (self.inner.try_wait()?, self.inner.id()) {
+			let pid = i32::try_from(pid).map_err(Error::other)?;
+			kill_tree(Pid::from_raw(pid)).inspect(|()| self.killed = true)
+		} else {
+			Ok(())
+		};
+
+		// let the inner wrappers know, e.g. to kill the rest of a process group, even if some of
+		// the tree couldn't be killed
+		let inner = self.inner.start_kill();
+		tree.and(inner)
+	}
+
+	fn kill_reason(&self) -> Option<String> {
+		if self.killed {
+			Some("process tree killed".into())
+		} else {
+			self.inner.kill_reason()
+		}
+	}
+}
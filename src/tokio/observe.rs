@@ -0,0 +1,201 @@
+use std::{
+	future::Future,
+	io::{Error, Result},
+	pin::Pin,
+	process::ExitStatus,
+	sync::Arc,
+	time::Instant,
+};
+
+use tokio::process::{Child, Command};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::Observer;
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper};
+
+/// Wrapper which reports lifecycle events of a `Command` and its child to [`Observer`]s.
+///
+/// Observers are notified before spawn, on spawn (or spawn failure), when the child is signalled
+/// or killed, when it exits, and when operations on it return errors.
+///
+/// Only calls which go through this wrapper's child wrapper are observed. To observe all signals,
+/// kills, and waits made on the child, this wrapper should be added last.
+///
+/// Adding this wrapper more than once merges the observers, so all of them are notified.
+///
+/// This wrapper provides a child wrapper: [`ObserveChild`].
+///
+/// ```rust,no_run
+/// use process_wrap::{Observer, tokio::*};
+/// use std::sync::Arc;
+///
+/// #[derive(Debug)]
+/// struct Audit;
+/// impl Observer for Audit {
+///     fn on_spawned(&self, pid: u32) {
+///         eprintln!("spawned {pid}");
+///     }
+/// }
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let audit = Arc::new(Audit);
+/// let mut child = CommandWrap::with_new("ls", |_| {})
+///     .wrap(Observe::new(audit.clone()))
+///     .spawn()?;
+/// child.wait().await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Observe {
+	observers: Vec<Arc<dyn Observer>>,
+	spawned_at: Option<Instant>,
+}
+
+impl Observe {
+	/// Create an observe wrapper with one observer.
+	pub fn new(observer: Arc<dyn Observer>) -> Self {
+		Self::default().with(observer)
+	}
+
+	/// Add an observer.
+	///
+	/// Returns `self` for chaining.
+	pub fn with(mut self, observer: Arc<dyn Observer>) -> Self {
+		self.observers.push(observer);
+		self
+	}
+
+	fn each(&self, f: impl Fn(&dyn Observer)) {
+		for observer in &self.observers {
+			f(&**observer);
+		}
+	}
+}
+
+impl CommandWrapper for Observe {
+	fn extend(&mut self, other: Box<dyn CommandWrapper>) {
+		let other = (other as Box<dyn std::any::Any>)
+			.downcast::<Self>()
+			.expect("extend() is only called with the same type");
+		self.observers.extend(other.observers);
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		self.each(|o| o.on_pre_spawn(command.as_std()));
+		Ok(())
+	}
+
+	fn post_spawn(
+		&mut self,
+		_command: &mut Command,
+		_child: &mut Child,
+		_core: &CommandWrap,
+	) -> Result<()> {
+		self.spawned_at = Some(Instant::now());
+		Ok(())
+	}
+
+	fn spawn_failed(&mut self, _command: &mut Command, error: &Error, _core: &CommandWrap) {
+		self.spawned_at = None;
+		self.each(|o| o.on_error(None, error));
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let pid = inner
+			.id()
+			.expect("Command was reaped before we could read its PID");
+		self.each(|o| o.on_spawned(pid));
+
+		Ok(Box::new(ObserveChild {
+			inner,
+			observers: self.observers.clone(),
+			pid,
+			spawned_at: self.spawned_at.take().unwrap_or_else(Instant::now),
+			exited: false,
+		}))
+	}
+}
+
+/// Wrapper for `Child` which reports lifecycle events to [`Observer`]s.
+#[derive(Debug)]
+pub struct ObserveChild {
+	inner: Box<dyn ChildWrapper>,
+	observers: Vec<Arc<dyn Observer>>,
+	pid: u32,
+	spawned_at: Instant,
+	exited: bool,
+}
+
+impl ObserveChild {
+	fn each(&self, f: impl Fn(&dyn Observer)) {
+		for observer in &self.observers {
+			f(&**observer);
+		}
+	}
+
+	fn report<T>(&self, res: Result<T>) -> Result<T> {
+		if let Err(err) = &res {
+			self.each(|o| o.on_error(Some(self.pid), err));
+		}
+		res
+	}
+
+	fn report_exit(&mut self, status: ExitStatus) {
+		if !self.exited {
+			self.exited = true;
+			let duration = self.spawned_at.elapsed();
+			self.each(|o| o.on_exit(self.pid, status, duration));
+		}
+	}
+}
+
+impl ChildWrapper for ObserveChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+
+	fn start_kill(&mut self) -> Result<()> {
+		self.each(|o| o.on_kill(self.pid));
+		let res = self.inner.start_kill();
+		self.report(res)
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		let res = self.inner.try_wait();
+		let status = self.report(res)?;
+		if let Some(status) = status {
+			self.report_exit(status);
+		}
+		Ok(status)
+	}
+
+	fn wait(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitStatus>> + Send + '_>> {
+		Box::pin(async {
+			let res = self.inner.wait().await;
+			let status = self.report(res)?;
+			self.report_exit(status);
+			Ok(status)
+		})
+	}
+
+	#[cfg(unix)]
+	fn signal(&self, sig: i32) -> Result<()> {
+		self.each(|o| o.on_signal(self.pid, sig));
+		self.report(self.inner.signal(sig))
+	}
+}
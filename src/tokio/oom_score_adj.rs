@@ -0,0 +1,96 @@
+use std::io::{Error, Result};
+
+use nix::unistd::Pid;
+use tokio::process::Command;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::unix::OomScore;
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper};
+
+/// Wrapper which sets the OOM score adjustment of a `Command`.
+///
+/// This writes the value to `/proc/self/oom_score_adj` in the child before it execs. It ranges from
+/// -1000 (never kill) to 1000 (kill first); positive values make the kernel's out-of-memory killer
+/// pick the child before other processes. Lowering the value below our own requires privileges, and
+/// spawning fails without them.
+///
+/// This wrapper provides a child wrapper: [`OomScoreAdjChild`], which can change the adjustment of
+/// the running child, or of its whole process group.
+///
+/// This wrapper is Linux-only.
+///
+/// ```rust,no_run
+/// # #[tokio::main(flavor = "current_thread")] async fn main() -> std::io::Result<()> {
+/// use process_wrap::tokio::*;
+///
+/// let mut child = CommandWrap::with_new("worker", |_| {})
+///     .wrap(OomScoreAdj(500))
+///     .spawn()?;
+/// child.wait().await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct OomScoreAdj(pub i32);
+
+/// Wrapper for `Child` which can change its OOM score adjustment while it runs.
+#[derive(Debug)]
+pub struct OomScoreAdjChild {
+	inner: Box<dyn ChildWrapper>,
+}
+
+impl OomScoreAdjChild {
+	/// Change the OOM score adjustment of the child.
+	pub fn set_score(&self, adj: i32) -> Result<()> {
+		OomScore::new(adj)?.apply_to(self.pid()?)
+	}
+
+	/// Change the OOM score adjustment of every process in the child's process group.
+	///
+	/// This applies to the processes in the group at the time of the call. It fails if the child
+	/// isn't in its own group, e.g. when `ProcessGroup` isn't used, as that would change our own
+	/// group's score too.
+	pub fn set_group_score(&self, adj: i32) -> Result<()> {
+		OomScore::new(adj)?.apply_to_group(self.pid()?)
+	}
+
+	fn pid(&self) -> Result<Pid> {
+		let pid = self
+			.inner
+			.id()
+			.ok_or_else(|| Error::other("the child has already exited"))?;
+		Ok(Pid::from_raw(i32::try_from(pid).map_err(Error::other)?))
+	}
+}
+
+impl CommandWrapper for OomScoreAdj {
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		let score = OomScore::new(self.0)?;
+		unsafe {
+			command.pre_exec(move || score.apply_to_self());
+		}
+		Ok(())
+	}
+
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		Ok(Box::new(OomScoreAdjChild { inner }))
+	}
+}
+
+impl ChildWrapper for OomScoreAdjChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+}
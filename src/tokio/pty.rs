@@ -0,0 +1,159 @@
+use std::{
+	io::{Error, Result, stdin, stdout},
+	os::fd::{AsFd, OwnedFd},
+	process::ExitStatus,
+};
+
+use tokio::{process::Command, task::spawn_blocking};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::unix::{cancel_pipe, claim_pty, open_pty, relay_pty};
+
+use super::{CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Helper which runs a command attached to our terminal through a pseudo-terminal.
+///
+/// This helper is only available on Unix.
+///
+/// This is what `script(1)` and remote shells do: the command is spawned on a freshly allocated
+/// pseudo-terminal, as its controlling terminal and stdin, stdout and stderr, and bytes are relayed
+/// between it and our own input and output (by default, our stdin and stdout) until it's closed.
+/// If our input is a terminal, it's put in raw mode for the duration, so keys like `^C` reach the
+/// command's terminal rather than ours, and its window size changes (`SIGWINCH`) are propagated to
+/// the pseudo-terminal.
+///
+/// The terminal settings are restored when the session ends, whether it's by the pseudo-terminal
+/// closing, an error, a panic, or a terminating signal (`SIGHUP`, `SIGINT`, `SIGQUIT`, `SIGTERM`).
+/// In the latter case, the signal is raised again once the terminal is restored. To do this, the
+/// session installs its own handlers for these signals and `SIGWINCH` while it runs, so only one
+/// session can run at a time. It also means they take over from any Tokio signal streams for them.
+///
+/// The relaying runs on a blocking thread (see [`spawn_blocking`]). If the future returned by
+/// [`run()`](PtySession::run) is dropped before it completes, the relaying stops and the terminal
+/// settings are restored, without waiting for the command.
+///
+/// The command is made the leader of a new session, so it can't be used with
+/// [`ProcessGroup`](super::ProcessGroup); use [`ProcessSession`](super::ProcessSession) instead.
+/// The stdio set on the command is ignored.
+///
+/// ```rust,no_run
+/// use process_wrap::tokio::*;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let mut command = CommandWrap::with_new("bash", |_| {});
+/// command.wrap(ProcessSession);
+/// let status = PtySession::new().run(command).await?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Default)]
+pub struct PtySession {
+	input: Option<OwnedFd>,
+	output: Option<OwnedFd>,
+}
+
+impl PtySession {
+	/// Create a session relaying our stdin and stdout.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Relay input from this file descriptor instead of stdin.
+	///
+	/// Returns `self` for chaining.
+	pub fn with_input(mut self, input: impl Into<OwnedFd>) -> Self {
+		self.input = Some(input.into());
+		self
+	}
+
+	/// Relay output to this file descriptor instead of stdout.
+	///
+	/// Returns `self` for chaining.
+	pub fn with_output(mut self, output: impl Into<OwnedFd>) -> Self {
+		self.output = Some(output.into());
+		self
+	}
+
+	/// Spawn the command on a pseudo-terminal, relay it until it's closed, and wait for it.
+	///
+	/// The pseudo-terminal is closed once the command and any other process it was passed to
+	/// have exited. If an error occurs after the command was spawned, it's dropped, so wrappers
+	/// like [`KillOnDrop`](struct@super::KillOnDrop) apply.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	pub async fn run(self, mut command: CommandWrap) -> Result<ExitStatus> {
+		let input = match self.input {
+			Some(fd) => fd,
+			None => stdin().as_fd().try_clone_to_owned()?,
+		};
+		let output = match self.output {
+			Some(fd) => fd,
+			None => stdout().as_fd().try_clone_to_owned()?,
+		};
+
+		let pty = open_pty(input.as_fd())?;
+		command.wrap(PtyTerminal {
+			slave: Some(pty.slave),
+		});
+		let spawned = command.spawn();
+
+		// the command holds copies of the slave side, which must all be closed to see the end
+		drop(command);
+
+		let mut child = spawned?;
+		let master = pty.master;
+
+		// if this future is dropped, the relay can't be aborted like a task, so it's told to stop
+		// (and restore the terminal) by this end of the pipe closing along with it
+		let (cancel, _cancel_on_drop) = cancel_pipe()?;
+		spawn_blocking(move || {
+			relay_pty(
+				master.as_fd(),
+				input.as_fd(),
+				output.as_fd(),
+				Some(cancel.as_fd()),
+			)
+		})
+		.await
+		.map_err(Error::other)??;
+		child.wait().await
+	}
+}
+
+/// Wrapper which puts the child on the slave side of a pseudo-terminal.
+#[derive(Debug)]
+pub(super) struct PtyTerminal {
+	slave: Option<OwnedFd>,
+}
+
+impl CommandWrapper for PtyTerminal {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![
+			#[cfg(feature = "process-group")]
+			WrapperConstraint::conflicts_with::<super::ProcessGroup>(),
+			#[cfg(feature = "process-session")]
+			WrapperConstraint::after::<super::ProcessSession>().reorderable(),
+			#[cfg(feature = "asciicast")]
+			WrapperConstraint::conflicts_with::<super::Asciicast>(),
+			#[cfg(feature = "virtual-terminal")]
+			WrapperConstraint::conflicts_with::<super::VirtualTerminal>(),
+			#[cfg(feature = "expect")]
+			WrapperConstraint::conflicts_with::<super::Expect>(),
+		]
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		if let Some(slave) = self.slave.take() {
+			command
+				.stdin(slave.try_clone()?)
+				.stdout(slave.try_clone()?)
+				.stderr(slave);
+			unsafe {
+				command.pre_exec(claim_pty);
+			}
+		}
+
+		Ok(())
+	}
+}
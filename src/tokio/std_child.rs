@@ -0,0 +1,259 @@
+use std::{
+	future::Future,
+	io::{Error, Result},
+	pin::Pin,
+	process::ExitStatus,
+};
+
+use futures::FutureExt;
+use tokio::{
+	process::{ChildStderr, ChildStdin, ChildStdout},
+	task::{JoinHandle, spawn_blocking},
+};
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use super::ChildWrapper;
+use crate::std::ChildWrapper as StdChildWrapper;
+
+type Reaping = JoinHandle<(Box<dyn StdChildWrapper>, Result<ExitStatus>)>;
+
+/// Adapter which makes a child from the std frontend usable from asynchronous code.
+///
+/// This implements the Tokio [`ChildWrapper`] over a `Box<dyn std::ChildWrapper>`. Methods go
+/// through the whole std wrapper chain, so e.g. `signal()` on a std `ProcessGroupChild` still
+/// signals the entire group.
+///
+/// Waiting is done on Tokio's blocking thread pool. On Unix, the adapter first waits for the
+/// process to exit without reaping it or holding on to the child, so that the wait can be cancelled
+/// (e.g. by a timeout in a `select!`) and the child still signalled or killed. Once the process
+/// has exited, the std wrappers are called to reap it. On other platforms, the child is handed to
+/// the blocking pool for the whole wait, and signalling or killing it while a cancelled wait is
+/// still in progress returns an error.
+///
+/// The child's piped stdin, stdout, and stderr are converted to their Tokio equivalents when the
+/// adapter is created, which must be done from within a Tokio runtime.
+///
+/// This adapter is the bottom of the Tokio wrapper chain: its `inner()` is itself, and as it
+/// doesn't contain a Tokio `Child`, `inner_child()` and friends will panic.
+///
+/// ```rust,no_run
+/// # #[tokio::main(flavor = "current_thread")] async fn main() -> std::io::Result<()> {
+/// use process_wrap::{std::CommandWrap, tokio::{ChildWrapper, StdChild}};
+///
+/// let child = CommandWrap::with_new("ls", |_| {}).spawn()?;
+/// let mut child = StdChild::new(child)?;
+/// child.wait().await?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct StdChild {
+	inner: Option<Box<dyn StdChildWrapper>>,
+	reaping: Option<Reaping>,
+	pid: u32,
+	stdin: Option<ChildStdin>,
+	stdout: Option<ChildStdout>,
+	stderr: Option<ChildStderr>,
+}
+
+impl StdChild {
+	/// Adapt a std child.
+	///
+	/// This fails if the child's pipes can't be registered with the Tokio runtime.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+	pub fn new(mut inner: Box<dyn StdChildWrapper>) -> Result<Self> {
+		Ok(Self {
+			pid: inner.id(),
+			stdin: inner.stdin().take().map(ChildStdin::from_std).transpose()?,
+			stdout: inner
+				.stdout()
+				.take()
+				.map(ChildStdout::from_std)
+				.transpose()?,
+			stderr: inner
+				.stderr()
+				.take()
+				.map(ChildStderr::from_std)
+				.transpose()?,
+			inner: Some(inner),
+			reaping: None,
+		})
+	}
+
+	/// Obtain a reference to the adapted std child.
+	///
+	/// Returns `None` while the child is being reaped on the blocking thread pool.
+	pub fn std_child(&self) -> Option<&dyn StdChildWrapper> {
+		self.inner.as_deref()
+	}
+
+	/// Obtain a mutable reference to the adapted std child.
+	///
+	/// Returns `None` while the child is being reaped on the blocking thread pool.
+	pub fn std_child_mut(&mut self) -> Option<&mut (dyn StdChildWrapper + 'static)> {
+		self.inner.as_deref_mut()
+	}
+
+	/// Get the std child back, with whichever of its pipes haven't been taken.
+	///
+	/// If the child is being reaped on the blocking thread pool, this waits for that to finish.
+	pub async fn into_std(mut self) -> Result<Box<dyn StdChildWrapper>> {
+		if let Some(reaping) = self.reaping.take() {
+			let (inner, _) = reaping.await.map_err(Error::other)?;
+			self.inner = Some(inner);
+		}
+
+		let mut inner = self.child_owned()?;
+
+		#[cfg(unix)]
+		{
+			if let Some(io) = self.stdin.take() {
+				*inner.stdin() = Some(io.into_owned_fd()?.into());
+			}
+			if let Some(io) = self.stdout.take() {
+				*inner.stdout() = Some(io.into_owned_fd()?.into());
+			}
+			if let Some(io) = self.stderr.take() {
+				*inner.stderr() = Some(io.into_owned_fd()?.into());
+			}
+		}
+		#[cfg(windows)]
+		{
+			if let Some(io) = self.stdin.take() {
+				*inner.stdin() = Some(io.into_owned_handle()?.into());
+			}
+			if let Some(io) = self.stdout.take() {
+				*inner.stdout() = Some(io.into_owned_handle()?.into());
+			}
+			if let Some(io) = self.stderr.take() {
+				*inner.stderr() = Some(io.into_owned_handle()?.into());
+			}
+		}
+
+		Ok(inner)
+	}
+
+	#[cfg(unix)]
+	fn child(&self) -> Result<&dyn StdChildWrapper> {
+		self.inner.as_deref().ok_or_else(Self::away)
+	}
+
+	fn child_mut(&mut self) -> Result<&mut dyn StdChildWrapper> {
+		self.reclaim()?;
+		match self.inner.as_deref_mut() {
+			Some(inner) => Ok(inner),
+			None => Err(Self::away()),
+		}
+	}
+
+	fn child_owned(&mut self) -> Result<Box<dyn StdChildWrapper>> {
+		self.inner.take().ok_or_else(Self::away)
+	}
+
+	fn away() -> Error {
+		Error::other("the child is being reaped on the blocking thread pool")
+	}
+
+	/// Take the child back from the blocking thread pool if it's done with it.
+	fn reclaim(&mut self) -> Result<Option<ExitStatus>> {
+		if !self.reaping.as_ref().is_some_and(JoinHandle::is_finished) {
+			return Ok(None);
+		}
+
+		// UNWRAP: is_finished() above means it's Some, and is ready
+		let (inner, res) = self
+			.reaping
+			.take()
+			.unwrap()
+			.now_or_never()
+			.unwrap()
+			.map_err(Error::other)?;
+		self.inner = Some(inner);
+		res.map(Some)
+	}
+
+	async fn wait_imp(&mut self) -> Result<ExitStatus> {
+		#[cfg(unix)]
+		if self.inner.is_some() {
+			let pid = nix::unistd::Pid::from_raw(i32::try_from(self.pid).map_err(Error::other)?);
+			// errors (like ECHILD if it was already reaped) are left to the wrappers to report
+			spawn_blocking(move || crate::unix::wait_exited(pid))
+				.await
+				.map_err(Error::other)?
+				.ok();
+
+			if let Some(status) = self.child_mut()?.try_wait()? {
+				return Ok(status);
+			}
+		}
+
+		// the wrappers still have work to do after the process exits, so let them block
+		if self.reaping.is_none() {
+			let mut inner = self.child_owned()?;
+			self.reaping = Some(spawn_blocking(move || {
+				let res = inner.wait();
+				(inner, res)
+			}));
+		}
+
+		// UNWRAP: just set above; it's only taken out once finished
+		let (inner, res) = self.reaping.as_mut().unwrap().await.map_err(Error::other)?;
+		self.reaping = None;
+		self.inner = Some(inner);
+		res
+	}
+}
+
+impl ChildWrapper for StdChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		self
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		self
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self
+	}
+	fn stdin(&mut self) -> &mut Option<ChildStdin> {
+		&mut self.stdin
+	}
+	fn stdout(&mut self) -> &mut Option<ChildStdout> {
+		&mut self.stdout
+	}
+	fn stderr(&mut self) -> &mut Option<ChildStderr> {
+		&mut self.stderr
+	}
+	fn id(&self) -> Option<u32> {
+		Some(self.pid)
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn start_kill(&mut self) -> Result<()> {
+		self.child_mut()?.start_kill()
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		if let Some(status) = self.reclaim()? {
+			return Ok(Some(status));
+		}
+
+		match self.inner.as_deref_mut() {
+			Some(inner) => inner.try_wait(),
+			None => Ok(None),
+		}
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wait(&mut self) -> Pin<Box<dyn Future<Output = Result<ExitStatus>> + Send + '_>> {
+		Box::pin(self.wait_imp())
+	}
+
+	fn kill_reason(&self) -> Option<String> {
+		self.inner.as_ref().and_then(|inner| inner.kill_reason())
+	}
+
+	#[cfg(unix)]
+	fn signal(&self, sig: i32) -> Result<()> {
+		self.child()?.signal(sig)
+	}
+}
@@ -0,0 +1,254 @@
+use std::{
+	fs::File,
+	io::{Error, Result, Write},
+	os::fd::{AsFd, OwnedFd},
+	process::Stdio,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use futures::{
+	StreamExt,
+	channel::mpsc::{Receiver, channel},
+};
+use tokio::{
+	process::{Child, Command},
+	time::{Instant, timeout_at},
+};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+use crate::{
+	unix::{claim_pty, open_pty_sized, resize_pty},
+	virtual_terminal::{Cell, LiveScreen, Screen},
+};
+
+use super::{ChildWrapper, CommandWrap, CommandWrapper, WrapperConstraint};
+
+/// Wrapper which runs the child on a virtual terminal, to look at its screen.
+///
+/// This wrapper is only available on Unix.
+///
+/// The child is spawned on its own pseudo-terminal, as its controlling terminal and stdin, stdout
+/// and stderr, and its output is fed to a VT100/xterm emulator which keeps a virtual [`Screen`].
+/// This is meant for testing terminal and full-screen programs: the child wrapper,
+/// [`VirtualTerminalChild`], gives the text on the screen, the cursor position and the attributes
+/// of each cell at any time, and can wait for the screen to match a predicate.
+///
+/// The emulator replies to the cursor position and device attributes queries, as some programs
+/// wait for those. Every character is taken to be one cell wide.
+///
+/// The child is made the leader of a new session, so it can't be used with
+/// [`ProcessGroup`](super::ProcessGroup); use [`ProcessSession`](super::ProcessSession) instead.
+/// The stdio set on the command is ignored, and it can't be used with [`Asciicast`](super::Asciicast),
+/// [`Expect`](super::Expect), or [`PtySession`](super::PtySession), which also set it. Once the
+/// command is spawned, or fails to, its stdin, stdout, and stderr are set to null, so that it
+/// doesn't keep the terminal open: set them again to spawn it without this wrapper.
+///
+/// ```rust,no_run
+/// use process_wrap::tokio::*;
+/// use std::time::Duration;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let mut child = CommandWrap::with_new("htop", |_| {})
+///     .wrap(VirtualTerminal::new(120, 40))
+///     .spawn()?;
+/// let terminal = child.find_layer_mut::<VirtualTerminalChild>().unwrap();
+/// terminal.wait_for_screen(|screen| screen.text().contains("Load average"), Duration::from_secs(5)).await?;
+/// terminal.send("q")?;
+/// child.wait().await?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct VirtualTerminal {
+	cols: u16,
+	rows: u16,
+	master: Option<OwnedFd>,
+}
+
+impl Default for VirtualTerminal {
+	fn default() -> Self {
+		Self::new(80, 24)
+	}
+}
+
+impl VirtualTerminal {
+	/// Create a virtual terminal of this size, in columns and rows.
+	pub fn new(cols: u16, rows: u16) -> Self {
+		Self {
+			cols,
+			rows,
+			master: None,
+		}
+	}
+}
+
+/// Wrapper for `Child` which keeps a virtual screen of its terminal.
+///
+/// See [`VirtualTerminal`] for details.
+#[derive(Debug)]
+pub struct VirtualTerminalChild {
+	inner: Box<dyn ChildWrapper>,
+	terminal: File,
+	screen: Arc<LiveScreen>,
+	// only to make this Sync, as it's only used through &mut self
+	updates: Mutex<Receiver<()>>,
+}
+
+impl VirtualTerminalChild {
+	/// A snapshot of the screen.
+	pub fn screen(&self) -> Screen {
+		self.screen.screen()
+	}
+
+	/// The text on the screen.
+	///
+	/// See [`Screen::text()`] for the format.
+	pub fn screen_text(&self) -> String {
+		self.screen.with_screen(Screen::text)
+	}
+
+	/// The position of the cursor, as zero-based row and column.
+	pub fn cursor(&self) -> (u16, u16) {
+		self.screen.with_screen(Screen::cursor)
+	}
+
+	/// The cell at a position, zero-based.
+	pub fn cell(&self, row: u16, col: u16) -> Option<Cell> {
+		self.screen
+			.with_screen(|screen| screen.cell(row, col).copied())
+	}
+
+	/// Wait until the screen matches the predicate, and return it.
+	///
+	/// The predicate is checked now and after each update of the screen. This needs the Tokio
+	/// timer to be enabled on the runtime. Fails with
+	/// [`ErrorKind::TimedOut`](std::io::ErrorKind::TimedOut) if the screen doesn't match in time,
+	/// or [`ErrorKind::UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) if the output ends
+	/// without it matching. The error message includes the text on the screen.
+	pub async fn wait_for_screen(
+		&mut self,
+		mut predicate: impl FnMut(&Screen) -> bool,
+		timeout: Duration,
+	) -> Result<Screen> {
+		let deadline = Instant::now() + timeout;
+		let updates = self
+			.updates
+			.get_mut()
+			.unwrap_or_else(|err| err.into_inner());
+		loop {
+			if let Some(screen) = self.screen.check(&mut predicate)? {
+				return Ok(screen);
+			}
+			if timeout_at(deadline, updates.next()).await.is_err() {
+				return Err(self.screen.timed_out_error(timeout));
+			}
+		}
+	}
+
+	/// Send input to the child, as if typed on the terminal.
+	///
+	/// This writes to the terminal synchronously, as for a blocking pipe.
+	pub fn send(&mut self, input: impl AsRef<[u8]>) -> Result<()> {
+		self.terminal.write_all(input.as_ref())
+	}
+
+	/// Resize the terminal, in columns and rows.
+	///
+	/// The child's foreground process group is sent `SIGWINCH`.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	pub fn resize(&mut self, cols: u16, rows: u16) -> Result<()> {
+		self.screen.resize(cols, rows);
+		resize_pty(self.terminal.as_fd(), cols, rows)
+	}
+}
+
+impl CommandWrapper for VirtualTerminal {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		vec![
+			#[cfg(feature = "process-group")]
+			WrapperConstraint::conflicts_with::<super::ProcessGroup>(),
+			#[cfg(feature = "process-session")]
+			WrapperConstraint::after::<super::ProcessSession>().reorderable(),
+			#[cfg(feature = "asciicast")]
+			WrapperConstraint::conflicts_with::<super::Asciicast>(),
+			#[cfg(feature = "expect")]
+			WrapperConstraint::conflicts_with::<super::Expect>(),
+			WrapperConstraint::conflicts_with::<super::pty::PtyTerminal>(),
+		]
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		let pty = open_pty_sized(self.cols, self.rows)?;
+		command
+			.stdin(pty.slave.try_clone()?)
+			.stdout(pty.slave.try_clone()?)
+			.stderr(pty.slave);
+		unsafe {
+			command.pre_exec(claim_pty);
+		}
+
+		self.master = Some(pty.master);
+		Ok(())
+	}
+
+	fn post_spawn(
+		&mut self,
+		command: &mut Command,
+		_child: &mut Child,
+		_core: &CommandWrap,
+	) -> Result<()> {
+		release_terminal(command);
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "tracing", instrument(level = "debug", skip(self)))]
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		let master = self
+			.master
+			.take()
+			.expect("wrap_child is only called after pre_spawn");
+		// a full channel already means there's an update to look at
+		let (mut notify, updates) = channel(0);
+		let screen = LiveScreen::start(self.cols, self.rows, master.try_clone()?, move || {
+			notify.try_send(()).ok();
+		})?;
+		Ok(Box::new(VirtualTerminalChild {
+			screen,
+			terminal: File::from(master),
+			updates: Mutex::new(updates),
+			inner,
+		}))
+	}
+
+	fn spawn_failed(&mut self, command: &mut Command, _error: &Error, _core: &CommandWrap) {
+		self.master = None;
+		release_terminal(command);
+	}
+}
+
+/// Don't keep the child's terminal open through the command.
+fn release_terminal(command: &mut Command) {
+	command
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null());
+}
+
+impl ChildWrapper for VirtualTerminalChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+}
@@ -0,0 +1,81 @@
+//! Tracing span and event helpers for the `TraceOutput` wrappers.
+
+use std::{ffi::OsStr, process::ExitStatus};
+
+use tracing::{Level, Span, field};
+
+/// The target of the spans and events emitted for child processes.
+const TARGET: &str = "process_wrap::output";
+
+/// Tracing macros need a level known at compile time, so dispatch a runtime level to each of them.
+///
+/// The bracketed tokens go before the level in the macro call, for `parent:`.
+macro_rules! at_level {
+	($level:expr, $mac:ident, [$($pre:tt)*], $($args:tt)*) => {
+		match $level {
+			Level::ERROR => ::tracing::$mac!(target: TARGET, $($pre)* Level::ERROR, $($args)*),
+			Level::WARN => ::tracing::$mac!(target: TARGET, $($pre)* Level::WARN, $($args)*),
+			Level::INFO => ::tracing::$mac!(target: TARGET, $($pre)* Level::INFO, $($args)*),
+			Level::DEBUG => ::tracing::$mac!(target: TARGET, $($pre)* Level::DEBUG, $($args)*),
+			Level::TRACE => ::tracing::$mac!(target: TARGET, $($pre)* Level::TRACE, $($args)*),
+		}
+	};
+}
+
+/// The command's program and arguments, captured at spawn time.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CommandLine {
+	program: String,
+	args: Vec<String>,
+}
+
+impl CommandLine {
+	pub fn new<'a>(program: &OsStr, args: impl Iterator<Item = &'a OsStr>) -> Self {
+		Self {
+			program: program.to_string_lossy().into_owned(),
+			args: args.map(|arg| arg.to_string_lossy().into_owned()).collect(),
+		}
+	}
+
+	/// Open the span for a child process.
+	///
+	/// The `status` field is left empty, to be recorded by [`exited`].
+	pub fn span(&self, level: Level, pid: Option<u32>) -> Span {
+		#[cfg(unix)]
+		let pgid = pid.and_then(|pid| {
+			nix::unistd::getpgid(Some(nix::unistd::Pid::from_raw(pid as _)))
+				.ok()
+				.map(|pgid| pgid.as_raw() as u32)
+		});
+		#[cfg(not(unix))]
+		let pgid: Option<u32> = None;
+
+		at_level!(
+			level,
+			span,
+			[],
+			"child",
+			program = %self.program,
+			args = ?self.args,
+			pid,
+			pgid,
+			status = field::Empty,
+		)
+	}
+}
+
+/// Emit a line of output as an event within the child's span.
+///
+/// Output that isn't valid UTF-8 is converted lossily. Trailing newlines are removed.
+pub(crate) fn line(level: Level, span: &Span, stream: &'static str, line: &[u8]) {
+	let line = line.strip_suffix(b"\n").unwrap_or(line);
+	let line = line.strip_suffix(b"\r").unwrap_or(line);
+	let line = String::from_utf8_lossy(line);
+	at_level!(level, event, [parent: span,], stream, "{line}");
+}
+
+/// Record the exit status on the child's span, and emit a final event within it.
+pub(crate) fn exited(level: Level, span: &Span, status: ExitStatus) {
+	span.record("status", field::display(status));
+	at_level!(level, event, [parent: span,], %status, "exited");
+}
@@ -0,0 +1,1457 @@
+//! Unix API support functions.
+
+#[cfg(feature = "process-group")]
+pub(crate) use group_wait::EmptyGroupWait;
+#[cfg(feature = "init")]
+pub(crate) use init::{Reaper, exit_like, prepare_init, wait_wakeup};
+#[cfg(all(target_os = "linux", feature = "kill-tree"))]
+pub(crate) use kill_tree::kill_tree;
+#[cfg(all(target_os = "linux", feature = "oom-score-adj"))]
+pub(crate) use oom::OomScore;
+#[cfg(all(feature = "pty", feature = "tokio1"))]
+pub(crate) use pty::cancel_pipe;
+#[cfg(feature = "pty")]
+pub(crate) use pty::{claim_pty, open_pty, relay_pty};
+#[cfg(any(feature = "asciicast", feature = "virtual-terminal"))]
+pub(crate) use pty::{open_pty_sized, resize_pty};
+#[cfg(all(target_os = "linux", feature = "scheduling"))]
+pub use scheduling::{IoPriority, SchedPolicy};
+#[cfg(all(target_os = "linux", feature = "scheduling"))]
+pub(crate) use scheduling::{SchedSettings, reaffine, reaffine_group, renice, renice_group};
+#[cfg(feature = "reset-sigmask")]
+pub(crate) use sigmask::sigmask_is_empty;
+#[cfg(feature = "init")]
+pub(crate) use signal_pipe::SignalPipe;
+#[cfg(feature = "foreground")]
+pub use terminal::ForegroundEvent;
+#[cfg(feature = "foreground")]
+pub(crate) use terminal::Terminal;
+#[cfg(all(feature = "std", feature = "tokio1"))]
+pub(crate) use wait::wait_exited;
+#[cfg(feature = "foreground")]
+pub(crate) use wait::{ChildState, wait_stopped_or_exited};
+
+#[cfg(any(feature = "foreground", all(feature = "std", feature = "tokio1")))]
+mod wait {
+	use std::{
+		io::{Error, Result},
+		mem,
+	};
+
+	use nix::{errno::Errno, libc, unistd::Pid};
+
+	/// What a child was observed doing by [`wait_stopped_or_exited`].
+	#[cfg(feature = "foreground")]
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub(crate) enum ChildState {
+		/// The child was stopped by this signal. The stop has been consumed.
+		Stopped(i32),
+
+		/// The child has exited. It has _not_ been reaped.
+		Exited,
+	}
+
+	/// Block until the child exits, without reaping it.
+	///
+	/// Like [`wait_stopped_or_exited`], this leaves the exit status to be collected by the usual
+	/// `wait()` machinery, so it can be used to wait for a child without holding on to it.
+	#[cfg(all(feature = "std", feature = "tokio1"))]
+	pub(crate) fn wait_exited(pid: Pid) -> Result<()> {
+		loop {
+			let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+			match Errno::result(unsafe {
+				libc::waitid(
+					libc::P_PID,
+					pid.as_raw() as _,
+					&mut info,
+					libc::WEXITED | libc::WNOWAIT,
+				)
+			}) {
+				Ok(_) => return Ok(()),
+				Err(Errno::EINTR) => continue,
+				Err(errno) => return Err(Error::from(errno)),
+			}
+		}
+	}
+
+	/// Block until the child stops or exits.
+	///
+	/// This doesn't reap an exited child, so that the usual `wait()` machinery (which may be Tokio's
+	/// or std's) remains in charge of collecting the exit status.
+	#[cfg(feature = "foreground")]
+	pub(crate) fn wait_stopped_or_exited(pid: Pid) -> Result<ChildState> {
+		loop {
+			let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+			match Errno::result(unsafe {
+				libc::waitid(
+					libc::P_PID,
+					pid.as_raw() as _,
+					&mut info,
+					libc::WEXITED | libc::WSTOPPED | libc::WNOWAIT,
+				)
+			}) {
+				Ok(_) => {}
+				Err(Errno::EINTR) => continue,
+				Err(errno) => return Err(Error::from(errno)),
+			}
+
+			if info.si_code != libc::CLD_STOPPED && info.si_code != libc::CLD_TRAPPED {
+				return Ok(ChildState::Exited);
+			}
+
+			#[cfg(target_vendor = "apple")]
+			let signal = info.si_status;
+			#[cfg(not(target_vendor = "apple"))]
+			let signal = unsafe { info.si_status() };
+
+			// WNOWAIT left the stop waitable, so consume it to not report it again next time.
+			// WNOHANG guards against the child having been continued in the meantime.
+			let mut consumed: libc::siginfo_t = unsafe { mem::zeroed() };
+			Errno::result(unsafe {
+				libc::waitid(
+					libc::P_PID,
+					pid.as_raw() as _,
+					&mut consumed,
+					libc::WSTOPPED | libc::WNOHANG,
+				)
+			})
+			.map_err(Error::from)?;
+
+			return Ok(ChildState::Stopped(signal));
+		}
+	}
+}
+
+#[cfg(feature = "foreground")]
+mod terminal {
+	use std::{
+		fmt,
+		fs::OpenOptions,
+		io::{Error, Result},
+		os::fd::{AsRawFd, BorrowedFd, OwnedFd, RawFd},
+		process::ExitStatus,
+	};
+
+	use nix::{
+		libc,
+		sys::{
+			signal::{SigSet, SigmaskHow, Signal, pthread_sigmask},
+			termios::{SetArg, tcgetattr, tcsetattr},
+		},
+		unistd::{Pid, getpgrp, tcgetpgrp, tcsetpgrp},
+	};
+	#[cfg(feature = "tracing")]
+	use tracing::{debug, instrument};
+
+	/// Job control event reported by a foreground child wrapper.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum ForegroundEvent {
+		/// The child was stopped by this signal, and the terminal was given back to us.
+		///
+		/// The child can be resumed in the foreground or the background.
+		Stopped(i32),
+
+		/// The child exited with this status, and the terminal was given back to us.
+		Exited(ExitStatus),
+	}
+
+	/// The controlling terminal, as it is handed back and forth between us and a child group.
+	///
+	/// This struct gives the terminal back to our process group when dropped.
+	pub(crate) struct Terminal {
+		tty: OwnedFd,
+		own_pgid: Pid,
+		own_termios: libc::termios,
+		child_termios: Option<libc::termios>,
+		handed_to: Option<Pid>,
+	}
+
+	impl fmt::Debug for Terminal {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			f.debug_struct("Terminal")
+				.field("tty", &self.tty)
+				.field("own_pgid", &self.own_pgid)
+				.field("handed_to", &self.handed_to)
+				.finish_non_exhaustive()
+		}
+	}
+
+	impl Terminal {
+		/// Open the controlling terminal, if there is one and we're in its foreground.
+		///
+		/// Returns `None` if there's no terminal, or if it's currently owned by another group:
+		/// in those cases there's nothing we could hand over.
+		#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+		pub fn open() -> Result<Option<Self>> {
+			let tty = match OpenOptions::new().read(true).write(true).open("/dev/tty") {
+				Ok(file) => OwnedFd::from(file),
+				Err(_err) => {
+					#[cfg(feature = "tracing")]
+					debug!(?_err, "no controlling terminal");
+					return Ok(None);
+				}
+			};
+
+			let own_pgid = getpgrp();
+			if tcgetpgrp(&tty)? != own_pgid {
+				#[cfg(feature = "tracing")]
+				debug!("not in the foreground of the controlling terminal");
+				return Ok(None);
+			}
+
+			Ok(Some(Self {
+				own_termios: tcgetattr(&tty)?.into(),
+				tty,
+				own_pgid,
+				child_termios: None,
+				handed_to: None,
+			}))
+		}
+
+		/// The raw terminal descriptor, for use in a `pre_exec` closure.
+		pub fn as_raw_fd(&self) -> RawFd {
+			self.tty.as_raw_fd()
+		}
+
+		/// Take the terminal for the calling process's group.
+		///
+		/// This is meant to be called in the child, between fork and exec.
+		pub fn claim(tty: RawFd) -> Result<()> {
+			// SAFETY: the descriptor is owned by the Terminal in the parent, which outlives the fork
+			let tty = unsafe { BorrowedFd::borrow_raw(tty) };
+			without_sigttou(|| tcsetpgrp(tty, getpgrp()).map_err(Error::from))
+		}
+
+		/// Make the given group the foreground group of the terminal.
+		///
+		/// If the terminal settings of that group were saved when it was last stopped, they're
+		/// restored first.
+		#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+		pub fn hand_to(&mut self, pgid: Pid) -> Result<()> {
+			without_sigttou(|| {
+				if let Some(termios) = self.child_termios.take() {
+					tcsetattr(&self.tty, SetArg::TCSADRAIN, &termios.into())?;
+				}
+				tcsetpgrp(&self.tty, pgid).map_err(Error::from)
+			})?;
+			self.handed_to = Some(pgid);
+			Ok(())
+		}
+
+		/// Give the terminal back to our own group, and restore our terminal settings.
+		///
+		/// If `save_child` is true, the child group's terminal settings are saved to be restored
+		/// the next time it's handed the terminal; this is what shells do on job stop.
+		#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+		pub fn reclaim(&mut self, save_child: bool) -> Result<()> {
+			if self.handed_to.take().is_none() {
+				return Ok(());
+			}
+
+			without_sigttou(|| {
+				if save_child {
+					self.child_termios = Some(tcgetattr(&self.tty)?.into());
+				}
+				tcsetpgrp(&self.tty, self.own_pgid)?;
+				tcsetattr(&self.tty, SetArg::TCSADRAIN, &self.own_termios.into())
+					.map_err(Error::from)
+			})
+		}
+	}
+
+	impl Drop for Terminal {
+		fn drop(&mut self) {
+			self.reclaim(false).ok();
+		}
+	}
+
+	/// Run a terminal operation with SIGTTOU blocked.
+	///
+	/// A process that isn't in the foreground group of its terminal gets SIGTTOU (which stops it by
+	/// default) when it calls `tcsetpgrp` or `tcsetattr`, unless it blocks or ignores the signal.
+	/// Blocking is preferred as it only affects the current thread and doesn't lose a disposition.
+	fn without_sigttou<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+		let mut block = SigSet::empty();
+		block.add(Signal::SIGTTOU);
+		let mut old = SigSet::empty();
+		pthread_sigmask(SigmaskHow::SIG_BLOCK, Some(&block), Some(&mut old))?;
+
+		let res = f();
+
+		pthread_sigmask(SigmaskHow::SIG_SETMASK, Some(&old), None)?;
+		res
+	}
+}
+
+#[cfg(feature = "init")]
+mod init {
+	use std::{
+		io::Result,
+		os::{
+			fd::{AsFd, BorrowedFd, OwnedFd},
+			unix::process::ExitStatusExt,
+		},
+		process::{ExitStatus, exit},
+	};
+
+	use nix::{
+		errno::Errno,
+		libc,
+		poll::{PollFd, PollFlags, PollTimeout, poll},
+		sys::signal::{
+			SaFlags, SigAction, SigHandler, SigSet, Signal, kill, killpg, raise, sigaction,
+		},
+		unistd::{Pid, getpgid, getpgrp, getpid},
+	};
+	#[cfg(feature = "tracing")]
+	use tracing::{debug, instrument};
+
+	use super::signal_pipe::SignalPipe;
+
+	/// Signals forwarded to the child, as the ones an init is likely to get to shut things down.
+	const FORWARDED: [Signal; 7] = [
+		Signal::SIGHUP,
+		Signal::SIGINT,
+		Signal::SIGQUIT,
+		Signal::SIGTERM,
+		Signal::SIGUSR1,
+		Signal::SIGUSR2,
+		Signal::SIGWINCH,
+	];
+
+	/// Start acting as an init, before the child is spawned.
+	///
+	/// Orphans are reparented to PID 1, so if we're not PID 1, we become a subreaper to have them
+	/// reparented to us instead, on Linux. Signals are handled from now on, so that none sent
+	/// while the child is being spawned are missed.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+	pub(crate) fn prepare_init() -> Result<SignalPipe> {
+		#[cfg(target_os = "linux")]
+		if getpid() != Pid::from_raw(1) {
+			Errno::result(unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1) })?;
+		}
+
+		let mut signals = FORWARDED.to_vec();
+		signals.push(Signal::SIGCHLD);
+		SignalPipe::install(&signals)
+	}
+
+	/// Reaps every process reparented to us and forwards signals to the child until it exits.
+	#[derive(Debug)]
+	pub(crate) struct Reaper {
+		signals: Option<SignalPipe>,
+		child: Pid,
+		group: Option<Pid>,
+		exited: Option<ExitStatus>,
+	}
+
+	impl Reaper {
+		pub fn new(signals: SignalPipe, child: u32) -> Self {
+			let child = Pid::from_raw(child as _);
+
+			// signalling our own group would signal us too
+			let group = getpgid(Some(child)).ok().filter(|&pgid| pgid != getpgrp());
+
+			Self {
+				signals: Some(signals),
+				child,
+				group,
+				exited: None,
+			}
+		}
+
+		/// Forward the signals received and reap whatever has exited, without blocking.
+		///
+		/// Returns the child's exit status once it has exited, after which signals are no longer
+		/// handled.
+		pub fn step(&mut self) -> Result<Option<ExitStatus>> {
+			if let Some(signals) = &self.signals {
+				for signal in signals.pending() {
+					if signal != Signal::SIGCHLD {
+						self.forward(signal);
+					}
+				}
+
+				// SIGCHLD may be coalesced or handled by someone else, so always look
+				self.reap()?;
+				if self.exited.is_some() {
+					self.signals = None;
+				}
+			}
+
+			Ok(self.exited)
+		}
+
+		/// A descriptor which becomes readable when there's something for [`step()`](Self::step)
+		/// to do.
+		pub fn wakeup_fd(&self) -> Result<Option<OwnedFd>> {
+			self.signals
+				.as_ref()
+				.map(|signals| signals.as_fd().try_clone_to_owned())
+				.transpose()
+		}
+
+		fn forward(&self, signal: Signal) {
+			#[cfg(feature = "tracing")]
+			debug!(?signal, group = ?self.group, "forwarding signal");
+			match self.group {
+				Some(group) => killpg(group, signal),
+				None => kill(self.child, signal),
+			}
+			.ok();
+		}
+
+		fn reap(&mut self) -> Result<()> {
+			loop {
+				let mut status: libc::c_int = 0;
+				match unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) } {
+					0 => return Ok(()),
+					-1 => match Errno::last() {
+						Errno::ECHILD => return Ok(()),
+						Errno::EINTR => {}
+						errno => return Err(errno.into()),
+					},
+					pid if pid == self.child.as_raw() => {
+						self.exited = Some(ExitStatus::from_raw(status));
+					}
+					_pid => {
+						#[cfg(feature = "tracing")]
+						debug!(pid = _pid, "reaped orphan");
+					}
+				}
+			}
+		}
+	}
+
+	/// Block until the descriptor is readable.
+	pub(crate) fn wait_wakeup(fd: BorrowedFd<'_>) -> Result<()> {
+		loop {
+			match poll(&mut [PollFd::new(fd, PollFlags::POLLIN)], PollTimeout::NONE) {
+				Err(Errno::EINTR) => {}
+				res => return res.map(drop).map_err(Into::into),
+			}
+		}
+	}
+
+	/// Exit like the child did.
+	///
+	/// If it was killed by a signal, we raise it on ourselves with its default action and without
+	/// dumping core. As the kernel doesn't let PID 1 be killed that way, or if it doesn't kill us,
+	/// we then exit with 128 plus the signal number, as shells report it.
+	pub(crate) fn exit_like(status: ExitStatus) -> ! {
+		let Some(number) = status.signal() else {
+			exit(status.code().unwrap_or(1));
+		};
+
+		if let (true, Ok(signal)) = (getpid() != Pid::from_raw(1), Signal::try_from(number)) {
+			let no_core = libc::rlimit {
+				rlim_cur: 0,
+				rlim_max: 0,
+			};
+			unsafe { libc::setrlimit(libc::RLIMIT_CORE, &no_core) };
+
+			let default = SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
+			unsafe { sigaction(signal, &default) }.ok();
+			SigSet::from(signal).thread_unblock().ok();
+			raise(signal).ok();
+		}
+
+		exit(128 + number)
+	}
+}
+
+#[cfg(any(feature = "init", feature = "pty"))]
+mod signal_pipe {
+	use std::{
+		io::{Error, Result},
+		os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+		ptr,
+		sync::atomic::{AtomicI32, Ordering},
+	};
+
+	use nix::{
+		errno::Errno,
+		fcntl::{FcntlArg, FdFlag, OFlag, fcntl},
+		libc,
+		sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction},
+		unistd::{pipe, read},
+	};
+
+	/// The write end of the installed self-pipe, or -1.
+	static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+	/// Our handlers for some signals, for as long as this lives.
+	///
+	/// The handlers write the signal number to a pipe, so a loop can wait for them along with
+	/// other file descriptors. As signal dispositions are process-wide, there can only be one of
+	/// these at a time.
+	#[derive(Debug)]
+	pub(crate) struct SignalPipe {
+		receiver: OwnedFd,
+		// only written to by the handlers, through SIGNAL_PIPE
+		_sender: OwnedFd,
+		previous: Vec<(Signal, SigAction)>,
+	}
+
+	impl SignalPipe {
+		pub fn install(signals: &[Signal]) -> Result<Self> {
+			let (receiver, sender) = pipe()?;
+			for fd in [&receiver, &sender] {
+				fcntl(fd, FcntlArg::F_SETFL(OFlag::O_NONBLOCK))?;
+				fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+			}
+
+			SIGNAL_PIPE
+				.compare_exchange(-1, sender.as_raw_fd(), Ordering::SeqCst, Ordering::SeqCst)
+				.map_err(|_| Error::other("a pty session or init is already handling signals"))?;
+			let mut pipe = Self {
+				receiver,
+				_sender: sender,
+				previous: Vec::with_capacity(signals.len()),
+			};
+
+			let action = SigAction::new(
+				SigHandler::Handler(on_signal),
+				SaFlags::SA_RESTART,
+				SigSet::empty(),
+			);
+			for &signal in signals {
+				let previous = unsafe { sigaction(signal, &action) }?;
+				pipe.previous.push((signal, previous));
+			}
+			Ok(pipe)
+		}
+
+		/// The signals received since last called.
+		pub fn pending(&self) -> Vec<Signal> {
+			let mut buf = [0; 64];
+			let mut signals = Vec::new();
+			while let Ok(n @ 1..) = read(&self.receiver, &mut buf) {
+				signals.extend(
+					buf[..n]
+						.iter()
+						.filter_map(|&signal| Signal::try_from(i32::from(signal)).ok()),
+				);
+			}
+			signals
+		}
+	}
+
+	impl AsFd for SignalPipe {
+		/// The end of the pipe which is readable when signals were received.
+		fn as_fd(&self) -> BorrowedFd<'_> {
+			self.receiver.as_fd()
+		}
+	}
+
+	impl Drop for SignalPipe {
+		fn drop(&mut self) {
+			for (signal, previous) in self.previous.drain(..).rev() {
+				// if a handler was installed over ours since, such as by a runtime which chains to
+				// the one it replaced, leave it there
+				if let Ok(current) = unsafe { sigaction(signal, &previous) } {
+					let ours = match current.handler() {
+						SigHandler::Handler(handler) => {
+							ptr::fn_addr_eq(handler, on_signal as extern "C" fn(_))
+						}
+						_ => false,
+					};
+					if !ours {
+						unsafe { sigaction(signal, &current) }.ok();
+					}
+				}
+			}
+			SIGNAL_PIPE.store(-1, Ordering::SeqCst);
+		}
+	}
+
+	extern "C" fn on_signal(signal: libc::c_int) {
+		let fd: RawFd = SIGNAL_PIPE.load(Ordering::SeqCst);
+		if fd < 0 {
+			return;
+		}
+
+		// only async-signal-safe calls in here, and errno must be left as it was
+		let errno = Errno::last_raw();
+		let byte = signal as u8;
+		unsafe { libc::write(fd, (&raw const byte).cast(), 1) };
+		Errno::set_raw(errno);
+	}
+}
+
+#[cfg(feature = "pty")]
+mod pty {
+	use std::{
+		io::{Error, ErrorKind, Result},
+		os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
+	};
+
+	#[cfg(feature = "tokio1")]
+	use nix::unistd::pipe;
+	use nix::{
+		errno::Errno,
+		fcntl::{FcntlArg, FdFlag, fcntl},
+		libc,
+		poll::{PollFd, PollFlags, PollTimeout, poll},
+		pty::{OpenptyResult, Winsize, openpty},
+		sys::{
+			signal::{Signal, raise},
+			termios::{SetArg, SpecialCharacterIndices, Termios, cfmakeraw, tcgetattr, tcsetattr},
+		},
+		unistd::{getpid, getsid, isatty, read, setsid, write},
+	};
+	#[cfg(feature = "tracing")]
+	use tracing::{debug, instrument};
+
+	use super::signal_pipe::SignalPipe;
+
+	/// Signals handled while a session is running: window size changes, and those which would
+	/// otherwise kill us with the terminal left in raw mode.
+	const SIGNALS: [Signal; 5] = [
+		Signal::SIGWINCH,
+		Signal::SIGHUP,
+		Signal::SIGINT,
+		Signal::SIGQUIT,
+		Signal::SIGTERM,
+	];
+
+	/// Open a pseudo-terminal with the settings and window size of `input`, if it's a terminal.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+	pub(crate) fn open_pty(input: BorrowedFd<'_>) -> Result<OpenptyResult> {
+		let termios = tcgetattr(input).ok();
+		let size = window_size(input);
+		close_on_exec(openpty(size.as_ref(), termios.as_ref())?)
+	}
+
+	/// Open a pseudo-terminal with the given window size and the default settings.
+	#[cfg(any(feature = "asciicast", feature = "virtual-terminal"))]
+	pub(crate) fn open_pty_sized(cols: u16, rows: u16) -> Result<OpenptyResult> {
+		close_on_exec(openpty(&winsize(cols, rows), None)?)
+	}
+
+	/// Keep both sides of a new pseudo-terminal from leaking into spawned processes.
+	///
+	/// `openpty()` doesn't set `FD_CLOEXEC`, so any process spawned on another thread while these
+	/// are open would inherit them, and the master wouldn't see the slave closed until it exits.
+	/// The slave is still given to the command as its stdio, as that's duplicated at spawn.
+	fn close_on_exec(pty: OpenptyResult) -> Result<OpenptyResult> {
+		for fd in [&pty.master, &pty.slave] {
+			fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+		}
+		Ok(pty)
+	}
+
+	/// Change the window size of a pseudo-terminal.
+	///
+	/// The kernel sends `SIGWINCH` to its foreground process group.
+	#[cfg(any(feature = "asciicast", feature = "virtual-terminal"))]
+	pub(crate) fn resize_pty(master: BorrowedFd<'_>, cols: u16, rows: u16) -> Result<()> {
+		set_window_size(master, &winsize(cols, rows))
+	}
+
+	#[cfg(any(feature = "asciicast", feature = "virtual-terminal"))]
+	fn winsize(cols: u16, rows: u16) -> Winsize {
+		Winsize {
+			ws_row: rows,
+			ws_col: cols,
+			ws_xpixel: 0,
+			ws_ypixel: 0,
+		}
+	}
+
+	/// Make the pseudo-terminal on stdin the controlling terminal of the calling process.
+	///
+	/// This is meant to be called in the child, between fork and exec. The process is made the
+	/// leader of a new session if it isn't already, as only session leaders can acquire a
+	/// controlling terminal.
+	pub(crate) fn claim_pty() -> Result<()> {
+		if getsid(None)? != getpid() {
+			setsid()?;
+		}
+		Errno::result(unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY as _, 0) })?;
+		Ok(())
+	}
+
+	/// Relay bytes between the pseudo-terminal and our own input and output until it's closed.
+	///
+	/// The pseudo-terminal is closed when every process which had it open has exited or closed it.
+	/// While this runs, `input` is put in raw mode if it's a terminal, and its window size changes
+	/// are copied to the pseudo-terminal. If `input` isn't a terminal, an end-of-file character is
+	/// sent once it's exhausted.
+	///
+	/// If we get a terminating signal, the terminal settings and signal dispositions are restored
+	/// and the signal is raised again. If `cancel` is given, relaying stops with an error as soon
+	/// as it's readable or hung up, such as when the write end of a [`cancel_pipe()`] is closed.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+	pub(crate) fn relay_pty(
+		master: BorrowedFd<'_>,
+		input: BorrowedFd<'_>,
+		output: BorrowedFd<'_>,
+		cancel: Option<BorrowedFd<'_>>,
+	) -> Result<()> {
+		let signals = SignalPipe::install(&SIGNALS)?;
+		let raw = RawMode::enter(input)?;
+		let input_is_tty = raw.is_some();
+
+		let mut input = Some(input);
+		let mut buf = [0; 8 * 1024];
+		loop {
+			let mut fds = vec![
+				PollFd::new(master, PollFlags::POLLIN),
+				PollFd::new(signals.as_fd(), PollFlags::POLLIN),
+			];
+			fds.extend(cancel.map(|fd| PollFd::new(fd, PollFlags::POLLIN)));
+			let input_index = fds.len();
+			if let Some(input) = input {
+				fds.push(PollFd::new(input, PollFlags::POLLIN));
+			}
+			match poll(&mut fds, PollTimeout::NONE) {
+				Ok(_) => {}
+				Err(Errno::EINTR) => continue,
+				Err(errno) => return Err(errno.into()),
+			}
+			let ready = |fd: &PollFd<'_>| fd.revents().is_some_and(|revents| !revents.is_empty());
+			let (master_ready, signal_ready) = (ready(&fds[0]), ready(&fds[1]));
+			let cancelled = cancel.is_some() && ready(&fds[2]);
+			let input_ready = fds.get(input_index).is_some_and(ready);
+			drop(fds);
+
+			if cancelled {
+				#[cfg(feature = "tracing")]
+				debug!("pty session cancelled, restoring the terminal");
+				return Err(Error::new(ErrorKind::Interrupted, "pty session cancelled"));
+			}
+
+			if signal_ready {
+				for signal in signals.pending() {
+					if signal == Signal::SIGWINCH {
+						if let Some(size) = window_size(input.unwrap_or(output)) {
+							set_window_size(master, &size)?;
+						}
+						continue;
+					}
+
+					#[cfg(feature = "tracing")]
+					debug!(?signal, "terminating signal, restoring the terminal");
+					drop(raw);
+					drop(signals);
+					raise(signal)?;
+					return Err(Error::new(
+						ErrorKind::Interrupted,
+						format!("pty session interrupted by {signal}"),
+					));
+				}
+			}
+
+			if master_ready {
+				match read(master, &mut buf) {
+					// EIO is what Linux returns once every slave descriptor is closed
+					Ok(0) | Err(Errno::EIO) => return Ok(()),
+					Ok(n) => write_all(output, &buf[..n])?,
+					Err(Errno::EINTR | Errno::EAGAIN) => {}
+					Err(errno) => return Err(errno.into()),
+				}
+			}
+
+			if let (true, Some(from)) = (input_ready, input) {
+				match read(from, &mut buf) {
+					Ok(0) => {
+						input = None;
+						if !input_is_tty {
+							write_all(master, &[eof_char(master)])?;
+						}
+					}
+					Ok(n) => write_all(master, &buf[..n])?,
+					Err(Errno::EINTR | Errno::EAGAIN) => {}
+					Err(errno) => return Err(errno.into()),
+				}
+			}
+		}
+	}
+
+	/// A pipe to cancel [`relay_pty()`] with: pass it the first end, and close the second to stop it.
+	#[cfg(feature = "tokio1")]
+	pub(crate) fn cancel_pipe() -> Result<(OwnedFd, OwnedFd)> {
+		let (receiver, sender) = pipe()?;
+		for fd in [&receiver, &sender] {
+			fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+		}
+		Ok((receiver, sender))
+	}
+
+	fn window_size(fd: BorrowedFd<'_>) -> Option<Winsize> {
+		let mut size: Winsize = unsafe { std::mem::zeroed() };
+		Errno::result(unsafe { libc::ioctl(fd.as_raw_fd(), libc::TIOCGWINSZ as _, &mut size) })
+			.ok()
+			.map(|_| size)
+	}
+
+	fn set_window_size(fd: BorrowedFd<'_>, size: &Winsize) -> Result<()> {
+		Errno::result(unsafe { libc::ioctl(fd.as_raw_fd(), libc::TIOCSWINSZ as _, size) })?;
+		Ok(())
+	}
+
+	/// The character which signals end-of-file to a reader of the terminal, usually `^D`.
+	fn eof_char(fd: BorrowedFd<'_>) -> u8 {
+		tcgetattr(fd).map_or(4, |termios| {
+			termios.control_chars[SpecialCharacterIndices::VEOF as usize]
+		})
+	}
+
+	fn write_all(fd: BorrowedFd<'_>, mut data: &[u8]) -> Result<()> {
+		while !data.is_empty() {
+			match write(fd, data) {
+				Ok(n) => data = &data[n..],
+				Err(Errno::EINTR) => {}
+				Err(errno) => return Err(errno.into()),
+			}
+		}
+		Ok(())
+	}
+
+	/// Raw mode on our input terminal, for as long as this lives.
+	///
+	/// In raw mode, everything typed (including `^C` and friends) is passed through as bytes for
+	/// the pseudo-terminal to interpret, rather than by our own terminal.
+	struct RawMode {
+		tty: OwnedFd,
+		saved: Termios,
+	}
+
+	impl RawMode {
+		fn enter(input: BorrowedFd<'_>) -> Result<Option<Self>> {
+			if !isatty(input).unwrap_or(false) {
+				return Ok(None);
+			}
+
+			let saved = tcgetattr(input)?;
+			let mut raw = saved.clone();
+			cfmakeraw(&mut raw);
+			tcsetattr(input, SetArg::TCSADRAIN, &raw)?;
+			Ok(Some(Self {
+				tty: input.try_clone_to_owned()?,
+				saved,
+			}))
+		}
+	}
+
+	impl Drop for RawMode {
+		fn drop(&mut self) {
+			tcsetattr(&self.tty, SetArg::TCSADRAIN, &self.saved).ok();
+		}
+	}
+}
+
+#[cfg(all(
+	target_os = "linux",
+	any(
+		feature = "kill-tree",
+		feature = "oom-score-adj",
+		feature = "process-group",
+		feature = "scheduling"
+	)
+))]
+mod procfs {
+	#[cfg(feature = "kill-tree")]
+	use std::collections::HashMap;
+	#[cfg(any(feature = "oom-score-adj", feature = "scheduling"))]
+	use std::io::Error;
+	use std::{fs, io::Result};
+
+	use nix::unistd::Pid;
+	#[cfg(any(feature = "oom-score-adj", feature = "scheduling"))]
+	use nix::unistd::{getpgid, getpgrp};
+
+	/// The process group a child leads or belongs to, if it's not our own.
+	///
+	/// Operating on our own group would affect us too, so that's an error.
+	#[cfg(any(feature = "oom-score-adj", feature = "scheduling"))]
+	pub(crate) fn child_pgid(pid: Pid) -> Result<Pid> {
+		let pgid = getpgid(Some(pid))?;
+		if pgid == getpgrp() {
+			return Err(Error::other("the child isn't in its own process group"));
+		}
+		Ok(pgid)
+	}
+
+	/// The processes currently in a process group, from `/proc`.
+	///
+	/// This is a snapshot: processes may join or leave the group at any time.
+	#[cfg(any(feature = "oom-score-adj", feature = "scheduling"))]
+	pub(crate) fn group_members(pgid: Pid) -> Result<Vec<Pid>> {
+		Ok(processes()?
+			.into_iter()
+			.filter(|stat| stat.pgrp == pgid)
+			.map(|stat| stat.pid)
+			.collect())
+	}
+
+	/// Whether any process in a process group hasn't exited yet, from `/proc`.
+	///
+	/// Zombies don't count: they've exited, and are only waiting for their parent to reap them.
+	#[cfg(feature = "process-group")]
+	pub(crate) fn group_running(pgid: Pid) -> Result<bool> {
+		Ok(processes()?
+			.iter()
+			.any(|stat| stat.pgrp == pgid && !stat.zombie))
+	}
+
+	/// The descendants of a process, from `/proc`, parents before their children.
+	///
+	/// This is a snapshot: processes may fork or exit at any time. Descendants whose parent exited
+	/// have been reparented away, so they can't be found this way.
+	#[cfg(feature = "kill-tree")]
+	pub(crate) fn descendants(pid: Pid) -> Result<Vec<Pid>> {
+		let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+		for stat in processes()? {
+			children.entry(stat.ppid).or_default().push(stat.pid);
+		}
+
+		let mut found = children.remove(&pid).unwrap_or_default();
+		let mut next = 0;
+		while let Some(&parent) = found.get(next) {
+			found.extend(children.remove(&parent).unwrap_or_default());
+			next += 1;
+		}
+		Ok(found)
+	}
+
+	/// The parent of a process, from `/proc`, if it's still around.
+	#[cfg(feature = "kill-tree")]
+	pub(crate) fn parent(pid: Pid) -> Option<Pid> {
+		let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+		parse_stat(&stat).map(|(_, ppid, _)| Pid::from_raw(ppid))
+	}
+
+	/// The threads of a process, from `/proc`.
+	///
+	/// Some per-process attributes are really per-thread on Linux, so must be set on each thread.
+	#[cfg(feature = "scheduling")]
+	pub(crate) fn threads(pid: Pid) -> Result<Vec<Pid>> {
+		let mut threads = Vec::new();
+		for entry in fs::read_dir(format!("/proc/{pid}/task"))? {
+			if let Some(tid) = entry?
+				.file_name()
+				.to_str()
+				.and_then(|name| name.parse().ok())
+			{
+				threads.push(Pid::from_raw(tid));
+			}
+		}
+		Ok(threads)
+	}
+
+	/// The IDs of a process, from `/proc/<pid>/stat`.
+	// which fields are read depends on the features enabled
+	#[allow(dead_code)]
+	struct ProcStat {
+		pid: Pid,
+		ppid: Pid,
+		pgrp: Pid,
+		zombie: bool,
+	}
+
+	/// Every process currently running, from `/proc`.
+	fn processes() -> Result<Vec<ProcStat>> {
+		let mut processes = Vec::new();
+		for entry in fs::read_dir("/proc")? {
+			let Some(pid) = entry?
+				.file_name()
+				.to_str()
+				.and_then(|name| name.parse().ok())
+			else {
+				continue;
+			};
+
+			// the process may have exited since the directory was listed
+			let Ok(stat) = fs::read_to_string(format!("/proc/{pid}/stat")) else {
+				continue;
+			};
+			if let Some((state, ppid, pgrp)) = parse_stat(&stat) {
+				processes.push(ProcStat {
+					pid: Pid::from_raw(pid),
+					ppid: Pid::from_raw(ppid),
+					pgrp: Pid::from_raw(pgrp),
+					zombie: state == "Z",
+				});
+			}
+		}
+		Ok(processes)
+	}
+
+	/// Get the state, parent PID, and process group ID out of the contents of `/proc/<pid>/stat`.
+	fn parse_stat(stat: &str) -> Option<(&str, i32, i32)> {
+		// the command name in parentheses can contain anything, so skip past its last `)`;
+		// after it come the state, the parent PID, and the process group ID
+		let (_, rest) = stat.rsplit_once(')')?;
+		let mut fields = rest.split_whitespace();
+		Some((
+			fields.next()?,
+			fields.next()?.parse().ok()?,
+			fields.next()?.parse().ok()?,
+		))
+	}
+}
+
+#[cfg(all(target_os = "linux", feature = "kill-tree"))]
+mod kill_tree {
+	use std::{collections::HashSet, io::Result};
+
+	use nix::{
+		errno::Errno,
+		sys::{
+			signal::{Signal, kill},
+			wait::waitpid,
+		},
+		unistd::{Pid, getpid},
+	};
+	#[cfg(feature = "tracing")]
+	use tracing::{debug, instrument};
+
+	use super::procfs::{descendants, parent};
+
+	/// Kill a process and all of its descendants, wherever their process group or session.
+	///
+	/// Each process is first frozen with `SIGSTOP`, so that it can't fork any further while the rest
+	/// of the tree is found, and once no new descendants turn up, they're all sent `SIGKILL`.
+	/// Descendants which are our own children (e.g. because we're a subreaper) are then reaped. The
+	/// root process is left for the caller to reap.
+	///
+	/// Processes we aren't permitted to signal, like setuid descendants, are skipped. If walking
+	/// the tree fails, the processes frozen so far are still killed, so none are left stopped.
+	///
+	/// Descendants which had already been orphaned before the tree was walked can't be found.
+	#[cfg_attr(feature = "tracing", instrument(level = "debug"))]
+	pub(crate) fn kill_tree(root: Pid) -> Result<()> {
+		let mut frozen = Vec::new();
+		let walked = freeze_tree(root, &mut frozen);
+
+		#[cfg(feature = "tracing")]
+		debug!(?frozen, "killing process tree");
+		let mut killed = Ok(());
+		for &pid in &frozen {
+			match kill(pid, Signal::SIGKILL) {
+				Ok(()) | Err(Errno::ESRCH) => {}
+				Err(err) => {
+					if killed.is_ok() {
+						killed = Err(err.into());
+					}
+				}
+			}
+		}
+
+		let us = getpid();
+		for &pid in &frozen {
+			if pid != root && parent(pid) == Some(us) {
+				waitpid(pid, None).ok();
+			}
+		}
+
+		walked.and(killed)
+	}
+
+	fn freeze_tree(root: Pid, frozen: &mut Vec<Pid>) -> Result<()> {
+		if freeze(root)? {
+			frozen.push(root);
+		}
+
+		let mut seen = HashSet::from([root]);
+		loop {
+			let mut new = 0;
+			for pid in descendants(root)? {
+				if seen.insert(pid) {
+					if freeze(pid)? {
+						frozen.push(pid);
+					}
+					new += 1;
+				}
+			}
+
+			// a process may have forked between the walk and being frozen, so walk again until
+			// nothing new turns up
+			if new == 0 {
+				return Ok(());
+			}
+		}
+	}
+
+	/// Stop a process, returning whether it was stopped, or had exited or can't be signalled.
+	fn freeze(pid: Pid) -> Result<bool> {
+		match kill(pid, Signal::SIGSTOP) {
+			Ok(()) => Ok(true),
+			Err(Errno::ESRCH) => Ok(false),
+			Err(Errno::EPERM) => {
+				#[cfg(feature = "tracing")]
+				debug!(%pid, "not permitted to stop process, skipping it");
+				Ok(false)
+			}
+			Err(err) => Err(err.into()),
+		}
+	}
+}
+
+#[cfg(all(target_os = "linux", feature = "scheduling"))]
+mod scheduling {
+	use std::{
+		io::{Error, ErrorKind, Result},
+		mem,
+	};
+
+	use nix::{errno::Errno, libc, unistd::Pid};
+
+	use super::procfs::{child_pgid, group_members, threads};
+
+	/// I/O scheduling class and priority, as set by `ionice(1)`.
+	///
+	/// Priorities go from 0 (highest) to 7 (lowest).
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum IoPriority {
+		/// Gets the disk first, regardless of other activity. Requires privileges.
+		RealTime(u8),
+
+		/// The default class.
+		BestEffort(u8),
+
+		/// Only gets the disk when no other process has asked for it for a while.
+		Idle,
+	}
+
+	impl IoPriority {
+		fn raw(self) -> Result<libc::c_int> {
+			const CLASS_SHIFT: libc::c_int = 13;
+			let (class, level) = match self {
+				Self::RealTime(level) => (1, level),
+				Self::BestEffort(level) => (2, level),
+				Self::Idle => (3, 0),
+			};
+			if level > 7 {
+				return Err(Error::new(
+					ErrorKind::InvalidInput,
+					"I/O priority must be between 0 and 7",
+				));
+			}
+			Ok(class << CLASS_SHIFT | libc::c_int::from(level))
+		}
+	}
+
+	/// CPU scheduling policy for non-interactive processes.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum SchedPolicy {
+		/// `SCHED_BATCH`: CPU-intensive work, which is slightly disfavoured in wakeups.
+		Batch,
+
+		/// `SCHED_IDLE`: very low priority work, which only runs when nothing else wants to.
+		Idle,
+	}
+
+	impl SchedPolicy {
+		fn raw(self) -> libc::c_int {
+			match self {
+				Self::Batch => libc::SCHED_BATCH,
+				Self::Idle => libc::SCHED_IDLE,
+			}
+		}
+	}
+
+	/// Scheduling settings, as accumulated by the `Scheduling` wrappers.
+	#[derive(Clone, Debug, Default)]
+	pub(crate) struct SchedSettings {
+		pub nice: Option<i32>,
+		pub io_priority: Option<IoPriority>,
+		pub affinity: Option<Vec<usize>>,
+		pub policy: Option<SchedPolicy>,
+	}
+
+	impl SchedSettings {
+		/// Merge another set of settings into this one, with the other's taking precedence.
+		pub fn merge(&mut self, other: Self) {
+			self.nice = other.nice.or(self.nice);
+			self.io_priority = other.io_priority.or(self.io_priority);
+			self.affinity = other.affinity.or(self.affinity.take());
+			self.policy = other.policy.or(self.policy);
+		}
+
+		/// Prepare a function applying the settings to the calling process, for `pre_exec`.
+		///
+		/// Everything that could allocate or fail validation is done here, so that the function
+		/// only makes system calls.
+		pub fn pre_exec(&self) -> Result<impl FnMut() -> Result<()> + Send + Sync + 'static> {
+			let nice = self.nice;
+			let io_priority = self.io_priority.map(IoPriority::raw).transpose()?;
+			let affinity = self.affinity.as_deref().map(cpu_set).transpose()?;
+			let policy = self.policy.map(SchedPolicy::raw);
+
+			Ok(move || {
+				if let Some(policy) = policy {
+					let param = libc::sched_param { sched_priority: 0 };
+					Errno::result(unsafe { libc::sched_setscheduler(0, policy, &param) })?;
+				}
+				if let Some(nice) = nice {
+					set_nice(Pid::from_raw(0), nice)?;
+				}
+				if let Some(io_priority) = io_priority {
+					const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+					Errno::result(unsafe {
+						libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, io_priority)
+					})?;
+				}
+				if let Some(set) = &affinity {
+					set_affinity(Pid::from_raw(0), set)?;
+				}
+				Ok(())
+			})
+		}
+	}
+
+	/// Set the niceness of a running process's threads.
+	pub(crate) fn renice(pid: Pid, nice: i32) -> Result<()> {
+		for tid in threads(pid)? {
+			ignore_exited(set_nice(tid, nice))?;
+		}
+		Ok(())
+	}
+
+	/// Set the niceness of every process in a child's process group.
+	pub(crate) fn renice_group(pid: Pid, nice: i32) -> Result<()> {
+		for member in group_members(child_pgid(pid)?)? {
+			ignore_exited(renice(member, nice))?;
+		}
+		Ok(())
+	}
+
+	/// Set the CPU affinity of a running process's threads.
+	pub(crate) fn reaffine(pid: Pid, cpus: &[usize]) -> Result<()> {
+		let set = cpu_set(cpus)?;
+		for tid in threads(pid)? {
+			ignore_exited(set_affinity(tid, &set))?;
+		}
+		Ok(())
+	}
+
+	/// Set the CPU affinity of every process in a child's process group.
+	pub(crate) fn reaffine_group(pid: Pid, cpus: &[usize]) -> Result<()> {
+		for member in group_members(child_pgid(pid)?)? {
+			ignore_exited(reaffine(member, cpus))?;
+		}
+		Ok(())
+	}
+
+	fn set_nice(tid: Pid, nice: i32) -> Result<()> {
+		Errno::result(unsafe {
+			libc::setpriority(libc::PRIO_PROCESS, tid.as_raw() as libc::id_t, nice)
+		})
+		.map(drop)
+		.map_err(Error::from)
+	}
+
+	fn set_affinity(tid: Pid, set: &libc::cpu_set_t) -> Result<()> {
+		Errno::result(unsafe {
+			libc::sched_setaffinity(tid.as_raw(), mem::size_of::<libc::cpu_set_t>(), set)
+		})
+		.map(drop)
+		.map_err(Error::from)
+	}
+
+	fn cpu_set(cpus: &[usize]) -> Result<libc::cpu_set_t> {
+		let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+		for &cpu in cpus {
+			if cpu >= libc::CPU_SETSIZE as usize {
+				return Err(Error::new(
+					ErrorKind::InvalidInput,
+					format!("CPU {cpu} is out of range"),
+				));
+			}
+			unsafe { libc::CPU_SET(cpu, &mut set) };
+		}
+		Ok(set)
+	}
+
+	/// Processes and threads can exit while we're going through them, which isn't an error.
+	fn ignore_exited(res: Result<()>) -> Result<()> {
+		match res {
+			Err(err)
+				if err.raw_os_error() == Some(libc::ESRCH) || err.kind() == ErrorKind::NotFound =>
+			{
+				Ok(())
+			}
+			res => res,
+		}
+	}
+}
+
+#[cfg(all(target_os = "linux", feature = "oom-score-adj"))]
+mod oom {
+	use std::{
+		fs,
+		io::{Error, ErrorKind, Result},
+	};
+
+	use nix::{errno::Errno, libc, unistd::Pid};
+
+	use super::procfs::{child_pgid, group_members};
+
+	/// An `oom_score_adj` value, validated and formatted ahead of time for use in `pre_exec`.
+	#[derive(Clone, Copy, Debug)]
+	pub(crate) struct OomScore {
+		text: [u8; 5],
+		len: usize,
+	}
+
+	impl OomScore {
+		pub fn new(adj: i32) -> Result<Self> {
+			if !(-1000..=1000).contains(&adj) {
+				return Err(Error::new(
+					ErrorKind::InvalidInput,
+					"OOM score adjustment must be between -1000 and 1000",
+				));
+			}
+
+			let formatted = adj.to_string();
+			let mut text = [0; 5];
+			text[..formatted.len()].copy_from_slice(formatted.as_bytes());
+			Ok(Self {
+				text,
+				len: formatted.len(),
+			})
+		}
+
+		fn as_bytes(&self) -> &[u8] {
+			&self.text[..self.len]
+		}
+
+		/// Set the calling process's score adjustment.
+		///
+		/// This only makes system calls, so it can be used in `pre_exec`.
+		pub fn apply_to_self(&self) -> Result<()> {
+			let fd = Errno::result(unsafe {
+				libc::open(
+					c"/proc/self/oom_score_adj".as_ptr(),
+					libc::O_WRONLY | libc::O_CLOEXEC,
+				)
+			})?;
+			let written = Errno::result(unsafe {
+				libc::write(fd, self.as_bytes().as_ptr().cast(), self.len)
+			});
+			unsafe { libc::close(fd) };
+			written.map(drop).map_err(Error::from)
+		}
+
+		/// Set a running process's score adjustment.
+		pub fn apply_to(&self, pid: Pid) -> Result<()> {
+			fs::write(format!("/proc/{pid}/oom_score_adj"), self.as_bytes())
+		}
+
+		/// Set the score adjustment of every process in a child's process group.
+		pub fn apply_to_group(&self, pid: Pid) -> Result<()> {
+			for member in group_members(child_pgid(pid)?)? {
+				match self.apply_to(member) {
+					// the process may have exited since the group was listed
+					Err(err) if err.kind() == ErrorKind::NotFound => {}
+					res => res?,
+				}
+			}
+			Ok(())
+		}
+	}
+}
+
+#[cfg(feature = "process-group")]
+mod group_wait {
+	use std::{
+		io::{Error, ErrorKind, Result},
+		thread,
+		time::{Duration, Instant},
+	};
+
+	use nix::{
+		errno::Errno,
+		sys::signal::{Signal, killpg},
+		unistd::Pid,
+	};
+
+	/// How often the group is checked while waiting for it to be empty.
+	const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+	/// Waiting for every process in a group to exit, including those which aren't our children.
+	#[derive(Clone, Copy, Debug)]
+	pub(crate) struct EmptyGroupWait {
+		pub timeout: Duration,
+		pub kill_stragglers: bool,
+	}
+
+	impl EmptyGroupWait {
+		/// Block until the group is empty, killing stragglers after the timeout if configured to.
+		pub fn wait(&self, pgid: Pid) -> Result<()> {
+			if self.wait_once(pgid)? {
+				return Ok(());
+			}
+
+			if self.kill_stragglers {
+				match killpg(pgid, Signal::SIGKILL) {
+					Ok(()) | Err(Errno::ESRCH) => {}
+					Err(errno) => return Err(errno.into()),
+				}
+				if self.wait_once(pgid)? {
+					return Ok(());
+				}
+			}
+
+			Err(Error::new(
+				ErrorKind::TimedOut,
+				format!(
+					"process group {pgid} still has running members after {:?}",
+					self.timeout
+				),
+			))
+		}
+
+		/// Whether the group is empty now, without waiting.
+		pub fn is_empty(&self, pgid: Pid) -> Result<bool> {
+			group_running(pgid).map(|running| !running)
+		}
+
+		/// Poll until the group is empty or the timeout is reached, returning whether it's empty.
+		fn wait_once(&self, pgid: Pid) -> Result<bool> {
+			let deadline = Instant::now() + self.timeout;
+			loop {
+				if !group_running(pgid)? {
+					return Ok(true);
+				}
+				if Instant::now() >= deadline {
+					return Ok(false);
+				}
+				thread::sleep(POLL_INTERVAL);
+			}
+		}
+	}
+
+	#[cfg(target_os = "linux")]
+	fn group_running(pgid: Pid) -> Result<bool> {
+		super::procfs::group_running(pgid)
+	}
+
+	/// Whether any process is left in the group; without `/proc`, this also counts zombies.
+	#[cfg(not(target_os = "linux"))]
+	fn group_running(pgid: Pid) -> Result<bool> {
+		match killpg(pgid, None) {
+			Err(Errno::ESRCH) => Ok(false),
+			Ok(()) | Err(Errno::EPERM) => Ok(true),
+			Err(errno) => Err(errno.into()),
+		}
+	}
+}
+
+#[cfg(feature = "reset-sigmask")]
+mod sigmask {
+	use std::io::Result;
+
+	use nix::sys::signal::{SigSet, SigmaskHow, pthread_sigmask};
+
+	/// Whether the calling thread doesn't block any signal, so a child would inherit an empty mask.
+	pub(crate) fn sigmask_is_empty() -> Result<bool> {
+		let mut current = SigSet::empty();
+		pthread_sigmask(SigmaskHow::SIG_BLOCK, None, Some(&mut current))?;
+		// compared whole, as iterating only covers the signals nix knows, not real-time ones
+		Ok(current == SigSet::empty())
+	}
+}
@@ -0,0 +1,864 @@
+//! A virtual terminal screen, kept up to date from the output of a child on a pseudo-terminal.
+//!
+//! The emulation covers what's commonly used by terminal programs out of the VT100 and xterm
+//! control sequences: cursor movement, erasing, insertion and deletion, scrolling regions, text
+//! attributes and colours, the alternate screen, and window titles. Every character takes a
+//! single cell, and unknown sequences are ignored.
+
+use std::{
+	fs::File,
+	io::{Error, ErrorKind, Read, Result, Write},
+	mem,
+	os::fd::OwnedFd,
+	sync::{Arc, Condvar, Mutex, MutexGuard},
+	thread,
+	time::Duration,
+};
+
+/// How much of an OSC string is kept.
+const MAX_OSC: usize = 4096;
+
+/// A colour of a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Color {
+	/// The terminal's default colour.
+	#[default]
+	Default,
+
+	/// One of the 256 indexed colours: 0–7 are the standard colours, 8–15 their bright versions.
+	Indexed(u8),
+
+	/// A 24-bit colour.
+	Rgb(u8, u8, u8),
+}
+
+/// The attributes of a cell, as set with SGR sequences.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CellAttrs {
+	/// The foreground colour.
+	pub fg: Color,
+
+	/// The background colour.
+	pub bg: Color,
+
+	/// Bold (or bright) text.
+	pub bold: bool,
+
+	/// Dim (or faint) text.
+	pub dim: bool,
+
+	/// Italic text.
+	pub italic: bool,
+
+	/// Underlined text.
+	pub underline: bool,
+
+	/// Blinking text.
+	pub blink: bool,
+
+	/// Foreground and background colours swapped.
+	pub inverse: bool,
+
+	/// Hidden text.
+	pub hidden: bool,
+
+	/// Crossed-out text.
+	pub strikethrough: bool,
+}
+
+/// A cell of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cell {
+	/// The character in the cell, a space if it's blank.
+	pub ch: char,
+
+	/// The attributes of the cell.
+	pub attrs: CellAttrs,
+}
+
+impl Default for Cell {
+	fn default() -> Self {
+		Self {
+			ch: ' ',
+			attrs: CellAttrs::default(),
+		}
+	}
+}
+
+/// A snapshot of a virtual terminal screen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Screen {
+	cells: Vec<Vec<Cell>>,
+	cursor: (u16, u16),
+	cursor_visible: bool,
+	alternate: bool,
+	title: String,
+}
+
+impl Screen {
+	fn new(cols: u16, rows: u16) -> Self {
+		Self {
+			cells: vec![vec![Cell::default(); usize::from(cols)]; usize::from(rows)],
+			cursor: (0, 0),
+			cursor_visible: true,
+			alternate: false,
+			title: String::new(),
+		}
+	}
+
+	/// The size of the screen, in columns and rows.
+	pub fn size(&self) -> (u16, u16) {
+		(self.cols(), self.rows())
+	}
+
+	/// The text on the screen.
+	///
+	/// Rows are separated by newlines, and trailing whitespace is removed from each row and from
+	/// the whole text.
+	pub fn text(&self) -> String {
+		let mut text = String::new();
+		for row in 0..self.rows() {
+			if row > 0 {
+				text.push('\n');
+			}
+			text.push_str(&self.row_text(row).unwrap_or_default());
+		}
+		text.truncate(text.trim_end().len());
+		text
+	}
+
+	/// The text on a row of the screen, without trailing whitespace.
+	pub fn row_text(&self, row: u16) -> Option<String> {
+		let text: String = self
+			.cells
+			.get(usize::from(row))?
+			.iter()
+			.map(|cell| cell.ch)
+			.collect();
+		Some(text.trim_end().to_owned())
+	}
+
+	/// The cell at a position, zero-based.
+	pub fn cell(&self, row: u16, col: u16) -> Option<&Cell> {
+		self.cells.get(usize::from(row))?.get(usize::from(col))
+	}
+
+	/// The position of the cursor, as zero-based row and column.
+	pub fn cursor(&self) -> (u16, u16) {
+		self.cursor
+	}
+
+	/// Whether the cursor is shown.
+	pub fn cursor_visible(&self) -> bool {
+		self.cursor_visible
+	}
+
+	/// Whether the alternate screen is in use, as full-screen programs do.
+	pub fn alternate_screen(&self) -> bool {
+		self.alternate
+	}
+
+	/// The window title, as last set by the program.
+	pub fn title(&self) -> &str {
+		&self.title
+	}
+
+	fn rows(&self) -> u16 {
+		self.cells.len() as u16
+	}
+
+	fn cols(&self) -> u16 {
+		self.cells.first().map_or(0, |row| row.len() as u16)
+	}
+}
+
+/// Where the parser is in the output stream.
+#[derive(Debug, Default)]
+enum State {
+	#[default]
+	Ground,
+	Escape,
+	EscapeIntermediate,
+	Csi(Csi),
+	Osc(Vec<u8>),
+	OscEscape(Vec<u8>),
+	IgnoreString,
+	IgnoreStringEscape,
+}
+
+/// A control sequence being parsed.
+#[derive(Debug, Default)]
+struct Csi {
+	private: Option<u8>,
+	params: Vec<u16>,
+	current: Option<u16>,
+	intermediate: bool,
+}
+
+/// A VT100/xterm terminal emulator, which only keeps the screen.
+#[derive(Debug)]
+pub(crate) struct Emulator {
+	screen: Screen,
+	state: State,
+	utf8: Vec<u8>,
+	pen: CellAttrs,
+	wrap_pending: bool,
+	autowrap: bool,
+	scroll_region: (u16, u16),
+	saved_cursor: Option<((u16, u16), CellAttrs)>,
+	saved_primary: Option<Vec<Vec<Cell>>>,
+	responses: Vec<u8>,
+}
+
+impl Emulator {
+	pub fn new(cols: u16, rows: u16) -> Self {
+		let (cols, rows) = (cols.max(1), rows.max(1));
+		Self {
+			screen: Screen::new(cols, rows),
+			state: State::Ground,
+			utf8: Vec::new(),
+			pen: CellAttrs::default(),
+			wrap_pending: false,
+			autowrap: true,
+			scroll_region: (0, rows - 1),
+			saved_cursor: None,
+			saved_primary: None,
+			responses: Vec::new(),
+		}
+	}
+
+	pub fn screen(&self) -> &Screen {
+		&self.screen
+	}
+
+	/// Take the replies to the queries in the output, which should be sent back as input.
+	pub fn take_responses(&mut self) -> Vec<u8> {
+		mem::take(&mut self.responses)
+	}
+
+	pub fn resize(&mut self, cols: u16, rows: u16) {
+		let (cols, rows) = (cols.max(1), rows.max(1));
+		for cells in [Some(&mut self.screen.cells), self.saved_primary.as_mut()]
+			.into_iter()
+			.flatten()
+		{
+			cells.resize_with(usize::from(rows), Vec::new);
+			for row in cells.iter_mut() {
+				row.resize(usize::from(cols), Cell::default());
+			}
+		}
+
+		let (row, col) = self.screen.cursor;
+		self.screen.cursor = (row.min(rows - 1), col.min(cols - 1));
+		self.scroll_region = (0, rows - 1);
+		self.wrap_pending = false;
+	}
+
+	pub fn feed(&mut self, data: &[u8]) {
+		for &byte in data {
+			self.byte(byte);
+		}
+	}
+
+	fn byte(&mut self, byte: u8) {
+		if let State::Csi(csi) = &mut self.state {
+			match byte {
+				0x1b => self.state = State::Escape,
+				0x00..=0x1f => self.control(byte),
+				b'0'..=b'9' => {
+					let digit = u16::from(byte - b'0');
+					csi.current = Some(
+						csi.current
+							.unwrap_or(0)
+							.saturating_mul(10)
+							.saturating_add(digit),
+					);
+				}
+				b';' | b':' => csi.params.push(csi.current.take().unwrap_or(0)),
+				0x3c..=0x3f if csi.params.is_empty() && csi.current.is_none() => {
+					csi.private = Some(byte);
+				}
+				0x20..=0x2f => csi.intermediate = true,
+				0x40..=0x7e => {
+					let mut csi = mem::take(csi);
+					self.state = State::Ground;
+					csi.params.extend(csi.current);
+					if !csi.intermediate {
+						self.csi(csi.private, &csi.params, byte);
+					}
+				}
+				_ => {}
+			}
+			return;
+		}
+
+		match mem::take(&mut self.state) {
+			State::Csi(_) => unreachable!("handled above"),
+			State::Ground => self.ground(byte),
+			State::Escape => self.escape(byte),
+			State::EscapeIntermediate => {
+				// charset designations and the like, which don't change the screen
+				if (0x20..=0x2f).contains(&byte) {
+					self.state = State::EscapeIntermediate;
+				}
+			}
+			State::Osc(mut data) => match byte {
+				0x07 => self.osc(&data),
+				0x1b => self.state = State::OscEscape(data),
+				_ => {
+					if data.len() < MAX_OSC {
+						data.push(byte);
+					}
+					self.state = State::Osc(data);
+				}
+			},
+			State::OscEscape(data) => {
+				self.osc(&data);
+				if byte != b'\\' {
+					self.escape(byte);
+				}
+			}
+			State::IgnoreString => match byte {
+				0x07 => {}
+				0x1b => self.state = State::IgnoreStringEscape,
+				_ => self.state = State::IgnoreString,
+			},
+			State::IgnoreStringEscape => {
+				if byte != b'\\' {
+					self.escape(byte);
+				}
+			}
+		}
+	}
+
+	fn ground(&mut self, byte: u8) {
+		if !self.utf8.is_empty() {
+			if (0x80..0xc0).contains(&byte) {
+				self.utf8.push(byte);
+				if self.utf8.len() == utf8_len(self.utf8[0]) {
+					let ch = std::str::from_utf8(&self.utf8)
+						.ok()
+						.and_then(|s| s.chars().next())
+						.unwrap_or(char::REPLACEMENT_CHARACTER);
+					self.utf8.clear();
+					self.print(ch);
+				}
+				return;
+			}
+
+			self.utf8.clear();
+			self.print(char::REPLACEMENT_CHARACTER);
+		}
+
+		match byte {
+			0x1b => self.state = State::Escape,
+			0x00..=0x1f | 0x7f => self.control(byte),
+			0x20..=0x7e => self.print(char::from(byte)),
+			0xc2..=0xf4 => self.utf8.push(byte),
+			_ => self.print(char::REPLACEMENT_CHARACTER),
+		}
+	}
+
+	fn escape(&mut self, byte: u8) {
+		match byte {
+			b'[' => self.state = State::Csi(Csi::default()),
+			b']' => self.state = State::Osc(Vec::new()),
+			b'P' | b'X' | b'^' | b'_' => self.state = State::IgnoreString,
+			0x20..=0x2f => self.state = State::EscapeIntermediate,
+			0x1b => self.state = State::Escape,
+			b'7' => self.save_cursor(),
+			b'8' => self.restore_cursor(),
+			b'D' => self.linefeed(),
+			b'E' => {
+				self.carriage_return();
+				self.linefeed();
+			}
+			b'M' => self.reverse_index(),
+			b'c' => {
+				let (cols, rows) = self.screen.size();
+				*self = Self::new(cols, rows);
+			}
+			_ => {}
+		}
+	}
+
+	fn control(&mut self, byte: u8) {
+		match byte {
+			0x08 => {
+				self.wrap_pending = false;
+				self.screen.cursor.1 = self.screen.cursor.1.saturating_sub(1);
+			}
+			0x09 => {
+				self.wrap_pending = false;
+				let next = (self.screen.cursor.1 / 8 + 1) * 8;
+				self.screen.cursor.1 = next.min(self.screen.cols() - 1);
+			}
+			0x0a..=0x0c => self.linefeed(),
+			0x0d => self.carriage_return(),
+			_ => {}
+		}
+	}
+
+	fn osc(&mut self, data: &[u8]) {
+		let data = String::from_utf8_lossy(data);
+		if let Some(("0" | "2", title)) = data.split_once(';') {
+			self.screen.title = title.to_owned();
+		}
+	}
+
+	fn csi(&mut self, private: Option<u8>, params: &[u16], action: u8) {
+		// a missing or zero parameter means the default, which is 1 for most sequences
+		let n = |i: usize| params.get(i).copied().filter(|&n| n > 0).unwrap_or(1);
+		let (rows, cols) = (self.screen.rows(), self.screen.cols());
+		let (row, col) = self.screen.cursor;
+
+		if private == Some(b'?') {
+			match action {
+				b'h' => params.iter().for_each(|&mode| self.dec_mode(mode, true)),
+				b'l' => params.iter().for_each(|&mode| self.dec_mode(mode, false)),
+				_ => {}
+			}
+			return;
+		} else if private.is_some() {
+			return;
+		}
+
+		match action {
+			b'@' => {
+				let line = &mut self.screen.cells[usize::from(row)];
+				for _ in 0..n(0).min(cols - col) {
+					line.pop();
+					line.insert(usize::from(col), blank(self.pen));
+				}
+			}
+			b'A' => self.move_to(row.saturating_sub(n(0)), col),
+			b'B' | b'e' => self.move_to(row.saturating_add(n(0)), col),
+			b'C' | b'a' => self.move_to(row, col.saturating_add(n(0))),
+			b'D' => self.move_to(row, col.saturating_sub(n(0))),
+			b'E' => self.move_to(row.saturating_add(n(0)), 0),
+			b'F' => self.move_to(row.saturating_sub(n(0)), 0),
+			b'G' | b'`' => self.move_to(row, n(0) - 1),
+			b'H' | b'f' => self.move_to(n(0) - 1, n(1) - 1),
+			b'd' => self.move_to(n(0) - 1, col),
+			b'J' => match params.first().copied().unwrap_or(0) {
+				0 => {
+					self.erase_line(row, col, cols);
+					(row + 1..rows).for_each(|row| self.erase_line(row, 0, cols));
+				}
+				1 => {
+					(0..row).for_each(|row| self.erase_line(row, 0, cols));
+					self.erase_line(row, 0, col + 1);
+				}
+				2 | 3 => (0..rows).for_each(|row| self.erase_line(row, 0, cols)),
+				_ => {}
+			},
+			b'K' => match params.first().copied().unwrap_or(0) {
+				0 => self.erase_line(row, col, cols),
+				1 => self.erase_line(row, 0, col + 1),
+				2 => self.erase_line(row, 0, cols),
+				_ => {}
+			},
+			b'L' | b'M' => {
+				let (top, bottom) = self.scroll_region;
+				if (top..=bottom).contains(&row) {
+					let count = n(0).min(bottom - row + 1);
+					if action == b'L' {
+						self.scroll_down_within(row, bottom, count);
+					} else {
+						self.scroll_up_within(row, bottom, count);
+					}
+					self.move_to(row, 0);
+				}
+			}
+			b'P' => {
+				let pen = self.pen;
+				let line = &mut self.screen.cells[usize::from(row)];
+				for _ in 0..n(0).min(cols - col) {
+					line.remove(usize::from(col));
+					line.push(blank(pen));
+				}
+			}
+			b'X' => self.erase_line(row, col, col.saturating_add(n(0)).min(cols)),
+			b'S' => {
+				let (top, bottom) = self.scroll_region;
+				self.scroll_up_within(top, bottom, n(0));
+			}
+			b'T' => {
+				let (top, bottom) = self.scroll_region;
+				self.scroll_down_within(top, bottom, n(0));
+			}
+			b'm' => self.sgr(params),
+			b'r' => {
+				let top = n(0) - 1;
+				let bottom = params
+					.get(1)
+					.copied()
+					.filter(|&n| n > 0)
+					.unwrap_or(rows)
+					.min(rows) - 1;
+				if top < bottom {
+					self.scroll_region = (top, bottom);
+					self.move_to(0, 0);
+				}
+			}
+			b's' => self.save_cursor(),
+			b'u' => self.restore_cursor(),
+			b'n' => match params.first() {
+				Some(5) => self.responses.extend_from_slice(b"\x1b[0n"),
+				Some(6) => {
+					let report = format!("\x1b[{};{}R", row + 1, col + 1);
+					self.responses.extend_from_slice(report.as_bytes());
+				}
+				_ => {}
+			},
+			// identify as a VT100 with advanced video, like xterm does by default
+			b'c' if params.first().copied().unwrap_or(0) == 0 => {
+				self.responses.extend_from_slice(b"\x1b[?1;2c");
+			}
+			_ => {}
+		}
+	}
+
+	fn dec_mode(&mut self, mode: u16, set: bool) {
+		match mode {
+			7 => self.autowrap = set,
+			25 => self.screen.cursor_visible = set,
+			47 | 1047 | 1049 => {
+				if set == self.screen.alternate {
+					return;
+				}
+				if mode == 1049 && set {
+					self.save_cursor();
+				}
+
+				let (cols, rows) = self.screen.size();
+				if set {
+					let blank = vec![vec![Cell::default(); usize::from(cols)]; usize::from(rows)];
+					self.saved_primary = Some(mem::replace(&mut self.screen.cells, blank));
+				} else if let Some(primary) = self.saved_primary.take() {
+					self.screen.cells = primary;
+				}
+				self.screen.alternate = set;
+
+				if mode == 1049 && !set {
+					self.restore_cursor();
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn sgr(&mut self, params: &[u16]) {
+		if params.is_empty() {
+			self.pen = CellAttrs::default();
+			return;
+		}
+
+		let mut params = params.iter().copied();
+		while let Some(param) = params.next() {
+			let pen = &mut self.pen;
+			match param {
+				0 => *pen = CellAttrs::default(),
+				1 => pen.bold = true,
+				2 => pen.dim = true,
+				3 => pen.italic = true,
+				4 => pen.underline = true,
+				5 | 6 => pen.blink = true,
+				7 => pen.inverse = true,
+				8 => pen.hidden = true,
+				9 => pen.strikethrough = true,
+				22 => (pen.bold, pen.dim) = (false, false),
+				23 => pen.italic = false,
+				24 => pen.underline = false,
+				25 => pen.blink = false,
+				27 => pen.inverse = false,
+				28 => pen.hidden = false,
+				29 => pen.strikethrough = false,
+				30..=37 => pen.fg = Color::Indexed((param - 30) as u8),
+				38 => pen.fg = extended_color(&mut params).unwrap_or(pen.fg),
+				39 => pen.fg = Color::Default,
+				40..=47 => pen.bg = Color::Indexed((param - 40) as u8),
+				48 => pen.bg = extended_color(&mut params).unwrap_or(pen.bg),
+				49 => pen.bg = Color::Default,
+				90..=97 => pen.fg = Color::Indexed((param - 90 + 8) as u8),
+				100..=107 => pen.bg = Color::Indexed((param - 100 + 8) as u8),
+				_ => {}
+			}
+		}
+	}
+
+	fn print(&mut self, ch: char) {
+		if self.wrap_pending {
+			self.carriage_return();
+			self.linefeed();
+		}
+
+		let (row, col) = self.screen.cursor;
+		self.screen.cells[usize::from(row)][usize::from(col)] = Cell {
+			ch,
+			attrs: self.pen,
+		};
+		if col + 1 < self.screen.cols() {
+			self.screen.cursor.1 += 1;
+		} else {
+			self.wrap_pending = self.autowrap;
+		}
+	}
+
+	fn move_to(&mut self, row: u16, col: u16) {
+		self.wrap_pending = false;
+		self.screen.cursor = (
+			row.min(self.screen.rows() - 1),
+			col.min(self.screen.cols() - 1),
+		);
+	}
+
+	fn carriage_return(&mut self) {
+		self.wrap_pending = false;
+		self.screen.cursor.1 = 0;
+	}
+
+	fn linefeed(&mut self) {
+		self.wrap_pending = false;
+		let (top, bottom) = self.scroll_region;
+		let row = self.screen.cursor.0;
+		if row == bottom {
+			self.scroll_up_within(top, bottom, 1);
+		} else if row + 1 < self.screen.rows() {
+			self.screen.cursor.0 += 1;
+		}
+	}
+
+	fn reverse_index(&mut self) {
+		self.wrap_pending = false;
+		let (top, bottom) = self.scroll_region;
+		let row = self.screen.cursor.0;
+		if row == top {
+			self.scroll_down_within(top, bottom, 1);
+		} else if row > 0 {
+			self.screen.cursor.0 -= 1;
+		}
+	}
+
+	fn scroll_up_within(&mut self, top: u16, bottom: u16, count: u16) {
+		let line = vec![blank(self.pen); usize::from(self.screen.cols())];
+		let region = &mut self.screen.cells[usize::from(top)..=usize::from(bottom)];
+		let count = usize::from(count).min(region.len());
+		region.rotate_left(count);
+		let len = region.len();
+		region[len - count..].fill(line);
+	}
+
+	fn scroll_down_within(&mut self, top: u16, bottom: u16, count: u16) {
+		let line = vec![blank(self.pen); usize::from(self.screen.cols())];
+		let region = &mut self.screen.cells[usize::from(top)..=usize::from(bottom)];
+		let count = usize::from(count).min(region.len());
+		region.rotate_right(count);
+		region[..count].fill(line);
+	}
+
+	fn erase_line(&mut self, row: u16, from: u16, to: u16) {
+		let pen = self.pen;
+		let line = &mut self.screen.cells[usize::from(row)];
+		line[usize::from(from)..usize::from(to)].fill(blank(pen));
+	}
+
+	fn save_cursor(&mut self) {
+		self.saved_cursor = Some((self.screen.cursor, self.pen));
+	}
+
+	fn restore_cursor(&mut self) {
+		let ((row, col), pen) = self.saved_cursor.unwrap_or_default();
+		self.pen = pen;
+		self.move_to(row, col);
+	}
+}
+
+/// An erased cell: blank, but keeping the background colour.
+fn blank(pen: CellAttrs) -> Cell {
+	Cell {
+		ch: ' ',
+		attrs: CellAttrs {
+			bg: pen.bg,
+			..CellAttrs::default()
+		},
+	}
+}
+
+/// The colour given by the parameters after a `38` or `48` SGR parameter.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+	match params.next()? {
+		5 => Some(Color::Indexed(params.next()? as u8)),
+		2 => Some(Color::Rgb(
+			params.next()? as u8,
+			params.next()? as u8,
+			params.next()? as u8,
+		)),
+		_ => None,
+	}
+}
+
+/// The length of a UTF-8 sequence from its first byte.
+fn utf8_len(first: u8) -> usize {
+	match first {
+		0xc0..=0xdf => 2,
+		0xe0..=0xef => 3,
+		_ => 4,
+	}
+}
+
+/// A virtual terminal screen fed from the master side of a pseudo-terminal by a background thread.
+#[derive(Debug)]
+pub(crate) struct LiveScreen {
+	state: Mutex<TerminalState>,
+	updated: Condvar,
+}
+
+#[derive(Debug)]
+struct TerminalState {
+	emulator: Emulator,
+	closed: bool,
+}
+
+impl LiveScreen {
+	/// Start feeding the terminal from the pseudo-terminal's master side.
+	///
+	/// `notify` is called after every update, and once the output is closed.
+	pub fn start(
+		cols: u16,
+		rows: u16,
+		master: OwnedFd,
+		mut notify: impl FnMut() + Send + 'static,
+	) -> Result<Arc<Self>> {
+		let terminal = Arc::new(Self {
+			state: Mutex::new(TerminalState {
+				emulator: Emulator::new(cols, rows),
+				closed: false,
+			}),
+			updated: Condvar::new(),
+		});
+
+		let mut master = File::from(master);
+		thread::Builder::new()
+			.name("process-wrap virtual terminal".into())
+			.spawn({
+				let terminal = terminal.clone();
+				move || {
+					let mut buf = [0; 8 * 1024];
+					loop {
+						let n = match master.read(&mut buf) {
+							// EIO is what Linux returns once every slave descriptor is closed
+							Ok(0) | Err(_) => break,
+							Ok(n) => n,
+						};
+
+						let responses = {
+							let mut state = terminal.lock();
+							state.emulator.feed(&buf[..n]);
+							state.emulator.take_responses()
+						};
+						if !responses.is_empty() {
+							master.write_all(&responses).ok();
+						}
+						terminal.updated.notify_all();
+						notify();
+					}
+
+					terminal.lock().closed = true;
+					terminal.updated.notify_all();
+					notify();
+				}
+			})?;
+
+		Ok(terminal)
+	}
+
+	/// A snapshot of the screen.
+	pub fn screen(&self) -> Screen {
+		self.lock().emulator.screen().clone()
+	}
+
+	/// Run a closure on the current screen.
+	pub fn with_screen<T>(&self, f: impl FnOnce(&Screen) -> T) -> T {
+		f(self.lock().emulator.screen())
+	}
+
+	pub fn resize(&self, cols: u16, rows: u16) {
+		self.lock().emulator.resize(cols, rows);
+	}
+
+	/// Check the screen against the predicate.
+	///
+	/// Returns `None` if it doesn't match yet, and an error if it never will.
+	#[cfg(feature = "tokio1")]
+	pub fn check(&self, predicate: &mut impl FnMut(&Screen) -> bool) -> Result<Option<Screen>> {
+		let state = self.lock();
+		Self::check_state(&state, predicate)
+	}
+
+	/// Block until the screen matches the predicate.
+	#[cfg(feature = "std")]
+	pub fn wait_for(
+		&self,
+		mut predicate: impl FnMut(&Screen) -> bool,
+		timeout: Duration,
+	) -> Result<Screen> {
+		let deadline = std::time::Instant::now() + timeout;
+		let mut state = self.lock();
+		loop {
+			if let Some(screen) = Self::check_state(&state, &mut predicate)? {
+				return Ok(screen);
+			}
+
+			let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+			if remaining.is_zero() {
+				return Err(Self::timed_out(state.emulator.screen(), timeout));
+			}
+			state = self
+				.updated
+				.wait_timeout(state, remaining)
+				.unwrap_or_else(|err| err.into_inner())
+				.0;
+		}
+	}
+
+	/// The error for a screen which didn't match in time.
+	#[cfg(feature = "tokio1")]
+	pub fn timed_out_error(&self, timeout: Duration) -> Error {
+		Self::timed_out(self.lock().emulator.screen(), timeout)
+	}
+
+	fn check_state(
+		state: &TerminalState,
+		predicate: &mut impl FnMut(&Screen) -> bool,
+	) -> Result<Option<Screen>> {
+		let screen = state.emulator.screen();
+		if predicate(screen) {
+			Ok(Some(screen.clone()))
+		} else if state.closed {
+			Err(Error::new(
+				ErrorKind::UnexpectedEof,
+				format!(
+					"output ended while waiting for the screen; screen:\n{}",
+					screen.text()
+				),
+			))
+		} else {
+			Ok(None)
+		}
+	}
+
+	fn timed_out(screen: &Screen, timeout: Duration) -> Error {
+		Error::new(
+			ErrorKind::TimedOut,
+			format!(
+				"timed out after {timeout:?} waiting for the screen; screen:\n{}",
+				screen.text()
+			),
+		)
+	}
+
+	fn lock(&self) -> MutexGuard<'_, TerminalState> {
+		self.state.lock().unwrap_or_else(|err| err.into_inner())
+	}
+}
@@ -0,0 +1,3 @@
+#![cfg(all(unix, feature = "futures"))]
+#[path = "futures_unix/mod.rs"]
+mod futures_unix;
@@ -0,0 +1,45 @@
+use super::prelude::*;
+
+#[test]
+fn nowrap() -> Result<()> {
+	block_on(async {
+		let child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.spawn()?;
+
+		assert_eq!(child.id(), child.inner().id());
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_group() -> Result<()> {
+	block_on(async {
+		let child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+
+		assert_eq!(child.id(), child.inner().id());
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_session() -> Result<()> {
+	block_on(async {
+		let child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessSession)
+		.spawn()?;
+
+		assert_eq!(child.id(), child.inner().id());
+
+		Ok(())
+	})
+}
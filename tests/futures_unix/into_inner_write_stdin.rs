@@ -0,0 +1,72 @@
+use super::prelude::*;
+
+#[test]
+fn nowrap() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("cat", |command| {
+			command.stdin(Stdio::piped()).stdout(Stdio::piped());
+		})
+		.spawn()?
+		.into_inner();
+
+		if let Some(mut din) = child.stdin().take() {
+			din.write_all(b"hello").await?;
+		}
+
+		let mut output = String::new();
+		if let Some(mut out) = child.stdout().take() {
+			out.read_to_string(&mut output).await?;
+		}
+
+		assert_eq!(output.as_str(), "hello");
+		Ok(())
+	})
+}
+
+#[test]
+fn process_group() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("cat", |command| {
+			command.stdin(Stdio::piped()).stdout(Stdio::piped());
+		})
+		.wrap(ProcessGroup::leader())
+		.spawn()?
+		.into_inner();
+
+		if let Some(mut din) = child.stdin().take() {
+			din.write_all(b"hello").await?;
+		}
+
+		let mut output = String::new();
+		if let Some(mut out) = child.stdout().take() {
+			out.read_to_string(&mut output).await?;
+		}
+
+		assert_eq!(output.as_str(), "hello");
+		Ok(())
+	})
+}
+
+#[test]
+fn process_session() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("cat", |command| {
+			command.stdin(Stdio::piped()).stdout(Stdio::piped());
+		})
+		.wrap(ProcessSession)
+		.spawn()?
+		.into_inner();
+
+		if let Some(mut din) = child.stdin().take() {
+			din.write_all(b"hello").await?;
+		}
+
+		let mut output = String::new();
+		if let Some(mut out) = child.stdout().take() {
+			out.read_to_string(&mut output).await?;
+		}
+
+		assert_eq!(output.as_str(), "hello");
+		Ok(())
+	})
+}
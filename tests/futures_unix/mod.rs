@@ -0,0 +1,37 @@
+mod prelude {
+	pub use std::{io::Result, os::unix::process::ExitStatusExt, process::Stdio, time::Duration};
+
+	pub use futures::{
+		executor::block_on,
+		io::{AsyncReadExt, AsyncWriteExt},
+	};
+	pub use nix::sys::signal::Signal;
+	pub use process_wrap::futures::*;
+
+	pub const DIE_TIME: Duration = Duration::from_millis(100);
+
+	/// Executor-agnostic sleep, as the tests shouldn't depend on a runtime's timer.
+	pub async fn sleep(duration: Duration) {
+		let (tx, rx) = futures::channel::oneshot::channel();
+		std::thread::spawn(move || {
+			std::thread::sleep(duration);
+			tx.send(()).ok();
+		});
+		rx.await.ok();
+	}
+}
+
+mod id_same_as_inner;
+mod inner_read_stdout;
+mod into_inner_write_stdin;
+mod kill_and_try_wait;
+mod output_events;
+mod signals;
+mod try_wait_after_die;
+mod try_wait_twice_after_sigterm;
+mod wait_after_die;
+mod wait_outcome;
+mod wait_twice;
+mod wait_twice_after_sigterm;
+mod wait_with_input_output;
+mod wait_with_output;
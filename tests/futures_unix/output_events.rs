@@ -0,0 +1,120 @@
+use nix::{
+	errno::Errno,
+	sys::wait::{WaitPidFlag, waitpid},
+	unistd::Pid,
+};
+
+use futures::TryStreamExt;
+
+use super::prelude::*;
+
+fn spawn_sh(script: &str, command: &mut CommandWrap) -> Result<Box<dyn ChildWrapper>> {
+	command
+		.command_mut()
+		.arg("-c")
+		.arg(script)
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped());
+	command.spawn()
+}
+
+/// Split events into stdout frames, stderr frames, and the exit status, checking it's last.
+fn split(events: Vec<OutputEvent>) -> (Vec<Vec<u8>>, Vec<Vec<u8>>, i32) {
+	let (mut stdout, mut stderr, mut code) = (Vec::new(), Vec::new(), None);
+	for event in events {
+		assert_eq!(code, None, "no events after exit");
+		match event {
+			OutputEvent::Stdout(frame) => stdout.push(frame),
+			OutputEvent::Stderr(frame) => stderr.push(frame),
+			OutputEvent::Exited(status) => code = status.code(),
+		}
+	}
+	(stdout, stderr, code.expect("exit is reported"))
+}
+
+#[test]
+fn lines() -> Result<()> {
+	block_on(async {
+		let child = spawn_sh(
+			r#"echo one; echo two >&2; printf 'three\r\nfour'"#,
+			&mut CommandWrap::with_new("sh", |_| {}),
+		)?;
+
+		let events = child.output_events(Framing::Lines).try_collect().await?;
+		let (stdout, stderr, code) = split(events);
+		assert_eq!(stdout, [&b"one"[..], b"three", b"four"]);
+		assert_eq!(stderr, [b"two"]);
+		assert_eq!(code, 0);
+		Ok(())
+	})
+}
+
+#[test]
+fn chunks() -> Result<()> {
+	block_on(async {
+		let child = spawn_sh(
+			"printf 'partial'; sleep 0.1; printf ' line\\nmore'; exit 3",
+			&mut CommandWrap::with_new("sh", |_| {}),
+		)?;
+
+		let events = child.output_events(Framing::Chunks).try_collect().await?;
+		let (stdout, stderr, code) = split(events);
+		assert!(stdout.len() >= 2, "read as it arrives: {stdout:?}");
+		assert_eq!(stdout.concat(), b"partial line\nmore");
+		assert!(stderr.is_empty());
+		assert_eq!(code, 3);
+		Ok(())
+	})
+}
+
+#[test]
+fn not_utf8() -> Result<()> {
+	block_on(async {
+		let child = spawn_sh(
+			r"printf '\377\376\n'",
+			&mut CommandWrap::with_new("sh", |_| {}),
+		)?;
+
+		let events = child.output_events(Framing::Lines).try_collect().await?;
+		let (stdout, _, _) = split(events);
+		assert_eq!(stdout, [[0xff, 0xfe]]);
+		Ok(())
+	})
+}
+
+#[test]
+fn no_outputs() -> Result<()> {
+	block_on(async {
+		let child = CommandWrap::with_new("sh", |command| {
+			command.arg("-c").arg("exit 2");
+		})
+		.spawn()?;
+
+		let events = child.output_events(Framing::Lines).try_collect().await?;
+		let (stdout, stderr, code) = split(events);
+		assert!(stdout.is_empty() && stderr.is_empty());
+		assert_eq!(code, 2);
+		Ok(())
+	})
+}
+
+#[test]
+fn process_group_waited() -> Result<()> {
+	block_on(async {
+		let mut command = CommandWrap::with_new("sh", |_| {});
+		command.wrap(ProcessGroup::leader());
+		let child = spawn_sh("(sleep 0.1; echo late) & echo early", &mut command)?;
+		let pgid = child.id() as i32;
+
+		let events = child.output_events(Framing::Lines).try_collect().await?;
+		let (stdout, _, code) = split(events);
+		assert_eq!(stdout, [&b"early"[..], b"late"]);
+		assert_eq!(code, 0);
+		assert_eq!(
+			waitpid(Pid::from_raw(-pgid), Some(WaitPidFlag::WNOHANG)),
+			Err(Errno::ECHILD),
+			"waited for through the wrappers"
+		);
+		Ok(())
+	})
+}
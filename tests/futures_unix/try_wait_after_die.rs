@@ -0,0 +1,51 @@
+use super::prelude::*;
+
+#[test]
+fn nowrap() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.spawn()?;
+		sleep(DIE_TIME).await;
+		let status = child.try_wait()?;
+		assert!(status.is_some());
+		assert!(status.unwrap().success());
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_group() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+		sleep(DIE_TIME).await;
+		let status = child.try_wait()?;
+		assert!(status.is_some());
+		assert!(status.unwrap().success());
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_session() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessSession)
+		.spawn()?;
+		sleep(DIE_TIME).await;
+		let status = child.try_wait()?;
+		assert!(status.is_some());
+		assert!(status.unwrap().success());
+
+		Ok(())
+	})
+}
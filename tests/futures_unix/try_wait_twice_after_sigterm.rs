@@ -0,0 +1,63 @@
+use super::prelude::*;
+
+#[test]
+fn nowrap() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("yes", |command| {
+			command.stdout(Stdio::null());
+		})
+		.spawn()?;
+		assert!(child.try_wait()?.is_none(), "pre sigterm");
+
+		child.signal(Signal::SIGTERM as _)?;
+		sleep(DIE_TIME).await;
+		assert!(child.try_wait()?.is_some(), "try_wait() one");
+
+		sleep(DIE_TIME).await;
+		assert!(child.try_wait()?.is_some(), "try_wait() two");
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_group() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("yes", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+		assert!(child.try_wait()?.is_none(), "pre sigterm");
+
+		child.signal(Signal::SIGTERM as _)?;
+		sleep(DIE_TIME).await;
+		assert!(child.try_wait()?.is_some(), "try_wait() one");
+
+		sleep(DIE_TIME).await;
+		assert!(child.try_wait()?.is_some(), "try_wait() two");
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_session() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("yes", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessSession)
+		.spawn()?;
+		assert!(child.try_wait()?.is_none(), "pre sigterm");
+
+		child.signal(Signal::SIGTERM as _)?;
+		sleep(DIE_TIME).await;
+		assert!(child.try_wait()?.is_some(), "try_wait() one");
+
+		sleep(DIE_TIME).await;
+		assert!(child.try_wait()?.is_some(), "try_wait() two");
+
+		Ok(())
+	})
+}
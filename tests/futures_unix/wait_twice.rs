@@ -0,0 +1,57 @@
+use super::prelude::*;
+
+#[test]
+fn nowrap() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.spawn()?;
+
+		let status = child.wait().await?;
+		assert!(status.success());
+
+		let status = child.wait().await?;
+		assert!(status.success());
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_group() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+
+		let status = child.wait().await?;
+		assert!(status.success());
+
+		let status = child.wait().await?;
+		assert!(status.success());
+
+		Ok(())
+	})
+}
+
+#[test]
+fn process_session() -> Result<()> {
+	block_on(async {
+		let mut child = CommandWrap::with_new("echo", |command| {
+			command.stdout(Stdio::null());
+		})
+		.wrap(ProcessSession)
+		.spawn()?;
+
+		let status = child.wait().await?;
+		assert!(status.success());
+
+		let status = child.wait().await?;
+		assert!(status.success());
+
+		Ok(())
+	})
+}
@@ -0,0 +1,103 @@
+use std::io::ErrorKind;
+
+use super::prelude::*;
+
+/// More than fits in the pipe buffers, so writing it all before reading would deadlock.
+fn big_input() -> Vec<u8> {
+	(0..1024 * 1024).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn nowrap() -> Result<()> {
+	block_on(async {
+		let input = big_input();
+		let child = CommandWrap::with_new("cat", |command| {
+			command
+				.stdin(Stdio::piped())
+				.stdout(Stdio::piped())
+				.stderr(Stdio::piped());
+		})
+		.spawn()?;
+
+		let output = child.wait_with_input_output(input.clone()).await?;
+		assert!(output.status.success());
+		assert!(output.stdout == input, "stdout is the input");
+		assert_eq!(output.stderr, Vec::new());
+		Ok(())
+	})
+}
+
+#[test]
+fn both_outputs() -> Result<()> {
+	block_on(async {
+		let input = big_input();
+		let child = CommandWrap::with_new("sh", |command| {
+			command
+				.arg("-c")
+				.arg("tee /dev/stderr")
+				.stdin(Stdio::piped())
+				.stdout(Stdio::piped())
+				.stderr(Stdio::piped());
+		})
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+
+		let output = child.wait_with_input_output(input.clone()).await?;
+		assert!(output.status.success());
+		assert!(output.stdout == input, "stdout is the input");
+		assert!(output.stderr == input, "stderr is the input");
+		Ok(())
+	})
+}
+
+#[test]
+fn child_closes_stdin() -> Result<()> {
+	block_on(async {
+		let child = CommandWrap::with_new("sh", |command| {
+			command
+				.arg("-c")
+				.arg("exec 0<&-; echo done")
+				.stdin(Stdio::piped())
+				.stdout(Stdio::piped());
+		})
+		.spawn()?;
+
+		let output = child.wait_with_input_output(big_input()).await?;
+		assert!(output.status.success());
+		assert_eq!(output.stdout, b"done\n".to_vec());
+		Ok(())
+	})
+}
+
+#[test]
+fn no_input() -> Result<()> {
+	block_on(async {
+		let child = CommandWrap::with_new("cat", |command| {
+			command.stdin(Stdio::piped()).stdout(Stdio::piped());
+		})
+		.wrap(ProcessSession)
+		.spawn()?;
+
+		let output = child.wait_with_input_output(Vec::new()).await?;
+		assert!(output.status.success());
+		assert_eq!(output.stdout, Vec::new());
+		Ok(())
+	})
+}
+
+#[test]
+fn stdin_not_piped() -> Result<()> {
+	block_on(async {
+		let mut command = CommandWrap::with_new("true", |command| {
+			command.stdin(Stdio::null());
+		});
+
+		let err = command
+			.spawn()?
+			.wait_with_input_output(b"hello".to_vec())
+			.await
+			.unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidInput);
+		Ok(())
+	})
+}
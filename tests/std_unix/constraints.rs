@@ -0,0 +1,128 @@
+use std::{
+	io::ErrorKind,
+	process::Command,
+	sync::{Arc, Mutex},
+};
+
+use super::prelude::*;
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+#[derive(Debug, Default)]
+struct First {
+	log: Log,
+	reorder: bool,
+}
+
+#[derive(Debug, Default)]
+struct Second {
+	log: Log,
+	before_first: bool,
+}
+
+impl CommandWrapper for First {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		let constraint = WrapperConstraint::before::<Second>();
+		vec![if self.reorder {
+			constraint.reorderable()
+		} else {
+			constraint
+		}]
+	}
+
+	fn pre_spawn(&mut self, _command: &mut Command, core: &CommandWrap) -> Result<()> {
+		assert!(
+			core.get_wrap::<Second>().is_some(),
+			"sees the other wrapper"
+		);
+		self.log.lock().unwrap().push("first");
+		Ok(())
+	}
+}
+
+impl CommandWrapper for Second {
+	fn constraints(&self) -> Vec<WrapperConstraint> {
+		if self.before_first {
+			vec![WrapperConstraint::before::<First>().reorderable()]
+		} else {
+			Vec::new()
+		}
+	}
+
+	fn pre_spawn(&mut self, _command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		self.log.lock().unwrap().push("second");
+		Ok(())
+	}
+}
+
+#[test]
+fn conflict() -> Result<()> {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(ProcessGroup::leader())
+		.wrap(ProcessSession)
+		.spawn()
+		.unwrap_err();
+
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	assert!(err.to_string().contains("conflicts with"), "{err}");
+	Ok(())
+}
+
+#[test]
+fn unmet_order() -> Result<()> {
+	let log = Log::default();
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(Second {
+			log: log.clone(),
+			..Default::default()
+		})
+		.wrap(First {
+			log: log.clone(),
+			reorder: false,
+		})
+		.spawn()
+		.unwrap_err();
+
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	assert!(err.to_string().contains("must be added before"), "{err}");
+	assert!(log.lock().unwrap().is_empty(), "no pre_spawn ran");
+	Ok(())
+}
+
+#[test]
+fn reorder() -> Result<()> {
+	let log = Log::default();
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(Second {
+			log: log.clone(),
+			..Default::default()
+		})
+		.wrap(First {
+			log: log.clone(),
+			reorder: true,
+		})
+		.spawn()?;
+
+	assert!(child.wait()?.success());
+	assert_eq!(*log.lock().unwrap(), ["first", "second"]);
+	Ok(())
+}
+
+#[test]
+fn cycle() -> Result<()> {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(First {
+			reorder: true,
+			..Default::default()
+		})
+		.wrap(Second {
+			before_first: true,
+			..Default::default()
+		})
+		.spawn()
+		.unwrap_err();
+
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	assert!(err.to_string().contains("can't all be met"), "{err}");
+	Ok(())
+}
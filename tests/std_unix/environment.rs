@@ -0,0 +1,182 @@
+#![cfg(feature = "environment")]
+
+use std::{ffi::OsStr, fs};
+
+use super::prelude::*;
+
+fn env_of(mut command: CommandWrap) -> Result<String> {
+	command.command_mut().stdout(Stdio::piped());
+	let output = command.spawn()?.wait_with_output()?;
+	Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn clean_with_allow_list() -> Result<()> {
+	let mut command = CommandWrap::with_new("/usr/bin/env", |command| {
+		command.env("FROM_COMMAND", "yes");
+	});
+	command.wrap(Environment::clean().allow("PAT?").allow("NOT_*"));
+
+	let env = env_of(command)?;
+	let mut names: Vec<_> = env
+		.lines()
+		.filter_map(|l| l.split_once('='))
+		.map(|(k, _)| k)
+		.collect();
+	names.sort();
+	assert_eq!(names, ["FROM_COMMAND", "PATH"]);
+	Ok(())
+}
+
+#[test]
+fn env_files_and_pins() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let first = dir.path().join("first.env");
+	let second = dir.path().join("second.env");
+	fs::write(
+		&first,
+		"# comment\nexport A=one\nB='two # not a comment'\nC=\"line\\nbreak\"\nD=four # comment\n",
+	)?;
+	fs::write(&second, "A=overridden\nTZ=Europe/Paris\n")?;
+
+	let mut command = CommandWrap::with_new("/usr/bin/env", |_| {});
+	command
+		.wrap(Environment::clean().env_file(&first).pin("TZ", "UTC"))
+		.wrap(Environment::new().env_file(&second));
+
+	let env = env_of(command)?;
+	assert!(env.lines().any(|line| line == "A=overridden"), "{env}");
+	assert!(env.contains("B=two # not a comment\n"), "{env}");
+	assert!(env.contains("C=line\nbreak\n"), "{env}");
+	assert!(env.lines().any(|line| line == "D=four"), "{env}");
+	assert!(env.lines().any(|line| line == "TZ=UTC"), "{env}");
+	Ok(())
+}
+
+#[test]
+fn effective() -> Result<()> {
+	let mut command = CommandWrap::with_new("true", |command| {
+		command.env_remove("LANG");
+	});
+	command.wrap(Environment::clean().pin("LANG", "C").pin("TZ", "UTC"));
+	assert!(
+		command
+			.get_wrap::<Environment>()
+			.unwrap()
+			.effective()
+			.is_none()
+	);
+
+	let preview = command
+		.get_wrap::<Environment>()
+		.unwrap()
+		.resolve(command.command())?;
+	command.spawn()?.wait()?;
+
+	let effective = command
+		.get_wrap::<Environment>()
+		.unwrap()
+		.effective()
+		.unwrap();
+	assert_eq!(
+		effective,
+		[
+			(OsStr::new("LANG"), OsStr::new("C")),
+			(OsStr::new("TZ"), OsStr::new("UTC"))
+		]
+	);
+	assert_eq!(preview.len(), 2);
+	Ok(())
+}
+
+#[test]
+fn missing_env_file() -> Result<()> {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(Environment::new().env_file("/nonexistent/.env"))
+		.spawn()
+		.unwrap_err();
+
+	assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+	Ok(())
+}
+
+#[test]
+fn respawn_rereads_env_files() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let file = dir.path().join("test.env");
+	fs::write(&file, "A=one\n")?;
+
+	let mut command = CommandWrap::with_new("/usr/bin/env", |command| {
+		command.env("B", "command").stdout(Stdio::piped());
+	});
+	command.wrap(Environment::clean().env_file(&file));
+
+	let output = command.spawn()?.wait_with_output()?;
+	let env = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert_eq!(env, "A=one\nB=command\n");
+
+	let output = command.command_mut().output()?;
+	let env = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert!(
+		env.lines().any(|line| line == "B=command"),
+		"the command's own variables are put back"
+	);
+	assert!(
+		env.lines().any(|line| line.starts_with("PATH=")),
+		"the parent's variables are put back"
+	);
+	assert!(
+		!env.lines().any(|line| line.starts_with("A=")),
+		"the policy's variables are removed"
+	);
+
+	fs::write(&file, "A=two\n")?;
+	let output = command.spawn()?.wait_with_output()?;
+	let env = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert_eq!(env, "A=two\nB=command\n");
+	Ok(())
+}
+
+#[test]
+fn direct_spawn_after_policy() -> Result<()> {
+	let mut command = CommandWrap::with_new("/usr/bin/env", |command| {
+		command.env("A", "command").stdout(Stdio::piped());
+	});
+	command.wrap(Environment::new().pin("A", "pinned").pin("B", "pinned"));
+
+	let output = command.spawn()?.wait_with_output()?;
+	let env = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert!(env.lines().any(|line| line == "A=pinned"));
+	assert!(env.lines().any(|line| line == "B=pinned"));
+
+	let output = command.command_mut().output()?;
+	let env = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert!(
+		env.lines().any(|line| line == "A=command"),
+		"the command's own variables are put back"
+	);
+	assert!(
+		!env.lines().any(|line| line.starts_with("B=")),
+		"the pinned variables are removed"
+	);
+	Ok(())
+}
+
+#[test]
+fn inherits_on_respawn() -> Result<()> {
+	let mut command = CommandWrap::with_new("true", |_| {});
+	command.wrap(Environment::new());
+	command.spawn()?.wait()?;
+
+	command.spawn()?.wait()?;
+	let effective = command
+		.get_wrap::<Environment>()
+		.unwrap()
+		.effective()
+		.unwrap();
+	assert!(
+		effective.iter().any(|(key, _)| *key == "PATH"),
+		"still inherits after the variables were put back"
+	);
+	Ok(())
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "expect")]
+
+use std::io::ErrorKind;
+
+use super::prelude::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn spawn_sh(script: &str, expect: Expect) -> Result<Box<dyn ChildWrapper>> {
+	CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg(script);
+	})
+	.wrap(ProcessGroup::leader())
+	.wrap(expect)
+	.spawn()
+}
+
+#[test]
+fn conversation() -> Result<()> {
+	let mut child = spawn_sh(
+		r#"printf 'name? '; read name; echo "hello, $name""#,
+		Expect::new(),
+	)?;
+	let session = child.find_layer_mut::<ExpectChild>().unwrap();
+
+	let prompt = session.expect(r"name\? ", TIMEOUT)?;
+	assert_eq!(prompt.before, "");
+	session.send_line("world")?;
+	let greeting = session.expect(r"hello, \w+", TIMEOUT)?;
+	assert_eq!(greeting.matched, "hello, world");
+	assert_eq!(session.expect_eof(TIMEOUT)?, "\n");
+
+	assert_eq!(
+		session.transcript(),
+		[
+			Exchange::Received(b"name? ".to_vec()),
+			Exchange::Sent(b"world\n".to_vec()),
+			Exchange::Received(b"hello, world\n".to_vec()),
+		]
+	);
+	assert!(child.wait()?.success());
+	Ok(())
+}
+
+#[test]
+fn timeout_shows_recent_output() -> Result<()> {
+	let mut child = spawn_sh("echo nope; sleep 10", Expect::new())?;
+	let session = child.find_layer_mut::<ExpectChild>().unwrap();
+
+	let err = session.expect("yes", DIE_TIME).unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::TimedOut);
+	assert!(err.to_string().contains("nope"), "{err}");
+
+	child.kill()?;
+	Ok(())
+}
+
+#[test]
+fn output_ends_before_match() -> Result<()> {
+	let mut child = spawn_sh("echo nope", Expect::new())?;
+	let session = child.find_layer_mut::<ExpectChild>().unwrap();
+
+	let err = session.expect("yes", TIMEOUT).unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+	assert!(err.to_string().contains("nope"), "{err}");
+
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn with_stderr() -> Result<()> {
+	// only on stderr, as the two streams are merged in the order they're read, which may vary
+	let mut child = spawn_sh("echo oops >&2", Expect::new().with_stderr())?;
+	let session = child.find_layer_mut::<ExpectChild>().unwrap();
+
+	session.expect("oops", TIMEOUT)?;
+	session.expect_eof(TIMEOUT)?;
+
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn invalid_pattern() -> Result<()> {
+	let mut child = spawn_sh("true", Expect::new())?;
+	let session = child.find_layer_mut::<ExpectChild>().unwrap();
+
+	let err = session.expect("(", TIMEOUT).unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+	child.wait()?;
+	Ok(())
+}
@@ -0,0 +1,81 @@
+use super::prelude::*;
+
+#[derive(Debug)]
+struct Logging;
+
+#[derive(Debug)]
+struct LoggingChild {
+	inner: Box<dyn ChildWrapper>,
+	calls: usize,
+}
+
+impl CommandWrapper for Logging {
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		Ok(Box::new(LoggingChild { inner, calls: 0 }))
+	}
+}
+
+impl ChildWrapper for LoggingChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.inner
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.inner
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.inner
+	}
+}
+
+#[test]
+fn under_custom_wrapper() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(ProcessGroup::leader())
+		.wrap(Logging)
+		.spawn()?;
+
+	let pgid = child.find_layer::<ProcessGroupChild>().unwrap().pgid();
+	assert_eq!(pgid, child.id());
+	assert!(child.find_layer::<LoggingChild>().is_some());
+	assert!(child.find_layer::<std::process::Child>().is_some());
+
+	child.find_layer_mut::<LoggingChild>().unwrap().calls += 1;
+	assert_eq!(child.find_layer::<LoggingChild>().unwrap().calls, 1);
+
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn under_process_group() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(Logging)
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+
+	assert!(child.find_layer::<ProcessGroupChild>().is_some());
+	assert!(child.find_layer::<std::process::Child>().is_some());
+
+	child.find_layer_mut::<LoggingChild>().unwrap().calls += 1;
+	assert_eq!(child.find_layer::<LoggingChild>().unwrap().calls, 1);
+
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn missing() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(Logging)
+		.spawn()?;
+
+	assert!(child.find_layer::<ProcessGroupChild>().is_none());
+	assert!(child.find_layer_mut::<ProcessGroupChild>().is_none());
+
+	child.wait()?;
+	Ok(())
+}
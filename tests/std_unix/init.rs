@@ -0,0 +1,119 @@
+#![cfg(feature = "init")]
+
+use std::{
+	env,
+	io::{BufRead, BufReader},
+	process::{Child, Command, Output},
+};
+
+use nix::{sys::signal::kill, unistd::Pid};
+
+use super::prelude::*;
+
+const SCRIPT: &str = "PROCESS_WRAP_TEST_INIT_SCRIPT";
+
+/// Not a test: this is run as the init by the tests below, as it must be in its own process.
+#[test]
+#[ignore = "run by the other init tests"]
+fn entrypoint() {
+	let Ok(script) = env::var(SCRIPT) else {
+		return;
+	};
+
+	let command = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg(script);
+	});
+	let Err(err) = run_init(command);
+	println!("init failed: {err}");
+}
+
+fn init(script: &str) -> Command {
+	let mut command = Command::new(env::current_exe().unwrap());
+	command
+		.args(["std_unix::init::entrypoint", "--exact", "--ignored"])
+		.args(["--nocapture", "--test-threads=1"])
+		.env(SCRIPT, script)
+		.stdout(Stdio::piped());
+	command
+}
+
+fn run(script: &str) -> Result<Output> {
+	init(script).output()
+}
+
+fn stdout(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn exits_with_code() -> Result<()> {
+	let output = run("exit 7")?;
+	assert_eq!(output.status.code(), Some(7), "{}", stdout(&output));
+	Ok(())
+}
+
+#[test]
+fn reaps_orphans() -> Result<()> {
+	let output = run("pid=$(sh -c 'sleep 0.1 >/dev/null & echo $!')
+		sleep 0.5
+		if kill -0 $pid 2>/dev/null; then echo zombie; else echo reaped; fi")?;
+	let stdout = stdout(&output);
+	assert!(output.status.success(), "{stdout}");
+	assert!(stdout.contains("reaped"), "{stdout}");
+	Ok(())
+}
+
+#[test]
+fn forwards_signals() -> Result<()> {
+	let mut init: Child = init(
+		"trap 'echo terminated; exit 3' TERM
+		echo ready
+		while :; do sleep 0.05; done",
+	)
+	.spawn()?;
+
+	// the test harness prints on the same line before the script's output
+	let mut lines = BufReader::new(init.stdout.take().unwrap()).lines();
+	while !lines.next().unwrap()?.ends_with("ready") {}
+	kill(Pid::from_raw(init.id() as _), Signal::SIGTERM)?;
+
+	assert_eq!(lines.next().unwrap()?, "terminated");
+	assert_eq!(init.wait()?.code(), Some(3));
+	Ok(())
+}
+
+#[test]
+fn raises_fatal_signal() -> Result<()> {
+	let output = run("kill -SEGV $$")?;
+	assert_eq!(
+		output.status.signal(),
+		Some(Signal::SIGSEGV as _),
+		"{}",
+		stdout(&output)
+	);
+	assert!(!output.status.core_dumped());
+	Ok(())
+}
+
+#[test]
+fn spawn_error() -> Result<()> {
+	let output = init("exit 0").env("PATH", "/nonexistent").output()?;
+	assert!(output.status.success());
+	assert!(
+		stdout(&output).contains("init failed"),
+		"{}",
+		stdout(&output)
+	);
+	Ok(())
+}
+
+#[cfg(feature = "kill-on-drop")]
+#[test]
+fn conflicts_with_kill_on_drop() {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(Init::new())
+		.wrap(KillOnDrop::new())
+		.spawn()
+		.unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,79 @@
+#![cfg(all(target_os = "linux", feature = "kill-tree"))]
+
+use std::fs;
+
+use super::prelude::*;
+
+/// Whether a process has exited, even if it's a zombie that init hasn't reaped yet.
+fn exited(pid: i32) -> bool {
+	fs::read_to_string(format!("/proc/{pid}/stat")).map_or(true, |stat| {
+		stat.rsplit_once(") ")
+			.is_some_and(|(_, rest)| rest.starts_with('Z'))
+	})
+}
+
+/// Spawn a shell which starts a background process, and get the PID of that process.
+fn spawn_with_descendant(
+	background: &str,
+	command: &mut CommandWrap,
+) -> Result<(Box<dyn ChildWrapper>, i32)> {
+	command
+		.command_mut()
+		.arg("-c")
+		.arg(format!("{background} & echo $!; wait"))
+		.stdout(Stdio::piped());
+	let mut child = command.spawn()?;
+
+	let mut line = String::new();
+	BufReader::new(child.stdout().as_mut().unwrap()).read_line(&mut line)?;
+	Ok((child, line.trim().parse().unwrap()))
+}
+
+#[test]
+fn escaped_session() -> Result<()> {
+	let mut command = CommandWrap::with_new("sh", |_| {});
+	command.wrap(ProcessGroup::leader()).wrap(KillTree);
+	let (mut child, daemon) = spawn_with_descendant("setsid sleep 10", &mut command)?;
+	assert!(!exited(daemon));
+
+	child.kill()?;
+	sleep(DIE_TIME);
+	assert!(exited(daemon), "daemon in another session killed");
+	assert_eq!(child.kill_reason().as_deref(), Some("process tree killed"));
+	Ok(())
+}
+
+#[test]
+fn nested() -> Result<()> {
+	let mut command = CommandWrap::with_new("sh", |_| {});
+	command.wrap(KillTree);
+	let (mut child, shell) =
+		spawn_with_descendant("sh -c 'setsid sleep 10 & sleep 10'", &mut command)?;
+	sleep(DIE_TIME);
+	let descendants: Vec<i32> = fs::read_to_string(format!("/proc/{shell}/task/{shell}/children"))?
+		.split_whitespace()
+		.map(|pid| pid.parse().unwrap())
+		.collect();
+	assert_eq!(descendants.len(), 2);
+
+	child.kill()?;
+	sleep(DIE_TIME);
+	assert!(exited(shell));
+	for pid in descendants {
+		assert!(exited(pid), "grandchild {pid} killed");
+	}
+	Ok(())
+}
+
+#[test]
+fn after_exit() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(KillTree)
+		.spawn()?;
+	assert!(child.wait()?.success());
+
+	// the tree is left alone, so this is only as successful as killing the reaped child
+	child.kill().ok();
+	assert_eq!(child.kill_reason(), None);
+	Ok(())
+}
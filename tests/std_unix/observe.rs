@@ -0,0 +1,121 @@
+#![cfg(feature = "observe")]
+
+use std::sync::{Arc, Mutex};
+
+use process_wrap::Observer;
+
+use super::prelude::*;
+
+#[derive(Debug, Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Recorder {
+	fn events(&self) -> Vec<String> {
+		self.0.lock().unwrap().clone()
+	}
+
+	fn push(&self, event: impl Into<String>) {
+		self.0.lock().unwrap().push(event.into());
+	}
+}
+
+impl Observer for Recorder {
+	fn on_pre_spawn(&self, command: &std::process::Command) {
+		self.push(format!("pre_spawn {}", command.get_program().display()));
+	}
+
+	fn on_spawned(&self, _pid: u32) {
+		self.push("spawned");
+	}
+
+	fn on_signal(&self, _pid: u32, signal: i32) {
+		self.push(format!("signal {signal}"));
+	}
+
+	fn on_kill(&self, _pid: u32) {
+		self.push("kill");
+	}
+
+	fn on_exit(&self, _pid: u32, status: std::process::ExitStatus, _duration: Duration) {
+		self.push(format!("exit {}", status.success()));
+	}
+
+	fn on_error(&self, pid: Option<u32>, _error: &std::io::Error) {
+		self.push(format!("error {}", pid.is_some()));
+	}
+}
+
+#[test]
+fn lifecycle() -> Result<()> {
+	let recorder = Arc::new(Recorder::default());
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(Observe::new(recorder.clone()))
+		.spawn()?;
+
+	child.wait()?;
+	child.wait()?;
+	assert_eq!(
+		recorder.events(),
+		vec!["pre_spawn true", "spawned", "exit true"]
+	);
+
+	Ok(())
+}
+
+#[test]
+fn signal_and_kill() -> Result<()> {
+	let recorder = Arc::new(Recorder::default());
+	let mut child = CommandWrap::with_new("yes", |command| {
+		command.stdout(Stdio::null());
+	})
+	.wrap(ProcessGroup::leader())
+	.wrap(Observe::new(recorder.clone()))
+	.spawn()?;
+
+	child.signal(Signal::SIGCONT as _)?;
+	child.kill()?;
+	assert_eq!(
+		recorder.events(),
+		vec![
+			"pre_spawn yes".to_string(),
+			"spawned".into(),
+			format!("signal {}", Signal::SIGCONT as i32),
+			"kill".into(),
+			"exit false".into(),
+		]
+	);
+
+	Ok(())
+}
+
+#[test]
+fn spawn_error() -> Result<()> {
+	let recorder = Arc::new(Recorder::default());
+	let res = CommandWrap::with_new("/nonexistent/program", |_| {})
+		.wrap(Observe::new(recorder.clone()))
+		.spawn();
+
+	assert!(res.is_err());
+	assert_eq!(
+		recorder.events(),
+		vec!["pre_spawn /nonexistent/program", "error false"]
+	);
+
+	Ok(())
+}
+
+#[test]
+fn multiple_observers() -> Result<()> {
+	let first = Arc::new(Recorder::default());
+	let second = Arc::new(Recorder::default());
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(Observe::new(first.clone()))
+		.wrap(Observe::new(second.clone()))
+		.spawn()?;
+
+	child.wait()?;
+	assert_eq!(first.events(), second.events());
+	assert_eq!(first.events().len(), 3);
+
+	Ok(())
+}
@@ -0,0 +1,99 @@
+#![cfg(all(target_os = "linux", feature = "oom-score-adj"))]
+
+use std::fs;
+
+use super::prelude::*;
+
+fn score(pid: u32) -> i32 {
+	fs::read_to_string(format!("/proc/{pid}/oom_score_adj"))
+		.unwrap()
+		.trim()
+		.parse()
+		.unwrap()
+}
+
+#[test]
+fn applied_at_spawn() -> Result<()> {
+	let mut child = CommandWrap::with_new("sleep", |command| {
+		command.arg("1");
+	})
+	.wrap(OomScoreAdj(500))
+	.spawn()?;
+
+	let pid = child.id();
+	assert_eq!(score(pid), 500);
+
+	child
+		.find_layer::<OomScoreAdjChild>()
+		.unwrap()
+		.set_score(600)?;
+	assert_eq!(score(pid), 600);
+
+	child.kill()?;
+	Ok(())
+}
+
+#[test]
+fn out_of_range() -> Result<()> {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(OomScoreAdj(1001))
+		.spawn()
+		.unwrap_err();
+
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[test]
+fn whole_group() -> Result<()> {
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg("sleep 1 & sleep 1; wait");
+	})
+	.wrap(ProcessGroup::leader())
+	.wrap(OomScoreAdj(100))
+	.spawn()?;
+	sleep(DIE_TIME);
+
+	let pid = child.id();
+	child
+		.find_layer::<OomScoreAdjChild>()
+		.unwrap()
+		.set_group_score(700)?;
+
+	let members: Vec<u32> = fs::read_dir("/proc")?
+		.filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+		.filter(|&p: &u32| {
+			fs::read_to_string(format!("/proc/{p}/stat")).is_ok_and(|stat| {
+				let (_, rest) = stat.rsplit_once(')').unwrap();
+				rest.split_whitespace().nth(2) == Some(&pid.to_string())
+			})
+		})
+		.collect();
+	assert!(members.len() >= 3, "{members:?}");
+	for member in members {
+		assert_eq!(score(member), 700);
+	}
+
+	child.kill()?;
+	Ok(())
+}
+
+#[test]
+fn not_own_group() -> Result<()> {
+	let mut child = CommandWrap::with_new("sleep", |command| {
+		command.arg("1");
+	})
+	.wrap(OomScoreAdj(100))
+	.spawn()?;
+
+	assert!(
+		child
+			.find_layer::<OomScoreAdjChild>()
+			.unwrap()
+			.set_group_score(200)
+			.is_err()
+	);
+
+	child.kill()?;
+	Ok(())
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "process-group")]
+
+use std::{
+	os::unix::process::CommandExt,
+	process::{Child, Command},
+	sync::{Arc, Mutex},
+};
+
+use super::prelude::*;
+
+fn member(pgid: u32, script: &str) -> Result<Child> {
+	Command::new("sh")
+		.arg("-c")
+		.arg(script)
+		.process_group(pgid as _)
+		.spawn()
+}
+
+/// Spawn a group leader, and members which exit with code 3 and SIGSEGV respectively.
+fn spawn_group() -> Result<(Box<dyn ChildWrapper>, [u32; 2])> {
+	let leader = CommandWrap::with_new("sleep", |command| {
+		command.arg("0.2");
+	})
+	.wrap(ProcessGroup::leader())
+	.spawn()?;
+
+	let exited = member(leader.id(), "exit 3")?;
+	let segfaulted = member(leader.id(), "kill -SEGV $$")?;
+	Ok((leader, [exited.id(), segfaulted.id()]))
+}
+
+#[test]
+fn records_member_statuses() -> Result<()> {
+	let (mut child, [exited, segfaulted]) = spawn_group()?;
+	assert!(child.wait()?.success());
+
+	let group = child.find_layer::<ProcessGroupChild>().unwrap();
+	let reaped = group.reaped_members();
+	assert_eq!(reaped.len(), 2, "{reaped:?}");
+	let status = |pid| reaped.iter().find(|(reaped, _)| *reaped == pid).unwrap().1;
+	assert_eq!(status(exited).code(), Some(3));
+	assert_eq!(status(segfaulted).signal(), Some(Signal::SIGSEGV as _));
+
+	Ok(())
+}
+
+#[test]
+fn calls_back_as_reaped() -> Result<()> {
+	let (mut child, _) = spawn_group()?;
+	let called = Arc::new(Mutex::new(Vec::new()));
+	let calls = called.clone();
+	child
+		.find_layer_mut::<ProcessGroupChild>()
+		.unwrap()
+		.on_reaped(move |pid, status| calls.lock().unwrap().push((pid, status)));
+	child.wait()?;
+
+	let group = child.find_layer::<ProcessGroupChild>().unwrap();
+	assert_eq!(called.lock().unwrap().len(), 2);
+	assert_eq!(*called.lock().unwrap(), group.reaped_members());
+
+	Ok(())
+}
+
+#[test]
+fn leader_not_included() -> Result<()> {
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg("exit 4");
+	})
+	.wrap(ProcessGroup::leader())
+	.spawn()?;
+	assert_eq!(child.wait()?.code(), Some(4));
+
+	let group = child.find_layer::<ProcessGroupChild>().unwrap();
+	assert_eq!(group.reaped_members(), Vec::new());
+
+	Ok(())
+}
@@ -0,0 +1,120 @@
+#![cfg(all(target_os = "linux", feature = "scheduling"))]
+
+use std::fs;
+
+use super::prelude::*;
+
+/// Fields of `/proc/<pid>/stat` after the command name, so numbered from 3 in proc(5).
+fn stat(pid: u32) -> Vec<String> {
+	let stat = fs::read_to_string(format!("/proc/{pid}/stat")).unwrap();
+	let (_, rest) = stat.rsplit_once(')').unwrap();
+	rest.split_whitespace().map(ToOwned::to_owned).collect()
+}
+
+fn nice(pid: u32) -> i32 {
+	stat(pid)[19 - 3].parse().unwrap()
+}
+
+fn policy(pid: u32) -> i32 {
+	stat(pid)[41 - 3].parse().unwrap()
+}
+
+fn cpus_allowed(pid: u32) -> String {
+	fs::read_to_string(format!("/proc/{pid}/status"))
+		.unwrap()
+		.lines()
+		.find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+		.unwrap()
+		.trim()
+		.to_owned()
+}
+
+#[test]
+fn applied_at_spawn() -> Result<()> {
+	let mut child = CommandWrap::with_new("sleep", |command| {
+		command.arg("1");
+	})
+	.wrap(
+		Scheduling::new()
+			.nice(5)
+			.io_priority(IoPriority::BestEffort(7))
+			.affinity([0])
+			.policy(SchedPolicy::Batch),
+	)
+	.spawn()?;
+
+	let pid = child.id();
+	assert_eq!(nice(pid), 5);
+	assert_eq!(policy(pid), 3, "SCHED_BATCH");
+	assert_eq!(cpus_allowed(pid), "0");
+
+	child.kill()?;
+	Ok(())
+}
+
+#[test]
+fn invalid_io_priority() -> Result<()> {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(Scheduling::new().io_priority(IoPriority::BestEffort(8)))
+		.spawn()
+		.unwrap_err();
+
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[test]
+fn change_while_running() -> Result<()> {
+	let mut child = CommandWrap::with_new("sleep", |command| {
+		command.arg("1");
+	})
+	.wrap(Scheduling::new().nice(1))
+	.spawn()?;
+
+	let pid = child.id();
+	let scheduling = child.find_layer::<SchedulingChild>().unwrap();
+	scheduling.set_nice(3)?;
+	scheduling.set_affinity(&[0])?;
+	assert_eq!(nice(pid), 3);
+	assert_eq!(cpus_allowed(pid), "0");
+
+	assert!(
+		scheduling.set_group_nice(4).is_err(),
+		"not in its own group"
+	);
+
+	child.kill()?;
+	Ok(())
+}
+
+#[test]
+fn change_group() -> Result<()> {
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg("sleep 1 & sleep 1; wait");
+	})
+	.wrap(ProcessGroup::leader())
+	.wrap(Scheduling::new())
+	.spawn()?;
+	sleep(DIE_TIME);
+
+	let pid = child.id();
+	child
+		.find_layer::<SchedulingChild>()
+		.unwrap()
+		.set_group_nice(6)?;
+
+	let members: Vec<u32> = fs::read_dir("/proc")?
+		.filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+		.filter(|&p: &u32| {
+			fs::read_to_string(format!("/proc/{p}/stat")).is_ok()
+				&& stat(p)[5 - 3] == pid.to_string()
+		})
+		.collect();
+	assert!(members.len() >= 3, "{members:?}");
+	for member in members {
+		assert_eq!(nice(member), 6);
+	}
+
+	child.kill()?;
+	Ok(())
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "tokio1")]
+
+use super::prelude::*;
+use process_wrap::tokio as tk;
+use tokio::runtime::Runtime;
+
+fn spawn_tokio(
+	runtime: &Runtime,
+	program: &str,
+	init: impl FnOnce(&mut tokio::process::Command),
+) -> Result<Box<dyn tk::ChildWrapper>> {
+	runtime.block_on(async {
+		tk::CommandWrap::with_new(program, init)
+			.wrap(tk::ProcessGroup::leader())
+			.spawn()
+	})
+}
+
+#[test]
+fn wait_twice() -> Result<()> {
+	let runtime = Runtime::new()?;
+	let child = spawn_tokio(&runtime, "true", |_| {})?;
+	let mut child = TokioChild::new(child, runtime.handle().clone())?;
+
+	assert!(child.wait()?.success(), "wait() one");
+	assert!(child.wait()?.success(), "wait() two");
+	Ok(())
+}
+
+#[test]
+fn signal_through_chain() -> Result<()> {
+	let runtime = Runtime::new()?;
+	let child = spawn_tokio(&runtime, "yes", |command| {
+		command.stdout(Stdio::null());
+	})?;
+	let mut child = TokioChild::new(child, runtime.handle().clone())?;
+	assert!(
+		(child.tokio_child() as &dyn std::any::Any).is::<tk::ProcessGroupChild>(),
+		"chain preserved"
+	);
+
+	child.signal(Signal::SIGTERM as _)?;
+	let status = child.wait()?;
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+	Ok(())
+}
+
+#[test]
+fn read_stdout() -> Result<()> {
+	let runtime = Runtime::new()?;
+	let child = spawn_tokio(&runtime, "echo", |command| {
+		command.arg("hello").stdout(Stdio::piped());
+	})?;
+	let child = TokioChild::new(child, runtime.handle().clone())?;
+
+	let output = Box::new(child).wait_with_output()?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"hello\n");
+	Ok(())
+}
+
+#[test]
+fn into_tokio() -> Result<()> {
+	let runtime = Runtime::new()?;
+	let child = spawn_tokio(&runtime, "cat", |command| {
+		command.stdin(Stdio::piped()).stdout(Stdio::piped());
+	})?;
+	let mut child = TokioChild::new(child, runtime.handle().clone())?;
+	if let Some(mut din) = child.stdin().take() {
+		din.write_all(b"hello")?;
+	}
+
+	let mut child = child.into_tokio()?;
+	let output = runtime.block_on(async {
+		use tokio::io::AsyncReadExt;
+		let mut output = String::new();
+		if let Some(mut out) = child.stdout().take() {
+			out.read_to_string(&mut output).await?;
+		}
+		child.wait().await?;
+		Ok::<_, std::io::Error>(output)
+	})?;
+
+	assert_eq!(output, "hello");
+	Ok(())
+}
+
+#[test]
+#[should_panic = "the bottom of the wrapper chain isn't a Child"]
+fn no_inner_child() {
+	let runtime = Runtime::new().unwrap();
+	let child = spawn_tokio(&runtime, "true", |_| {}).unwrap();
+	let child: Box<dyn ChildWrapper> =
+		Box::new(TokioChild::new(child, runtime.handle().clone()).unwrap());
+	child.inner_child();
+}
+
+#[derive(Debug)]
+struct InlineChild(std::process::Child);
+
+impl ChildWrapper for InlineChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&self.0
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut self.0
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		Box::new((*self).0)
+	}
+}
+
+#[test]
+fn inline_inner_child() -> Result<()> {
+	// the Child may be at the same address as the wrapper, which mustn't be taken for the bottom
+	let child = std::process::Command::new("true").spawn()?;
+	let mut child: Box<dyn ChildWrapper> = Box::new(InlineChild(child));
+
+	assert_eq!(child.inner_child().id(), child.id());
+	assert_eq!(unsafe { child.inner_child_mut() }.id(), child.id());
+	assert_eq!(child.layers().0.len(), 2);
+
+	child.wait()?;
+	let _ = unsafe { child.into_inner_child() };
+	Ok(())
+}
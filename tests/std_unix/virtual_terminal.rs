@@ -0,0 +1,168 @@
+#![cfg(feature = "virtual-terminal")]
+
+use std::io::ErrorKind;
+
+use super::prelude::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn spawn(script: &str, terminal: VirtualTerminal) -> Result<Box<dyn ChildWrapper>> {
+	CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg(script);
+	})
+	.wrap(terminal)
+	.spawn()
+}
+
+fn terminal(child: &mut Box<dyn ChildWrapper>) -> &mut VirtualTerminalChild {
+	child.find_layer_mut::<VirtualTerminalChild>().unwrap()
+}
+
+#[test]
+fn screen_text_and_cursor() -> Result<()> {
+	let mut child = spawn("printf 'hello\\nworld'", VirtualTerminal::default())?;
+	let screen = terminal(&mut child).wait_for_screen(|s| s.text().contains("world"), TIMEOUT)?;
+	assert_eq!(screen.text(), "hello\nworld");
+	assert_eq!(screen.cursor(), (1, 5));
+	assert_eq!(screen.size(), (80, 24));
+	assert!(child.wait()?.success());
+	assert_eq!(terminal(&mut child).screen_text(), "hello\nworld");
+	Ok(())
+}
+
+#[test]
+fn cursor_movement_and_erase() -> Result<()> {
+	let mut child = spawn(
+		r"printf 'garbage\033[2J\033[3;5Hxy\033[1;1Habcdef\033[1;4H\033[K'",
+		VirtualTerminal::default(),
+	)?;
+	child.wait()?;
+	let terminal = terminal(&mut child);
+	terminal.wait_for_screen(|s| s.text().contains("xy"), TIMEOUT)?;
+	assert_eq!(terminal.screen_text(), "abc\n\n    xy");
+	assert_eq!(terminal.cursor(), (0, 3));
+	Ok(())
+}
+
+#[test]
+fn cell_attributes() -> Result<()> {
+	let mut child = spawn(
+		r"printf '\033[1;31mR\033[0;4;38;5;200mU\033[48;2;1;2;3mB\033[0mN'",
+		VirtualTerminal::default(),
+	)?;
+	child.wait()?;
+	let terminal = terminal(&mut child);
+	terminal.wait_for_screen(|s| s.text() == "RUBN", TIMEOUT)?;
+
+	let red = terminal.cell(0, 0).unwrap();
+	assert_eq!(red.ch, 'R');
+	assert!(red.attrs.bold);
+	assert_eq!(red.attrs.fg, Color::Indexed(1));
+
+	let underlined = terminal.cell(0, 1).unwrap().attrs;
+	assert!(underlined.underline && !underlined.bold);
+	assert_eq!(underlined.fg, Color::Indexed(200));
+
+	let background = terminal.cell(0, 2).unwrap().attrs;
+	assert_eq!(background.bg, Color::Rgb(1, 2, 3));
+	assert_eq!(background.fg, Color::Indexed(200));
+
+	assert_eq!(terminal.cell(0, 3).unwrap().attrs, CellAttrs::default());
+	assert_eq!(terminal.cell(24, 0), None);
+	Ok(())
+}
+
+#[test]
+fn wrapping_and_scrolling() -> Result<()> {
+	let mut child = spawn(
+		"printf 0123456789abc; echo; seq 1 10",
+		VirtualTerminal::new(10, 5),
+	)?;
+	child.wait()?;
+	let terminal = terminal(&mut child);
+	let screen = terminal.wait_for_screen(|s| s.text().ends_with("10"), TIMEOUT)?;
+	assert_eq!(screen.text(), "7\n8\n9\n10");
+	assert_eq!(screen.cursor(), (4, 0));
+	Ok(())
+}
+
+#[test]
+fn alternate_screen() -> Result<()> {
+	let mut child = spawn(
+		r"printf 'main\033[?1049h\033[Halt'; read line; printf '\033[?1049l'; read line",
+		VirtualTerminal::default(),
+	)?;
+	let terminal = terminal(&mut child);
+	let screen = terminal.wait_for_screen(|s| s.alternate_screen(), TIMEOUT)?;
+	assert_eq!(screen.text(), "alt");
+
+	terminal.send("\n")?;
+	let screen = terminal.wait_for_screen(|s| !s.alternate_screen(), TIMEOUT)?;
+	assert_eq!(screen.text(), "main");
+	assert_eq!(screen.cursor(), (0, 4));
+
+	terminal.send("\n")?;
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn title() -> Result<()> {
+	let mut child = spawn(
+		r"printf '\033]0;my title\007shown'",
+		VirtualTerminal::default(),
+	)?;
+	let screen = terminal(&mut child).wait_for_screen(|s| s.text() == "shown", TIMEOUT)?;
+	assert_eq!(screen.title(), "my title");
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn answers_cursor_position_query() -> Result<()> {
+	let mut child = spawn(
+		r"stty -icanon -echo min 6; printf 'ab\033[6n'; head -c 6 | od -An -c",
+		VirtualTerminal::default(),
+	)?;
+	let screen = terminal(&mut child).wait_for_screen(|s| s.text().contains('R'), TIMEOUT)?;
+	let answer: String = screen.text().split_whitespace().skip(1).collect();
+	assert_eq!(answer, "033[1;3R");
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn resize() -> Result<()> {
+	let mut child = spawn("read line; stty size", VirtualTerminal::default())?;
+	let terminal = terminal(&mut child);
+	terminal.resize(100, 30)?;
+	terminal.send("\n")?;
+	let screen = terminal.wait_for_screen(|s| s.text().contains("30 100"), TIMEOUT)?;
+	assert_eq!(screen.size(), (100, 30));
+	child.wait()?;
+	Ok(())
+}
+
+#[test]
+fn timed_out() -> Result<()> {
+	let mut child = spawn("echo waiting; sleep 10", VirtualTerminal::default())?;
+	let err = terminal(&mut child)
+		.wait_for_screen(|s| s.text().contains("done"), DIE_TIME)
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::TimedOut);
+	assert!(err.to_string().contains("waiting"), "{err}");
+	child.kill()?;
+	Ok(())
+}
+
+#[test]
+fn output_ended() -> Result<()> {
+	let mut child = spawn("echo finished", VirtualTerminal::default())?;
+	child.wait()?;
+	let err = terminal(&mut child)
+		.wait_for_screen(|s| s.text().contains("done"), TIMEOUT)
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+	assert!(err.to_string().contains("finished"), "{err}");
+	Ok(())
+}
@@ -17,5 +17,6 @@ mod into_inner_write_stdin;
 mod kill_and_try_wait;
 mod try_wait_after_die;
 mod wait_after_die;
+mod wait_outcome;
 mod wait_twice;
 mod wait_with_output;
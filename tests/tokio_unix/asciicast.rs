@@ -0,0 +1,125 @@
+#![cfg(feature = "asciicast")]
+
+use std::{fs, io::ErrorKind, path::Path};
+
+use super::prelude::*;
+
+fn replayed(path: &Path) -> Result<String> {
+	let mut output = Vec::new();
+	replay_asciicast(
+		std::io::BufReader::new(fs::File::open(path)?),
+		&mut output,
+		f64::INFINITY,
+	)?;
+	Ok(String::from_utf8(output).unwrap())
+}
+
+#[tokio::test]
+async fn records_output() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let path = dir.path().join("session.cast");
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command
+			.arg("-c")
+			.arg(r#"test -t 1 && echo hello; printf '\033[1mété\n'"#)
+			.env("TERM", "xterm-test");
+	})
+	.wrap(Asciicast::new(&path).size(100, 30).title("a \"test\""))
+	.spawn()?;
+	assert!(child.wait().await?.success());
+
+	let recording = fs::read_to_string(&path)?;
+	let header = recording.lines().next().unwrap();
+	assert!(
+		header.starts_with(r#"{"version": 2, "width": 100, "height": 30, "#),
+		"{header}"
+	);
+	assert!(header.contains(r#""title": "a \"test\"""#), "{header}");
+	assert!(header.contains(r#""TERM": "xterm-test""#), "{header}");
+	assert!(recording.contains(r#"\u001b[1m"#), "{recording}");
+
+	assert_eq!(replayed(&path)?, "hello\r\n\u{1b}[1mété\r\n");
+	Ok(())
+}
+
+#[tokio::test]
+async fn input_and_resize() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let path = dir.path().join("session.cast");
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command
+			.arg("-c")
+			.arg(r#"read line; stty size; echo "got $line""#);
+	})
+	.wrap(Asciicast::new(&path).record_input())
+	.spawn()?;
+	let session = child.find_layer_mut::<AsciicastChild>().unwrap();
+	session.resize(132, 43)?;
+	session.send("abc\n")?;
+	assert!(child.wait().await?.success());
+
+	let recording = fs::read_to_string(&path)?;
+	assert!(recording.contains(r#", "r", "132x43"]"#), "{recording}");
+	assert!(recording.contains(r#", "i", "abc\n"]"#), "{recording}");
+	// the input is echoed by the terminal, so it's also in the output
+	assert_eq!(replayed(&path)?, "abc\r\n43 132\r\ngot abc\r\n");
+	Ok(())
+}
+
+#[tokio::test]
+async fn input_not_recorded_by_default() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let path = dir.path().join("session.cast");
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg("read secret");
+	})
+	.wrap(Asciicast::new(&path))
+	.spawn()?;
+	child
+		.find_layer_mut::<AsciicastChild>()
+		.unwrap()
+		.send("hunter2\n")?;
+	assert!(child.wait().await?.success());
+
+	let recording = fs::read_to_string(&path)?;
+	assert!(!recording.contains(r#", "i", "#), "{recording}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn conflicts_with_process_group() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(ProcessGroup::leader())
+		.wrap(Asciicast::new(dir.path().join("session.cast")))
+		.spawn()
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[cfg(feature = "virtual-terminal")]
+#[tokio::test]
+async fn conflicts_with_virtual_terminal() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(VirtualTerminal::new(80, 24))
+		.wrap(Asciicast::new(dir.path().join("session.cast")))
+		.spawn()
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[cfg(feature = "expect")]
+#[tokio::test]
+async fn conflicts_with_expect() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(Asciicast::new(dir.path().join("session.cast")))
+		.wrap(Expect::new())
+		.spawn()
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	Ok(())
+}
@@ -0,0 +1,100 @@
+use super::prelude::*;
+
+#[tokio::test]
+async fn describe() -> Result<()> {
+	let mut command = CommandWrap::with_new("echo", |command| {
+		command
+			.arg("hello")
+			.current_dir("/")
+			.env("FOO", "bar")
+			.env_remove("BAZ");
+	});
+	command.wrap(ProcessSession).wrap(ProcessGroup::leader());
+
+	let plan = command.describe();
+	assert_eq!(plan.program, "echo");
+	assert_eq!(plan.args, ["hello"]);
+	assert_eq!(plan.current_dir.as_deref(), Some("/".as_ref()));
+	assert_eq!(
+		plan.envs,
+		[("BAZ".into(), None), ("FOO".into(), Some("bar".into()))]
+	);
+
+	assert_eq!(plan.wrappers.len(), 2);
+	assert!(
+		plan.wrappers[0].name.ends_with("::ProcessSession"),
+		"{plan}"
+	);
+	assert!(plan.wrappers[1].name.ends_with("::ProcessGroup"), "{plan}");
+	assert_eq!(plan.wrappers[0].config, "ProcessSession");
+
+	let printed = plan.to_string();
+	assert!(printed.starts_with(r#""echo" "hello""#), "{printed}");
+	assert!(printed.contains("env: FOO=\"bar\""), "{printed}");
+	assert!(printed.contains("env: unset BAZ"), "{printed}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn layers() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+
+	let layers = child.layers();
+	assert_eq!(layers.0.len(), 2);
+	assert!(layers.0[0].ends_with("::ProcessGroupChild"), "{layers}");
+	assert!(layers.0[1].ends_with("::Child"), "{layers}");
+	assert!(
+		layers.to_string().contains("ProcessGroupChild > "),
+		"{layers}"
+	);
+
+	child.wait().await?;
+	Ok(())
+}
+
+#[derive(Debug)]
+struct Marker;
+
+#[derive(Debug)]
+struct MarkerChild(Box<dyn ChildWrapper>);
+
+impl CommandWrapper for Marker {
+	fn wrap_child(
+		&mut self,
+		inner: Box<dyn ChildWrapper>,
+		_core: &CommandWrap,
+	) -> Result<Box<dyn ChildWrapper>> {
+		Ok(Box::new(MarkerChild(inner)))
+	}
+}
+
+impl ChildWrapper for MarkerChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&*self.0
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut *self.0
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		self.0
+	}
+}
+
+#[tokio::test]
+async fn layers_below_process_group() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(Marker)
+		.wrap(ProcessGroup::leader())
+		.spawn()?;
+
+	let layers = child.layers();
+	assert_eq!(layers.0.len(), 3, "{layers}");
+	assert!(layers.0[0].ends_with("::ProcessGroupChild"), "{layers}");
+	assert!(layers.0[1].ends_with("::MarkerChild"), "{layers}");
+	assert!(layers.0[2].ends_with("::Child"), "{layers}");
+
+	child.wait().await?;
+	Ok(())
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "process-group")]
+
+use std::{io::ErrorKind, time::Instant};
+
+use super::prelude::*;
+
+/// Spawn a group leader which exits straight away, leaving a `sleep` behind in its group.
+fn leave_behind(sleep: &str, group: ProcessGroup) -> Result<Box<dyn ChildWrapper>> {
+	CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg(format!("sleep {sleep} &"));
+	})
+	.wrap(group)
+	.spawn()
+}
+
+#[tokio::test]
+async fn default_returns_with_leader() -> Result<()> {
+	let mut child = leave_behind("10", ProcessGroup::leader())?;
+	let start = Instant::now();
+	assert!(child.wait().await?.success());
+	assert!(start.elapsed() < Duration::from_secs(5));
+
+	child.signal(Signal::SIGKILL as _)?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn waits_for_members() -> Result<()> {
+	let start = Instant::now();
+	let mut child = leave_behind(
+		"0.3",
+		ProcessGroup::leader().wait_for_empty(Duration::from_secs(5)),
+	)?;
+	assert!(child.wait().await?.success());
+	assert!(start.elapsed() >= Duration::from_millis(300));
+	Ok(())
+}
+
+#[tokio::test]
+async fn timed_out() -> Result<()> {
+	let mut child = leave_behind("10", ProcessGroup::leader().wait_for_empty(DIE_TIME))?;
+	let err = child.wait().await.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+	// the status is kept, but only returned once the group is empty
+	assert_eq!(child.try_wait()?, None);
+	assert_eq!(child.wait().await.unwrap_err().kind(), ErrorKind::TimedOut);
+
+	child.signal(Signal::SIGKILL as _)?;
+	assert!(child.wait().await?.success());
+	Ok(())
+}
+
+#[tokio::test]
+async fn try_wait_for_members() -> Result<()> {
+	const POLL: Duration = Duration::from_millis(20);
+
+	let start = Instant::now();
+	let mut child = leave_behind(
+		"0.3",
+		ProcessGroup::leader().wait_for_empty(Duration::from_secs(5)),
+	)?;
+	let status = loop {
+		if let Some(status) = child.try_wait()? {
+			break status;
+		}
+		assert!(start.elapsed() < Duration::from_secs(5));
+		tokio::time::sleep(POLL).await;
+	};
+	assert!(status.success());
+	assert!(start.elapsed() >= Duration::from_millis(300));
+	assert_eq!(child.try_wait()?, Some(status));
+	Ok(())
+}
+
+#[tokio::test]
+async fn kills_stragglers() -> Result<()> {
+	let mut child = leave_behind(
+		"10",
+		ProcessGroup::leader()
+			.wait_for_empty(DIE_TIME)
+			.kill_stragglers(),
+	)?;
+	let start = Instant::now();
+	assert!(child.wait().await?.success());
+	assert!(start.elapsed() < Duration::from_secs(5));
+	Ok(())
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "foreground")]
+
+use super::prelude::*;
+
+#[tokio::test]
+async fn wait_twice() -> Result<()> {
+	let mut child = CommandWrap::with_new("echo", |command| {
+		command.stdout(Stdio::null());
+	})
+	.wrap(ProcessGroup::leader())
+	.wrap(Foreground::default())
+	.spawn()?;
+
+	let status = child.wait().await?;
+	assert!(status.success());
+
+	let status = child.wait().await?;
+	assert!(status.success());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn stop_and_resume() -> Result<()> {
+	let mut child = CommandWrap::with_new("yes", |command| {
+		command.stdout(Stdio::null());
+	})
+	.wrap(ProcessGroup::leader())
+	.wrap(Foreground::default())
+	.spawn()?;
+	let fg: &mut ForegroundChild = (child.as_mut() as &mut dyn std::any::Any)
+		.downcast_mut()
+		.expect("outermost wrapper is ForegroundChild");
+
+	fg.signal(Signal::SIGSTOP as _)?;
+	assert_eq!(
+		fg.wait_event().await?,
+		ForegroundEvent::Stopped(Signal::SIGSTOP as _)
+	);
+
+	fg.resume_background()?;
+	sleep(DIE_TIME).await;
+	assert!(fg.try_wait()?.is_none(), "running again after resume");
+
+	fg.signal(Signal::SIGTERM as _)?;
+	let ForegroundEvent::Exited(status) = fg.wait_event().await? else {
+		panic!("expected exit event");
+	};
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as _));
+
+	assert_eq!(fg.wait_event().await?, ForegroundEvent::Exited(status));
+
+	Ok(())
+}
+
+#[cfg(feature = "process-session")]
+#[tokio::test]
+async fn conflicts_with_process_session() {
+	let err = CommandWrap::with_new("true", |_| {})
+		.wrap(ProcessSession)
+		.wrap(Foreground::default())
+		.spawn()
+		.unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "kill-on-drop")]
+
+use std::fs;
+
+use super::prelude::*;
+
+/// Spawn a shell which starts a background `sleep`, and get the PIDs of both.
+async fn spawn_with_grandchild(
+	script: &str,
+	command: &mut CommandWrap,
+) -> Result<(Box<dyn ChildWrapper>, i32, i32)> {
+	command
+		.command_mut()
+		.arg("-c")
+		.arg(format!("{script}; sleep 10 & echo $!; wait"))
+		.stdout(Stdio::piped());
+	let mut child = command.spawn()?;
+
+	let mut line = String::new();
+	BufReader::new(child.stdout().as_mut().unwrap())
+		.read_line(&mut line)
+		.await?;
+	let grandchild = line.trim().parse().unwrap();
+	let pid = child.id().unwrap() as i32;
+	Ok((child, pid, grandchild))
+}
+
+/// Whether a process has exited, even if it's a zombie that init hasn't reaped yet.
+fn exited(pid: i32) -> bool {
+	fs::read_to_string(format!("/proc/{pid}/stat")).map_or(true, |stat| {
+		stat.rsplit_once(") ")
+			.is_some_and(|(_, rest)| rest.starts_with('Z'))
+	})
+}
+
+#[tokio::test]
+async fn single_child() -> Result<()> {
+	let child = CommandWrap::with_new("sleep", |command| {
+		command.arg("10");
+	})
+	.wrap(KillOnDrop)
+	.spawn()?;
+	let pid = child.id().unwrap() as i32;
+	assert!(pid_alive(pid));
+
+	drop(child);
+	sleep(DIE_TIME).await;
+	assert!(!pid_alive(pid), "killed and reaped");
+	Ok(())
+}
+
+#[tokio::test]
+async fn whole_group() -> Result<()> {
+	let mut command = CommandWrap::with_new("sh", |_| {});
+	command.wrap(ProcessGroup::leader()).wrap(KillOnDrop);
+	let (child, pid, grandchild) = spawn_with_grandchild("true", &mut command).await?;
+	assert!(!exited(grandchild));
+
+	drop(child);
+	sleep(DIE_TIME).await;
+	assert!(!pid_alive(pid), "leader killed and reaped");
+	assert!(exited(grandchild), "rest of the group killed");
+	Ok(())
+}
+
+#[tokio::test]
+async fn graceful() -> Result<()> {
+	let dir = tempfile::tempdir()?;
+	let marker = dir.path().join("terminated");
+
+	let mut command = CommandWrap::with_new("sh", |_| {});
+	command
+		.wrap(ProcessSession)
+		.wrap(KillOnDrop::graceful(Duration::from_secs(5)));
+	let (child, pid, grandchild) = spawn_with_grandchild(
+		&format!("trap 'echo > {}; kill $!; exit' TERM", marker.display()),
+		&mut command,
+	)
+	.await?;
+
+	drop(child);
+	sleep(DIE_TIME * 5).await;
+	assert!(fs::exists(&marker)?, "got SIGTERM first");
+	assert!(!pid_alive(pid));
+	assert!(exited(grandchild));
+	Ok(())
+}
+
+#[tokio::test]
+async fn graceful_then_kill() -> Result<()> {
+	let mut command = CommandWrap::with_new("sh", |_| {});
+	command
+		.wrap(ProcessGroup::leader())
+		.wrap(KillOnDrop::graceful(DIE_TIME * 3).signal(Signal::SIGUSR1 as _));
+	let (child, pid, grandchild) = spawn_with_grandchild("trap '' USR1", &mut command).await?;
+
+	drop(child);
+	sleep(DIE_TIME).await;
+	assert!(pid_alive(pid), "still in the grace period");
+
+	sleep(DIE_TIME * 4).await;
+	assert!(!pid_alive(pid), "killed after the grace period");
+	assert!(exited(grandchild));
+	Ok(())
+}
+
+#[tokio::test]
+async fn not_after_wait() -> Result<()> {
+	let mut child = CommandWrap::with_new("true", |_| {})
+		.wrap(ProcessGroup::leader())
+		.wrap(KillOnDrop)
+		.spawn()?;
+	assert!(child.wait().await?.success());
+	drop(child);
+	Ok(())
+}
@@ -22,15 +22,38 @@ mod prelude {
 	}
 }
 
+mod asciicast;
+mod constraints;
+mod describe;
+mod empty_group;
+mod environment;
+mod expect;
+mod find_layer;
+mod foreground;
 mod id_same_as_inner;
+mod init;
 mod inner_read_stdout;
 mod into_inner_write_stdin;
 mod kill_and_try_wait;
+mod kill_on_drop;
+mod kill_tree;
 mod multiproc_linux;
+mod observe;
+mod oom_score_adj;
+mod output_events;
+mod pty;
+mod reaped_members;
+mod scheduling;
 mod signals;
+mod std_child;
+mod trace_output;
 mod try_wait_after_die;
 mod try_wait_twice_after_sigterm;
+mod virtual_terminal;
 mod wait_after_die;
+mod wait_outcome;
 mod wait_twice;
 mod wait_twice_after_sigterm;
+mod wait_with_input_output;
 mod wait_with_output;
+mod wrap_access;
@@ -0,0 +1,226 @@
+#![cfg(feature = "pty")]
+
+use std::{
+	io::{PipeReader, Read, Write, pipe},
+	os::fd::{AsFd, AsRawFd, OwnedFd},
+	process::ExitStatus,
+	sync::LazyLock,
+	thread,
+};
+
+use nix::{
+	libc,
+	pty::{Winsize, openpty},
+	sys::{
+		signal::kill,
+		termios::{LocalFlags, tcgetattr},
+	},
+	unistd::getpid,
+};
+
+use futures::lock::Mutex;
+
+use super::prelude::*;
+
+// only one session can run at a time, and tests run concurrently
+static SESSION: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+async fn run(
+	script: &str,
+	input: impl Into<OwnedFd>,
+	wrap: impl FnOnce(&mut CommandWrap),
+) -> Result<(Result<ExitStatus>, String)> {
+	let _session = SESSION.lock().await;
+
+	let (mut output_reader, output_writer) = pipe()?;
+	let output = thread::spawn(move || -> Result<String> {
+		let mut output = String::new();
+		output_reader.read_to_string(&mut output)?;
+		Ok(output)
+	});
+
+	let mut command = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg(script);
+	});
+	wrap(&mut command);
+	let status = PtySession::new()
+		.with_input(input)
+		.with_output(output_writer)
+		.run(command)
+		.await;
+	Ok((status, output.join().unwrap()?))
+}
+
+fn piped(input: &str) -> Result<PipeReader> {
+	let (reader, mut writer) = pipe()?;
+	writer.write_all(input.as_bytes())?;
+	Ok(reader)
+}
+
+fn terminal(rows: u16, cols: u16) -> Result<(OwnedFd, OwnedFd)> {
+	let size = Winsize {
+		ws_row: rows,
+		ws_col: cols,
+		ws_xpixel: 0,
+		ws_ypixel: 0,
+	};
+	let pty = openpty(&size, None)?;
+	Ok((pty.master, pty.slave))
+}
+
+#[tokio::test]
+async fn relays_both_ways() -> Result<()> {
+	let (status, output) = run(r#"read line; echo "got $line""#, piped("hello\n")?, |_| {}).await?;
+	assert!(status?.success());
+	assert!(output.contains("got hello"), "{output:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn on_a_terminal() -> Result<()> {
+	let (status, output) = run(
+		"test -t 0 && test -t 1 && test -t 2 && : < /dev/tty && echo on a terminal",
+		piped("")?,
+		|_| {},
+	)
+	.await?;
+	assert!(status?.success());
+	assert!(output.contains("on a terminal"), "{output:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn end_of_input() -> Result<()> {
+	let (status, output) = run("cat; echo done", piped("one\ntwo\n")?, |_| {}).await?;
+	assert!(status?.success());
+	assert!(output.contains("done"), "{output:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn exit_status() -> Result<()> {
+	let (status, _) = run("exit 3", piped("")?, |_| {}).await?;
+	assert_eq!(status?.code(), Some(3));
+	Ok(())
+}
+
+#[tokio::test]
+async fn window_size() -> Result<()> {
+	let (_master, tty) = terminal(30, 100)?;
+	let (status, output) = run("stty size", tty, |_| {}).await?;
+	assert!(status?.success());
+	assert!(output.contains("30 100"), "{output:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn window_size_change() -> Result<()> {
+	let (_master, tty) = terminal(30, 100)?;
+	let resize = tty.try_clone()?;
+	let resizer = thread::spawn(move || {
+		thread::sleep(DIE_TIME * 2);
+		let size = Winsize {
+			ws_row: 40,
+			ws_col: 120,
+			ws_xpixel: 0,
+			ws_ypixel: 0,
+		};
+		unsafe { libc::ioctl(resize.as_raw_fd(), libc::TIOCSWINSZ as _, &size) };
+		// the kernel signals the terminal's foreground group, which we aren't in here
+		kill(getpid(), Signal::SIGWINCH).unwrap();
+	});
+
+	let (status, output) = run("sleep 0.5; stty size", tty, |_| {}).await?;
+	resizer.join().unwrap();
+	assert!(status?.success());
+	assert!(output.contains("40 120"), "{output:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn restores_terminal() -> Result<()> {
+	let (_master, tty) = terminal(30, 100)?;
+	let check = tty.try_clone()?;
+	assert!(
+		tcgetattr(check.as_fd())?
+			.local_flags
+			.contains(LocalFlags::ICANON)
+	);
+
+	let (status, output) = run("stty -a < /dev/tty", tty, |_| {}).await?;
+	assert!(status?.success());
+	// the session copies the input's settings, before putting it in raw mode
+	assert!(!output.contains("-icanon"), "{output:?}");
+	assert!(
+		tcgetattr(check.as_fd())?
+			.local_flags
+			.contains(LocalFlags::ICANON)
+	);
+	Ok(())
+}
+
+#[tokio::test]
+async fn with_process_session() -> Result<()> {
+	let (status, output) = run("echo in session", piped("")?, |command| {
+		command.wrap(ProcessSession);
+	})
+	.await?;
+	assert!(status?.success());
+	assert!(output.contains("in session"), "{output:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn conflicts_with_process_group() -> Result<()> {
+	let (status, _) = run("true", piped("")?, |command| {
+		command.wrap(ProcessGroup::leader());
+	})
+	.await?;
+	assert_eq!(status.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+	Ok(())
+}
+
+#[tokio::test]
+async fn restores_terminal_when_dropped() -> Result<()> {
+	let _session = SESSION.lock().await;
+	let (_master, tty) = terminal(30, 100)?;
+	let check = tty.try_clone()?;
+	let (mut output_reader, output_writer) = pipe()?;
+
+	// without KillOnDrop, the command outlives the session, until the relay closes the terminal
+	let command = CommandWrap::with_new("sleep", |command| {
+		command.arg("10");
+	});
+	let session = PtySession::new()
+		.with_input(tty)
+		.with_output(output_writer)
+		.run(command);
+	assert!(tokio::time::timeout(DIE_TIME, session).await.is_err());
+
+	// the relay lets go of the output once it has stopped, which is after restoring the terminal
+	let stopped = tokio::task::spawn_blocking(move || {
+		let mut output = Vec::new();
+		output_reader.read_to_end(&mut output)
+	});
+	tokio::time::timeout(DIE_TIME * 10, stopped)
+		.await
+		.expect("relay should stop")
+		.unwrap()?;
+	assert!(
+		tcgetattr(check.as_fd())?
+			.local_flags
+			.contains(LocalFlags::ICANON)
+	);
+	Ok(())
+}
+
+#[cfg(feature = "expect")]
+#[tokio::test]
+async fn conflicts_with_expect() -> Result<()> {
+	let (status, _) = run("true", piped("")?, |command| {
+		command.wrap(Expect::new());
+	})
+	.await?;
+	assert_eq!(status.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+	Ok(())
+}
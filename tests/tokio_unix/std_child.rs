@@ -0,0 +1,108 @@
+#![cfg(feature = "std")]
+
+use super::prelude::*;
+use process_wrap::std as st;
+
+#[tokio::test]
+async fn wait_twice() -> Result<()> {
+	let child = st::CommandWrap::with_new("true", |_| {})
+		.wrap(st::ProcessGroup::leader())
+		.spawn()?;
+	let mut child = StdChild::new(child)?;
+
+	assert!(child.wait().await?.success(), "wait() one");
+	assert!(child.wait().await?.success(), "wait() two");
+	Ok(())
+}
+
+#[tokio::test]
+async fn kill_after_cancelled_wait() -> Result<()> {
+	let child = st::CommandWrap::with_new("yes", |command| {
+		command.stdout(Stdio::null());
+	})
+	.wrap(st::ProcessGroup::leader())
+	.spawn()?;
+	let mut child = StdChild::new(child)?;
+	assert!(
+		child
+			.std_child()
+			.is_some_and(|c| (c as &dyn std::any::Any).is::<st::ProcessGroupChild>()),
+		"chain preserved"
+	);
+
+	assert!(
+		tokio::time::timeout(DIE_TIME, child.wait()).await.is_err(),
+		"still running"
+	);
+
+	child.signal(Signal::SIGTERM as _)?;
+	let status = child.wait().await?;
+	assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+	Ok(())
+}
+
+#[tokio::test]
+async fn read_stdout() -> Result<()> {
+	let child = st::CommandWrap::with_new("echo", |command| {
+		command.arg("hello").stdout(Stdio::piped());
+	})
+	.spawn()?;
+	let child = StdChild::new(child)?;
+
+	let output = Box::into_pin(Box::new(child).wait_with_output()).await?;
+	assert!(output.status.success());
+	assert_eq!(output.stdout, b"hello\n");
+	Ok(())
+}
+
+#[tokio::test]
+async fn into_std() -> Result<()> {
+	let child = st::CommandWrap::with_new("cat", |command| {
+		command.stdin(Stdio::piped()).stdout(Stdio::piped());
+	})
+	.spawn()?;
+	let mut child = StdChild::new(child)?;
+	if let Some(mut din) = child.stdin().take() {
+		din.write_all(b"hello").await?;
+	}
+
+	let mut child = child.into_std().await?;
+	let mut output = String::new();
+	if let Some(mut out) = child.stdout().take() {
+		std::io::Read::read_to_string(&mut out, &mut output)?;
+	}
+	child.wait()?;
+
+	assert_eq!(output, "hello");
+	Ok(())
+}
+
+#[derive(Debug)]
+struct InlineChild(tokio::process::Child);
+
+impl ChildWrapper for InlineChild {
+	fn inner(&self) -> &dyn ChildWrapper {
+		&self.0
+	}
+	fn inner_mut(&mut self) -> &mut dyn ChildWrapper {
+		&mut self.0
+	}
+	fn into_inner(self: Box<Self>) -> Box<dyn ChildWrapper> {
+		Box::new((*self).0)
+	}
+}
+
+#[tokio::test]
+async fn inline_inner_child() -> Result<()> {
+	// the Child may be at the same address as the wrapper, which mustn't be taken for the bottom
+	let child = tokio::process::Command::new("true").spawn()?;
+	let mut child: Box<dyn ChildWrapper> = Box::new(InlineChild(child));
+
+	assert_eq!(child.inner_child().id(), child.id());
+	assert_eq!(unsafe { child.inner_child_mut() }.id(), child.id());
+	assert_eq!(child.layers().0.len(), 2);
+
+	child.wait().await?;
+	let _ = unsafe { child.into_inner_child() };
+	Ok(())
+}
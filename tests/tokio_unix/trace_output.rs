@@ -0,0 +1,136 @@
+#![cfg(feature = "trace-output")]
+
+use std::{
+	fmt::Debug,
+	sync::{
+		Arc, Mutex,
+		atomic::{AtomicU64, Ordering},
+	},
+};
+
+use tracing::{
+	Event, Level, Metadata, Subscriber,
+	field::{Field, Visit},
+	span::{Attributes, Id, Record},
+};
+
+use super::prelude::*;
+
+/// Records events and span fields as strings.
+#[derive(Clone, Debug, Default)]
+struct Recorder {
+	lines: Arc<Mutex<Vec<String>>>,
+	next_id: Arc<AtomicU64>,
+}
+
+struct Fields(String);
+
+impl Visit for Fields {
+	fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+		if field.name() == "message" {
+			self.0.push_str(&format!(" {value:?}"));
+		} else {
+			self.0.push_str(&format!(" {}={value:?}", field.name()));
+		}
+	}
+}
+
+impl Recorder {
+	fn lines(&self) -> Vec<String> {
+		self.lines.lock().unwrap().clone()
+	}
+
+	fn push(&self, kind: &str, visit: impl FnOnce(&mut Fields)) {
+		let mut fields = Fields(kind.into());
+		visit(&mut fields);
+		self.lines.lock().unwrap().push(fields.0);
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		metadata.target() == "process_wrap::output"
+	}
+
+	fn new_span(&self, span: &Attributes<'_>) -> Id {
+		self.push("span", |f| span.record(f));
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	fn record(&self, _span: &Id, values: &Record<'_>) {
+		self.push("record", |f| values.record(f));
+	}
+
+	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		assert_eq!(*event.metadata().level(), Level::DEBUG);
+		self.push("event", |f| event.record(f));
+	}
+
+	fn enter(&self, _span: &Id) {}
+
+	fn exit(&self, _span: &Id) {}
+}
+
+#[tokio::test]
+async fn lines_in_span() -> Result<()> {
+	let recorder = Recorder::default();
+	let _guard = tracing::subscriber::set_default(recorder.clone());
+
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command
+			.arg("-c")
+			.arg("echo one; echo two >&2; printf three");
+	})
+	.wrap(TraceOutput::new(Level::DEBUG))
+	.spawn()?;
+
+	let pid = child.id().unwrap();
+	assert!(child.wait().await?.success());
+
+	let mut lines = recorder.lines();
+	assert!(
+		lines[0].starts_with(&format!(
+			"span program=sh args=[\"-c\", \"echo one; echo two >&2; printf three\"] pid={pid} pgid="
+		)),
+		"{lines:?}"
+	);
+
+	// stdout and stderr are read concurrently so may interleave in any order
+	let mut output = lines.drain(1..4).collect::<Vec<_>>();
+	output.sort();
+	assert_eq!(
+		output,
+		vec![
+			"event one stream=\"stdout\"",
+			"event three stream=\"stdout\"",
+			"event two stream=\"stderr\"",
+		]
+	);
+
+	assert_eq!(
+		&lines[1..],
+		&[
+			"record status=exit status: 0",
+			"event exited status=exit status: 0"
+		]
+	);
+	Ok(())
+}
+
+#[tokio::test]
+async fn non_utf8() -> Result<()> {
+	let recorder = Recorder::default();
+	let _guard = tracing::subscriber::set_default(recorder.clone());
+
+	let mut child = CommandWrap::with_new("printf", |command| {
+		command.arg("a\\377b\\r\\n");
+	})
+	.wrap(TraceOutput::new(Level::DEBUG))
+	.spawn()?;
+	child.wait().await?;
+
+	assert_eq!(recorder.lines()[1], "event a\u{FFFD}b stream=\"stdout\"");
+	Ok(())
+}
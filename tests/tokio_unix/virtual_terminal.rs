@@ -0,0 +1,112 @@
+#![cfg(feature = "virtual-terminal")]
+
+use std::io::ErrorKind;
+
+use super::prelude::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn spawn(script: &str, terminal: VirtualTerminal) -> Result<Box<dyn ChildWrapper>> {
+	CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg(script);
+	})
+	.wrap(terminal)
+	.spawn()
+}
+
+fn terminal(child: &mut Box<dyn ChildWrapper>) -> &mut VirtualTerminalChild {
+	child.find_layer_mut::<VirtualTerminalChild>().unwrap()
+}
+
+#[tokio::test]
+async fn screen_text_and_cursor() -> Result<()> {
+	let mut child = spawn("printf 'hello\\nworld'", VirtualTerminal::default())?;
+	let screen = terminal(&mut child)
+		.wait_for_screen(|s| s.text().contains("world"), TIMEOUT)
+		.await?;
+	assert_eq!(screen.text(), "hello\nworld");
+	assert_eq!(screen.cursor(), (1, 5));
+	assert_eq!(screen.size(), (80, 24));
+	assert!(child.wait().await?.success());
+	assert_eq!(terminal(&mut child).screen_text(), "hello\nworld");
+	Ok(())
+}
+
+#[tokio::test]
+async fn alternate_screen() -> Result<()> {
+	let mut child = spawn(
+		r"printf 'main\033[?1049h\033[Halt'; read line; printf '\033[?1049l'; read line",
+		VirtualTerminal::default(),
+	)?;
+	let terminal = terminal(&mut child);
+	let screen = terminal
+		.wait_for_screen(|s| s.alternate_screen(), TIMEOUT)
+		.await?;
+	assert_eq!(screen.text(), "alt");
+
+	terminal.send("\n")?;
+	let screen = terminal
+		.wait_for_screen(|s| !s.alternate_screen(), TIMEOUT)
+		.await?;
+	assert_eq!(screen.text(), "main");
+	assert_eq!(screen.cursor(), (0, 4));
+
+	terminal.send("\n")?;
+	child.wait().await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn answers_cursor_position_query() -> Result<()> {
+	let mut child = spawn(
+		r"stty -icanon -echo min 6; printf 'ab\033[6n'; head -c 6 | od -An -c",
+		VirtualTerminal::default(),
+	)?;
+	let screen = terminal(&mut child)
+		.wait_for_screen(|s| s.text().contains('R'), TIMEOUT)
+		.await?;
+	let answer: String = screen.text().split_whitespace().skip(1).collect();
+	assert_eq!(answer, "033[1;3R");
+	child.wait().await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn resize() -> Result<()> {
+	let mut child = spawn("read line; stty size", VirtualTerminal::default())?;
+	let terminal = terminal(&mut child);
+	terminal.resize(100, 30)?;
+	terminal.send("\n")?;
+	let screen = terminal
+		.wait_for_screen(|s| s.text().contains("30 100"), TIMEOUT)
+		.await?;
+	assert_eq!(screen.size(), (100, 30));
+	child.wait().await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn timed_out() -> Result<()> {
+	let mut child = spawn("echo waiting; sleep 10", VirtualTerminal::default())?;
+	let err = terminal(&mut child)
+		.wait_for_screen(|s| s.text().contains("done"), DIE_TIME)
+		.await
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::TimedOut);
+	assert!(err.to_string().contains("waiting"), "{err}");
+	Box::into_pin(child.kill()).await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn output_ended() -> Result<()> {
+	let mut child = spawn("echo finished", VirtualTerminal::default())?;
+	child.wait().await?;
+	let err = terminal(&mut child)
+		.wait_for_screen(|s| s.text().contains("done"), TIMEOUT)
+		.await
+		.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+	assert!(err.to_string().contains("finished"), "{err}");
+	Ok(())
+}
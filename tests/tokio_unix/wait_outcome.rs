@@ -0,0 +1,76 @@
+use super::prelude::*;
+
+#[tokio::test]
+async fn nowrap_exit_code() -> Result<()> {
+	let mut child = CommandWrap::with_new("sh", |command| {
+		command.arg("-c").arg("exit 3");
+	})
+	.spawn()?;
+
+	let outcome = child.wait_outcome().await?;
+	assert_eq!(outcome, ExitOutcome::Exited(3));
+	assert_eq!(outcome.code(), 3);
+	assert!(!outcome.success());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn nowrap_signaled() -> Result<()> {
+	let mut child = CommandWrap::with_new("yes", |command| {
+		command.stdout(Stdio::null());
+	})
+	.spawn()?;
+
+	child.signal(Signal::SIGTERM as _)?;
+	let outcome = child.wait_outcome().await?;
+	assert_eq!(
+		outcome,
+		ExitOutcome::Signaled {
+			signal: Signal::SIGTERM as _,
+			core_dumped: false
+		}
+	);
+	assert_eq!(outcome.code(), 128 + Signal::SIGTERM as i32);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn process_group_killed() -> Result<()> {
+	let mut child = CommandWrap::with_new("yes", |command| {
+		command.stdout(Stdio::null());
+	})
+	.wrap(ProcessGroup::leader())
+	.spawn()?;
+
+	child.start_kill()?;
+	let outcome = child.wait_outcome().await?;
+	assert!(
+		matches!(outcome, ExitOutcome::KilledByWrapper { .. }),
+		"killed by wrapper: {outcome:?}"
+	);
+	assert_eq!(outcome.signal(), Some(Signal::SIGKILL as _));
+	assert_eq!(outcome.code(), 128 + Signal::SIGKILL as i32);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn process_group_signaled() -> Result<()> {
+	let mut child = CommandWrap::with_new("yes", |command| {
+		command.stdout(Stdio::null());
+	})
+	.wrap(ProcessGroup::leader())
+	.spawn()?;
+
+	child.signal(Signal::SIGTERM as _)?;
+	let outcome = child.wait_outcome().await?;
+	assert_eq!(outcome.signal(), Some(Signal::SIGTERM as _));
+	assert!(
+		matches!(outcome, ExitOutcome::Signaled { .. }),
+		"signaled, not killed by wrapper: {outcome:?}"
+	);
+
+	Ok(())
+}
@@ -0,0 +1,98 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::process::Command;
+
+use super::prelude::*;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+#[derive(Debug)]
+struct First(Log, &'static str);
+
+#[derive(Debug)]
+struct Second(Log, &'static str);
+
+impl CommandWrapper for First {
+	fn pre_spawn(&mut self, _command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		self.0.lock().unwrap().push(format!("first {}", self.1));
+		Ok(())
+	}
+}
+
+impl CommandWrapper for Second {
+	fn pre_spawn(&mut self, _command: &mut Command, _core: &CommandWrap) -> Result<()> {
+		self.0.lock().unwrap().push(format!("second {}", self.1));
+		Ok(())
+	}
+}
+
+async fn spawn(mut command: CommandWrap) -> Result<()> {
+	assert!(command.spawn()?.wait().await?.success());
+	Ok(())
+}
+
+#[tokio::test]
+async fn get_wrap_mut() -> Result<()> {
+	let log = Log::default();
+	let mut command = CommandWrap::with_new("true", |_| {});
+	command.wrap(First(log.clone(), "a"));
+
+	command.get_wrap_mut::<First>().unwrap().1 = "b";
+	assert!(command.get_wrap_mut::<Second>().is_none());
+
+	spawn(command).await?;
+	assert_eq!(*log.lock().unwrap(), ["first b"]);
+	Ok(())
+}
+
+#[tokio::test]
+async fn unwrap() -> Result<()> {
+	let log = Log::default();
+	let mut command = CommandWrap::with_new("true", |_| {});
+	command
+		.wrap(First(log.clone(), "a"))
+		.wrap(ProcessGroup::leader())
+		.wrap(Second(log.clone(), "a"));
+
+	assert_eq!(command.unwrap::<First>().unwrap().1, "a");
+	assert!(command.unwrap::<First>().is_none());
+	assert!(!command.has_wrap::<First>());
+	assert!(command.has_wrap::<ProcessGroup>());
+
+	spawn(command).await?;
+	assert_eq!(*log.lock().unwrap(), ["second a"]);
+	Ok(())
+}
+
+#[tokio::test]
+async fn replace() -> Result<()> {
+	let log = Log::default();
+	let mut command = CommandWrap::with_new("true", |_| {});
+	command.wrap(First(log.clone(), "a"));
+
+	assert!(command.replace(Second(log.clone(), "a")).is_none());
+	assert_eq!(command.replace(First(log.clone(), "b")).unwrap().1, "a");
+
+	spawn(command).await?;
+	assert_eq!(*log.lock().unwrap(), ["first b", "second a"]);
+	Ok(())
+}
+
+#[tokio::test]
+async fn wrap_boxed() -> Result<()> {
+	let log = Log::default();
+	let wrappers: Vec<Box<dyn CommandWrapper>> = vec![
+		Box::new(Second(log.clone(), "a")),
+		Box::new(First(log.clone(), "a")),
+	];
+
+	let mut command = CommandWrap::with_new("true", |_| {});
+	for wrapper in wrappers {
+		command.wrap_boxed(wrapper);
+	}
+	assert_eq!(command.get_wrap::<First>().unwrap().1, "a");
+
+	spawn(command).await?;
+	assert_eq!(*log.lock().unwrap(), ["second a", "first a"]);
+	Ok(())
+}
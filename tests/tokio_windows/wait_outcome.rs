@@ -0,0 +1,33 @@
+use super::prelude::*;
+
+#[tokio::test]
+async fn nowrap_exit_code() -> Result<()> {
+	let mut child = CommandWrap::with_new("powershell.exe", |command| {
+		command.arg("/C").arg("exit 3");
+	})
+	.spawn()?;
+
+	let outcome = child.wait_outcome().await?;
+	assert_eq!(outcome, ExitOutcome::Exited(3));
+	assert_eq!(outcome.code(), 3);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn job_object_killed() -> Result<()> {
+	let mut child = CommandWrap::with_new("powershell.exe", |command| {
+		command.arg("/C").arg("pause").stdout(Stdio::null());
+	})
+	.wrap(JobObject)
+	.spawn()?;
+
+	child.start_kill()?;
+	let outcome = child.wait_outcome().await?;
+	assert!(
+		matches!(outcome, ExitOutcome::KilledByWrapper { .. }),
+		"killed by wrapper: {outcome:?}"
+	);
+
+	Ok(())
+}